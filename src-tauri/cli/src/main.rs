@@ -0,0 +1,627 @@
+// my-todo 命令行：直接读写桌面端使用的 todo.db，无需打开窗口
+//
+// 构建：cargo build --release -p my-todo-cli
+
+use chrono::{Local, NaiveDate, TimeZone};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use my_todo_core::{Store, Task, TaskRepository};
+
+#[derive(Parser)]
+#[command(name = "my-todo", version, about = "Manage My TODO tasks from the terminal")]
+struct Cli {
+    /// 数据库文件（默认使用桌面端应用数据目录下的 todo.db）
+    #[arg(long, global = true, env = "MY_TODO_DB")]
+    db: Option<PathBuf>,
+
+    /// 以 JSON 输出
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// 添加任务
+    Add {
+        title: String,
+        #[arg(short, long)]
+        description: Option<String>,
+        /// 清单 ID
+        #[arg(short, long, default_value = "all")]
+        list: String,
+        #[arg(short, long, value_enum, default_value_t = Priority::None)]
+        priority: Priority,
+        /// 截止日期（YYYY-MM-DD、today 或 tomorrow）
+        #[arg(long)]
+        due: Option<String>,
+    },
+    /// 列出任务（默认不含已完成）
+    List(Filter),
+    /// 标记任务完成（支持 ID 前缀）
+    Done { ids: Vec<String> },
+    /// 修改任务
+    Edit {
+        id: String,
+        #[arg(long)]
+        title: Option<String>,
+        #[arg(short, long)]
+        description: Option<String>,
+        #[arg(short, long)]
+        list: Option<String>,
+        #[arg(short, long, value_enum)]
+        priority: Option<Priority>,
+        #[arg(short, long, value_enum)]
+        status: Option<Status>,
+        /// 截止日期（YYYY-MM-DD、today 或 tomorrow）
+        #[arg(long, conflicts_with = "clear_due")]
+        due: Option<String>,
+        /// 清除截止日期
+        #[arg(long)]
+        clear_due: bool,
+    },
+    /// 删除任务（支持 ID 前缀）
+    Rm { ids: Vec<String> },
+    /// 全文搜索标题、描述与子任务标题（与桌面端一致，按相关度排序）
+    Search {
+        query: String,
+        #[command(flatten)]
+        filter: Filter,
+    },
+    /// 导出全部任务
+    Export {
+        #[arg(short, long, value_enum, default_value_t = ExportFormat::Json)]
+        format: ExportFormat,
+        /// 输出文件（默认输出到标准输出）
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Args)]
+struct Filter {
+    /// 清单 ID
+    #[arg(short, long)]
+    list: Option<String>,
+    #[arg(short, long, value_enum)]
+    priority: Option<Priority>,
+    #[arg(short, long, value_enum)]
+    status: Option<Status>,
+    /// 截止日期筛选
+    #[arg(long, value_enum)]
+    due: Option<DueFilter>,
+    /// 包含已完成任务
+    #[arg(short, long)]
+    all: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Priority {
+    None,
+    Low,
+    Medium,
+    High,
+}
+
+impl Priority {
+    fn as_str(self) -> &'static str {
+        match self {
+            Priority::None => "none",
+            Priority::Low => "low",
+            Priority::Medium => "medium",
+            Priority::High => "high",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Status {
+    Todo,
+    InProgress,
+    Done,
+}
+
+impl Status {
+    fn as_str(self) -> &'static str {
+        match self {
+            Status::Todo => "todo",
+            Status::InProgress => "in_progress",
+            Status::Done => "done",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum DueFilter {
+    /// 已过期
+    Overdue,
+    /// 今天到期
+    Today,
+    /// 7 天内到期
+    Week,
+    /// 没有截止日期
+    None,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ExportFormat {
+    Json,
+    Csv,
+}
+
+fn main() {
+    let cli = Cli::parse();
+    if let Err(e) = run(cli) {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run(cli: Cli) -> Result<(), String> {
+    let db_path = match cli.db {
+        Some(path) => path,
+        None => default_db_path()?,
+    };
+    let store = Store::open(db_path)?;
+
+    match cli.command {
+        Command::Add {
+            title,
+            description,
+            list,
+            priority,
+            due,
+        } => {
+            let mut partial = serde_json::json!({
+                "title": title,
+                "description": description,
+                "listId": list,
+                "priority": priority.as_str(),
+            });
+            if let Some(due) = due {
+                partial["dueDate"] = parse_due(&due)?.into();
+            }
+            let task = my_todo_core::task_from_partial(partial)?;
+            store.save_task(&task)?;
+            print_tasks(&[task], cli.json)
+        }
+        Command::List(filter) => {
+            let tasks = store.tasks()?;
+            let tasks: Vec<Task> = tasks.into_iter().filter(|t| filter.matches(t)).collect();
+            print_tasks(&tasks, cli.json)
+        }
+        Command::Done { ids } => {
+            let tasks = complete_each(&store, &ids)?;
+            print_tasks(&tasks, cli.json)
+        }
+        Command::Edit {
+            id,
+            title,
+            description,
+            list,
+            priority,
+            status,
+            due,
+            clear_due,
+        } => {
+            let mut changes = serde_json::Map::new();
+            if let Some(title) = title {
+                changes.insert("title".into(), title.into());
+            }
+            if let Some(description) = description {
+                changes.insert("description".into(), description.into());
+            }
+            if let Some(list) = list {
+                changes.insert("listId".into(), list.into());
+            }
+            if let Some(priority) = priority {
+                changes.insert("priority".into(), priority.as_str().into());
+            }
+            if let Some(status) = status {
+                changes.insert("status".into(), status.as_str().into());
+                changes.insert("completed".into(), (status == Status::Done).into());
+            }
+            if let Some(due) = due {
+                changes.insert("dueDate".into(), parse_due(&due)?.into());
+            }
+            if clear_due {
+                changes.insert("dueDate".into(), serde_json::Value::Null);
+            }
+            if changes.is_empty() {
+                return Err("Nothing to change".to_string());
+            }
+            let tasks = update_each(&store, &[id], &serde_json::Value::Object(changes))?;
+            print_tasks(&tasks, cli.json)
+        }
+        Command::Rm { ids } => {
+            let tasks = store.tasks()?;
+            let selected = resolve_each(&tasks, &ids)?;
+            for task in &selected {
+                // 祖先也在其中时随祖先一起删除
+                if !has_ancestor_in(&tasks, &selected, task) {
+                    store.delete_task_tree(&task.id)?;
+                }
+            }
+            if !cli.json {
+                for task in &selected {
+                    println!("Deleted {} {}", short_id(&task.id), task.title);
+                }
+            }
+            Ok(())
+        }
+        Command::Search { query, filter } => {
+            let tasks = search(&store, &query, &filter)?;
+            print_tasks(&tasks, cli.json)
+        }
+        Command::Export { format, output } => {
+            let tasks = store.tasks()?;
+            let data = match format {
+                ExportFormat::Json => serde_json::to_string_pretty(&tasks)
+                    .map_err(|e| format!("Failed to serialize tasks: {}", e))?,
+                ExportFormat::Csv => to_csv(&tasks),
+            };
+            match output {
+                Some(path) => {
+                    std::fs::write(&path, data).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+                    eprintln!("Exported {} tasks to {}", tasks.len(), path.display());
+                }
+                None => println!("{}", data.trim_end()),
+            }
+            Ok(())
+        }
+    }
+}
+
+/// 与桌面端相同的应用数据目录（<系统数据目录>/<应用标识>）
+fn default_db_path() -> Result<PathBuf, String> {
+    let dir = dirs::data_dir()
+        .ok_or_else(|| "Failed to locate the system data directory, use --db".to_string())?
+        .join(my_todo_core::APP_IDENTIFIER);
+    Ok(my_todo_core::db_path_in(&dir))
+}
+
+impl Filter {
+    /// 回收站中的任务不显示；未指定 --all 或 --status 时隐藏已完成任务
+    fn matches(&self, task: &Task) -> bool {
+        !task.deleted
+            && (self.all || self.status.is_some() || !task.completed)
+            && self.list.as_ref().is_none_or(|l| &task.list_id == l)
+            && self.priority.is_none_or(|p| task.priority == p.as_str())
+            && self.status.is_none_or(|s| task.status == s.as_str())
+            && self.due.is_none_or(|d| matches_due(task, d))
+    }
+}
+
+fn matches_due(task: &Task, filter: DueFilter) -> bool {
+    let due = task
+        .due_date
+        .as_deref()
+        .and_then(|d| chrono::DateTime::parse_from_rfc3339(d).ok())
+        .map(|d| d.with_timezone(&Local).date_naive());
+    let today = Local::now().date_naive();
+    match (filter, due) {
+        (DueFilter::None, due) => due.is_none(),
+        (_, None) => false,
+        (DueFilter::Overdue, Some(due)) => due < today && !task.completed,
+        (DueFilter::Today, Some(due)) => due == today,
+        (DueFilter::Week, Some(due)) => due >= today && due < today + chrono::Duration::days(7),
+    }
+}
+
+/// 解析截止日期为本地零点的 RFC 3339 时间
+fn parse_due(value: &str) -> Result<String, String> {
+    let today = Local::now().date_naive();
+    let date = match value {
+        "today" => today,
+        "tomorrow" => today + chrono::Duration::days(1),
+        _ => NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .map_err(|_| format!("Invalid due date: {} (expected YYYY-MM-DD)", value))?,
+    };
+    let midnight = date.and_hms_opt(0, 0, 0).expect("midnight is valid");
+    Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        .map(|d| d.to_utc().to_rfc3339())
+        .ok_or_else(|| format!("Invalid local date: {}", value))
+}
+
+/// 按完整 ID 或唯一前缀查找任务
+fn resolve_task<'a>(tasks: &'a [Task], id: &str) -> Result<&'a Task, String> {
+    if let Some(task) = tasks.iter().find(|t| t.id == id) {
+        return Ok(task);
+    }
+    let matches: Vec<&Task> = tasks.iter().filter(|t| t.id.starts_with(id)).collect();
+    match matches.as_slice() {
+        [task] => Ok(task),
+        [] => Err(format!("Task not found: {}", id)),
+        _ => Err(format!("Ambiguous task id: {}", id)),
+    }
+}
+
+/// 全文搜索后按筛选条件过滤（保持相关度顺序）
+fn search(store: &Store, query: &str, filter: &Filter) -> Result<Vec<Task>, String> {
+    Ok(store
+        .search_tasks(query, usize::MAX)?
+        .into_iter()
+        .map(|r| r.task)
+        .filter(|t| filter.matches(t))
+        .collect())
+}
+
+/// 解析多个 ID（去掉重复的任务）
+fn resolve_each<'a>(tasks: &'a [Task], ids: &[String]) -> Result<Vec<&'a Task>, String> {
+    if ids.is_empty() {
+        return Err("No task id given".to_string());
+    }
+    let mut resolved: Vec<&Task> = Vec::with_capacity(ids.len());
+    for id in ids {
+        let task = resolve_task(tasks, id)?;
+        if !resolved.iter().any(|t| t.id == task.id) {
+            resolved.push(task);
+        }
+    }
+    Ok(resolved)
+}
+
+/// 任务的某个祖先是否在 selected 中
+fn has_ancestor_in(tasks: &[Task], selected: &[&Task], task: &Task) -> bool {
+    let mut seen = HashSet::new();
+    let mut next = task.parent_id.as_deref();
+    while let Some(id) = next {
+        if selected.iter().any(|t| t.id == id) {
+            return true;
+        }
+        if !seen.insert(id) {
+            return false;
+        }
+        next = tasks.iter().find(|t| t.id == id).and_then(|t| t.parent_id.as_deref());
+    }
+    false
+}
+
+fn update_each(store: &Store, ids: &[String], changes: &serde_json::Value) -> Result<Vec<Task>, String> {
+    if ids.is_empty() {
+        return Err("No task id given".to_string());
+    }
+    let tasks = store.tasks()?;
+    let mut updated = Vec::with_capacity(ids.len());
+    for id in ids {
+        let task = my_todo_core::update_task_from_partial(resolve_task(&tasks, id)?, changes.clone())?;
+        store.save_task(&task)?;
+        updated.push(task);
+    }
+    Ok(updated)
+}
+
+/// 完成任务（与界面一致，连同未完成的后代一起完成），返回指定的任务
+fn complete_each(store: &Store, ids: &[String]) -> Result<Vec<Task>, String> {
+    if ids.is_empty() {
+        return Err("No task id given".to_string());
+    }
+    let tasks = store.tasks()?;
+    let mut completed = Vec::with_capacity(ids.len());
+    for id in ids {
+        let id = &resolve_task(&tasks, id)?.id;
+        completed.extend(store.set_task_completed(id, true)?.into_iter().take(1));
+    }
+    Ok(completed)
+}
+
+// ========== 输出 ==========
+
+fn print_tasks(tasks: &[Task], json: bool) -> Result<(), String> {
+    if json {
+        let data = serde_json::to_string_pretty(tasks).map_err(|e| format!("Failed to serialize tasks: {}", e))?;
+        println!("{}", data);
+        return Ok(());
+    }
+    if tasks.is_empty() {
+        println!("No tasks");
+        return Ok(());
+    }
+
+    let header = ["ID", "", "PRIORITY", "DUE", "LIST", "TITLE"];
+    let rows: Vec<[String; 6]> = tasks
+        .iter()
+        .map(|t| {
+            [
+                short_id(&t.id).to_string(),
+                if t.completed { "✔" } else { " " }.to_string(),
+                t.priority.clone(),
+                t.due_date.as_deref().map(format_due).unwrap_or_default(),
+                t.list_id.clone(),
+                t.title.clone(),
+            ]
+        })
+        .collect();
+
+    let mut widths = header.map(display_width);
+    for row in &rows {
+        for (w, cell) in widths.iter_mut().zip(row) {
+            *w = (*w).max(display_width(cell));
+        }
+    }
+    print_row(&header.map(String::from), &widths);
+    for row in &rows {
+        print_row(row, &widths);
+    }
+    Ok(())
+}
+
+fn print_row(cells: &[String; 6], widths: &[usize; 6]) {
+    let line: Vec<String> = cells
+        .iter()
+        .zip(widths)
+        .enumerate()
+        .map(|(i, (cell, width))| {
+            // 最后一列不补齐空格
+            if i == cells.len() - 1 {
+                cell.clone()
+            } else {
+                format!("{}{}", cell, " ".repeat(width - display_width(cell)))
+            }
+        })
+        .collect();
+    println!("{}", line.join("  "));
+}
+
+fn format_due(value: &str) -> String {
+    chrono::DateTime::parse_from_rfc3339(value)
+        .map(|d| d.with_timezone(&Local).format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|_| value.to_string())
+}
+
+fn short_id(id: &str) -> &str {
+    id.get(..8).unwrap_or(id)
+}
+
+/// 终端显示宽度（中日韩等全角字符按 2 列计算）
+fn display_width(s: &str) -> usize {
+    s.chars()
+        .map(|c| match c as u32 {
+            0x1100..=0x115F | 0x2E80..=0xA4CF | 0xAC00..=0xD7A3 | 0xF900..=0xFAFF | 0xFE30..=0xFE4F
+            | 0xFF00..=0xFF60 | 0xFFE0..=0xFFE6 => 2,
+            _ => 1,
+        })
+        .sum()
+}
+
+/// 标签以 JSON 数组保存（`[{ id, name, color }]`），只取名称
+fn tag_names(task: &Task) -> Vec<String> {
+    serde_json::from_str::<Vec<serde_json::Value>>(&task.tags)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|t| t.get("name").and_then(|n| n.as_str()).map(String::from))
+        .collect()
+}
+
+fn to_csv(tasks: &[Task]) -> String {
+    let escape = |s: &str| {
+        if s.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", s.replace('"', "\"\""))
+        } else {
+            s.to_string()
+        }
+    };
+    let mut out = String::from("id,title,description,completed,priority,status,list_id,tags,due_date,created_at,updated_at\n");
+    for t in tasks {
+        let fields = [
+            t.id.clone(),
+            t.title.clone(),
+            t.description.clone().unwrap_or_default(),
+            t.completed.to_string(),
+            t.priority.clone(),
+            t.status.clone(),
+            t.list_id.clone(),
+            tag_names(t).join(";"),
+            t.due_date.clone().unwrap_or_default(),
+            t.created_at.clone(),
+            t.updated_at.clone(),
+        ];
+        let line: Vec<String> = fields.iter().map(|f| escape(f)).collect();
+        out.push_str(&line.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cli(db: &std::path::Path, args: &[&str]) -> Cli {
+        let db = db.to_str().unwrap();
+        Cli::try_parse_from(["my-todo", "--db", db, "--json"].iter().chain(args)).unwrap()
+    }
+
+    fn titles(store: &Store) -> Vec<String> {
+        let mut titles: Vec<String> = store.tasks().unwrap().into_iter().map(|t| t.title).collect();
+        titles.sort();
+        titles
+    }
+
+    #[test]
+    fn parses_arguments() {
+        let parsed = Cli::try_parse_from(["my-todo", "add", "Buy milk", "-p", "high", "--due", "today", "-l", "work"]).unwrap();
+        match parsed.command {
+            Command::Add { title, priority, due, list, .. } => {
+                assert_eq!(title, "Buy milk");
+                assert_eq!(priority.as_str(), "high");
+                assert_eq!(due.as_deref(), Some("today"));
+                assert_eq!(list, "work");
+            }
+            _ => panic!("expected add"),
+        }
+
+        let parsed = Cli::try_parse_from(["my-todo", "--json", "done", "abc", "def"]).unwrap();
+        assert!(parsed.json);
+        assert!(matches!(parsed.command, Command::Done { ids } if ids == ["abc", "def"]));
+
+        let parsed = Cli::try_parse_from(["my-todo", "list", "-s", "in-progress", "--due", "overdue"]).unwrap();
+        assert!(matches!(
+            parsed.command,
+            Command::List(Filter { status: Some(Status::InProgress), due: Some(DueFilter::Overdue), .. })
+        ));
+
+        // 无效的枚举值与互斥参数
+        assert!(Cli::try_parse_from(["my-todo", "add", "x", "-p", "urgent"]).is_err());
+        assert!(Cli::try_parse_from(["my-todo", "edit", "abc", "--due", "today", "--clear-due"]).is_err());
+        assert!(Cli::try_parse_from(["my-todo"]).is_err());
+    }
+
+    #[test]
+    fn add_done_and_rm_against_a_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("todo.db");
+
+        run(cli(&db, &["add", "Parent", "-p", "high"])).unwrap();
+        let store = Store::open(&db).unwrap();
+        let parent = store.tasks().unwrap().pop().unwrap();
+        assert_eq!(parent.priority, "high");
+        let child = my_todo_core::task_from_partial(serde_json::json!({ "title": "Child", "parentId": parent.id })).unwrap();
+        store.save_task(&child).unwrap();
+        run(cli(&db, &["add", "Other"])).unwrap();
+
+        // 按 ID 前缀完成，后代一并完成
+        run(cli(&db, &["done", &parent.id[..8]])).unwrap();
+        assert!(store.task(&parent.id).unwrap().unwrap().completed);
+        assert!(store.task(&child.id).unwrap().unwrap().completed);
+        assert!(run(cli(&db, &["done", "missing"])).unwrap_err().contains("Task not found"));
+
+        // 同时给出父任务与子任务（以及重复的 ID）时只删除一次
+        run(cli(&db, &["rm", &child.id, &parent.id, &parent.id])).unwrap();
+        assert_eq!(titles(&store), ["Other"]);
+    }
+
+    #[test]
+    fn search_uses_full_text_search() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = Store::open(dir.path().join("todo.db")).unwrap();
+        for (title, sub_tasks, completed) in [
+            ("Groceries", r#"[{"id":"s1","title":"oat milk","completed":false}]"#, false),
+            ("Milk the cows", "[]", true),
+            ("Unrelated", "[]", false),
+        ] {
+            let task = my_todo_core::task_from_partial(serde_json::json!({
+                "title": title,
+                "subTasks": sub_tasks,
+                "completed": completed,
+            }))
+            .unwrap();
+            store.save_task(&task).unwrap();
+        }
+
+        let titles = |args: &[&str]| {
+            let parsed = Cli::try_parse_from(["my-todo", "search"].iter().chain(args)).unwrap();
+            let Command::Search { query, filter } = parsed.command else { panic!("expected search") };
+            search(&store, &query, &filter).unwrap().into_iter().map(|t| t.title).collect::<Vec<_>>()
+        };
+        // 子任务标题同样参与搜索，默认隐藏已完成任务
+        assert_eq!(titles(&["milk"]), ["Groceries"]);
+        assert_eq!(titles(&["milk", "--all"]), ["Milk the cows", "Groceries"]);
+    }
+}
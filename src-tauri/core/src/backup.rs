@@ -0,0 +1,359 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::{Cursor, Read, Write};
+
+use crate::model::SCHEMA_VERSION;
+
+// 备份清单中记录的单个文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestFile {
+    pub name: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+// 完整备份的清单文件（manifest.json）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupManifest {
+    // 生成备份的应用版本
+    pub app_version: String,
+    // 数据库结构版本
+    pub schema_version: u32,
+    pub created_at: String,
+    pub task_count: i64,
+    pub list_count: i64,
+    pub files: Vec<ManifestFile>,
+}
+
+// 可单独恢复的备份内容
+pub const COMPONENT_DATABASE: &str = "database";
+pub const COMPONENT_NOTIFICATION_SETTINGS: &str = "notificationSettings";
+pub const COMPONENT_WEBDAV_SETTINGS: &str = "webdavSettings";
+
+// 完整备份 ZIP 中的文件名
+pub const DB_ENTRY: &str = "todo.db";
+pub const NOTIFICATION_SETTINGS_ENTRY: &str = "notification_settings.json";
+pub const WEBDAV_SETTINGS_ENTRY: &str = "webdav_settings.json";
+const MANIFEST_ENTRY: &str = "manifest.json";
+
+// 备份文件名前缀（列表与清理只处理此前缀的文件）
+pub const BACKUP_PREFIX: &str = "todo-backup-";
+
+// 备份目标中的单个备份文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupEntry {
+    pub name: String,
+    pub size: Option<u64>,
+    // 最后修改时间（RFC 3339，目标不提供时为空）
+    pub modified: Option<String>,
+}
+
+// 将各文件与生成的清单打包为完整备份 ZIP
+pub fn build_archive(
+    mut entries: Vec<(&str, Vec<u8>)>,
+    app_version: &str,
+    task_count: i64,
+    list_count: i64,
+) -> Result<Vec<u8>, String> {
+    let manifest = BackupManifest {
+        app_version: app_version.to_string(),
+        schema_version: SCHEMA_VERSION,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        task_count,
+        list_count,
+        files: entries
+            .iter()
+            .map(|(name, data)| ManifestFile {
+                name: name.to_string(),
+                size: data.len() as u64,
+                sha256: sha256_hex(data),
+            })
+            .collect(),
+    };
+    let manifest_json = serde_json::to_vec_pretty(&manifest)
+        .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+    entries.push((MANIFEST_ENTRY, manifest_json));
+    write_zip(&entries)
+}
+
+// 将文件写入 ZIP
+fn write_zip(entries: &[(&str, Vec<u8>)]) -> Result<Vec<u8>, String> {
+    let mut zip_buf = Cursor::new(Vec::<u8>::new());
+    {
+        let mut zip = zip::ZipWriter::new(&mut zip_buf);
+        let options = zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        for (name, data) in entries {
+            zip.start_file(*name, options)
+                .map_err(|e| format!("Failed to write zip: {}", e))?;
+            zip.write_all(data)
+                .map_err(|e| format!("Failed to write zip: {}", e))?;
+        }
+        zip.finish().map_err(|e| format!("Failed to finalize zip: {}", e))?;
+    }
+    Ok(zip_buf.into_inner())
+}
+
+// 读取并校验完整备份 ZIP 中选定的内容（components 为 COMPONENT_* 常量，为空时读取全部），返回 (文件名, 内容)；
+// 任一文件不符合清单时整体失败，避免部分恢复
+pub fn read_archive(bytes: Vec<u8>, components: Option<&[String]>) -> Result<Vec<(&'static str, Vec<u8>)>, String> {
+    let mut zip = zip::ZipArchive::new(Cursor::new(bytes)).map_err(|e| format!("Failed to open zip: {}", e))?;
+
+    let wants = |component: &str| match components {
+        Some(c) => c.iter().any(|x| x == component),
+        None => true,
+    };
+
+    // 旧版备份没有清单且只包含数据库；其他没有清单的备份无法校验，拒绝恢复
+    let manifest = match read_zip_entry(&mut zip, MANIFEST_ENTRY)? {
+        Some(data) => Some(
+            serde_json::from_slice::<BackupManifest>(&data)
+                .map_err(|e| format!("Failed to parse manifest: {}", e))?,
+        ),
+        None if zip.len() == 1 && zip.file_names().any(|name| name == DB_ENTRY) => None,
+        None => return Err(format!("{} not found in backup, backup may be corrupted", MANIFEST_ENTRY)),
+    };
+
+    let mut files: Vec<(&'static str, Vec<u8>)> = Vec::new();
+    for (component, entry) in [
+        (COMPONENT_DATABASE, DB_ENTRY),
+        (COMPONENT_NOTIFICATION_SETTINGS, NOTIFICATION_SETTINGS_ENTRY),
+        (COMPONENT_WEBDAV_SETTINGS, WEBDAV_SETTINGS_ENTRY),
+    ] {
+        if !wants(component) {
+            continue;
+        }
+        match read_zip_entry(&mut zip, entry)? {
+            Some(data) => files.push((entry, data)),
+            None if components.is_some() => {
+                return Err(format!("{} not found in backup", entry));
+            }
+            None => {}
+        }
+    }
+
+    if let Some(manifest) = &manifest {
+        if manifest.schema_version > SCHEMA_VERSION {
+            return Err(format!(
+                "Backup schema version {} is newer than supported version {}, please upgrade the app",
+                manifest.schema_version,
+                SCHEMA_VERSION
+            ));
+        }
+        for (entry, data) in &files {
+            let expected = manifest
+                .files
+                .iter()
+                .find(|f| f.name == *entry)
+                .ok_or_else(|| format!("{} is not listed in manifest", entry))?;
+            if expected.size != data.len() as u64 || expected.sha256 != sha256_hex(data) {
+                return Err(format!("Checksum mismatch for {}, backup may be corrupted", entry));
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+// 读取 ZIP 中的指定文件，不存在时返回 None
+fn read_zip_entry(
+    zip: &mut zip::ZipArchive<Cursor<Vec<u8>>>,
+    name: &str,
+) -> Result<Option<Vec<u8>>, String> {
+    let mut file = match zip.by_name(name) {
+        Ok(file) => file,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(format!("Failed to read {} from zip: {}", name, e)),
+    };
+    let mut buf = Vec::new();
+    file.read_to_end(&mut buf).map_err(|e| format!("Failed to read zip entry: {}", e))?;
+    Ok(Some(buf))
+}
+
+// 计算 SHA-256 十六进制摘要
+pub fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// 本应用生成的备份（忽略上传中的临时文件），按文件名（即时间戳）倒序排列
+pub fn backup_entries(entries: Vec<BackupEntry>) -> Vec<BackupEntry> {
+    let mut entries: Vec<BackupEntry> = entries
+        .into_iter()
+        .filter(|e| e.name.starts_with(BACKUP_PREFIX) && !is_partial_upload(&e.name))
+        .collect();
+    entries.sort_by(|a, b| b.name.cmp(&a.name));
+    entries
+}
+
+// 超出保留数量、需要清理的旧备份（entries 为 backup_entries 的结果，max 为 0 表示不限制）
+pub fn expired_backups(entries: &[BackupEntry], max: u32) -> &[BackupEntry] {
+    if max == 0 {
+        return &[];
+    }
+    entries.get(max as usize..).unwrap_or_default()
+}
+
+// 上传中的临时文件或探测文件（以 . 开头或以 .tmp 结尾）
+pub fn is_partial_upload(name: &str) -> bool {
+    name.starts_with('.') || name.ends_with(".tmp")
+}
+
+// 备份文件名只能是单个文件名，防止路径穿越
+pub fn validate_backup_name(filename: &str) -> Result<(), String> {
+    if filename.is_empty()
+        || filename.contains('/')
+        || filename.contains('\\')
+        || filename == "."
+        || filename == ".."
+    {
+        return Err(format!("Invalid backup filename: {}", filename));
+    }
+    Ok(())
+}
+
+// 提取指定元素的内容（忽略命名空间前缀，如 d:href、D:href、href），用于解析 WebDAV 与 S3 的列表响应
+pub fn xml_elements<'a>(xml: &'a str, local_name: &str) -> Vec<&'a str> {
+    let mut result = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        let tag_end = match rest.find('>') {
+            Some(i) => i,
+            None => break,
+        };
+        let tag = &rest[..tag_end];
+        if tag.starts_with('/') || tag.ends_with('/') {
+            continue;
+        }
+        let full_name = tag.split_whitespace().next().unwrap_or("");
+        let name = full_name.rsplit(':').next().unwrap_or("");
+        if !name.eq_ignore_ascii_case(local_name) {
+            continue;
+        }
+        let content = &rest[tag_end + 1..];
+        let closing = format!("</{}>", full_name);
+        if let Some(end) = content.find(&closing) {
+            result.push(&content[..end]);
+            rest = &content[end + closing.len()..];
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn archive() -> Vec<u8> {
+        build_archive(vec![(DB_ENTRY, b"db".to_vec()), (NOTIFICATION_SETTINGS_ENTRY, b"{}".to_vec())], "1.0.0", 2, 1).unwrap()
+    }
+
+    // 读取备份中的全部文件，替换 replace 中的文件后重新打包（不重新生成清单）
+    fn rewrite(bytes: Vec<u8>, replace: &[(&str, Option<Vec<u8>>)]) -> Vec<u8> {
+        let mut zip = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+        let names: Vec<String> = zip.file_names().map(str::to_string).collect();
+        let mut entries: Vec<(&str, Vec<u8>)> = Vec::new();
+        for name in &names {
+            match replace.iter().find(|(n, _)| n == name) {
+                Some((_, Some(data))) => entries.push((name, data.clone())),
+                Some((_, None)) => {}
+                None => entries.push((name, read_zip_entry(&mut zip, name).unwrap().unwrap())),
+            }
+        }
+        write_zip(&entries).unwrap()
+    }
+
+    #[test]
+    fn reads_selected_components() {
+        let files = read_archive(archive(), None).unwrap();
+        assert_eq!(files, vec![(DB_ENTRY, b"db".to_vec()), (NOTIFICATION_SETTINGS_ENTRY, b"{}".to_vec())]);
+
+        let components = vec![COMPONENT_DATABASE.to_string()];
+        assert_eq!(read_archive(archive(), Some(&components)).unwrap(), vec![(DB_ENTRY, b"db".to_vec())]);
+        let components = vec![COMPONENT_WEBDAV_SETTINGS.to_string()];
+        assert!(read_archive(archive(), Some(&components)).unwrap_err().contains("not found"));
+    }
+
+    #[test]
+    fn rejects_checksum_mismatch() {
+        let bytes = rewrite(archive(), &[(DB_ENTRY, Some(b"xx".to_vec()))]);
+        assert!(read_archive(bytes, None).unwrap_err().contains("Checksum mismatch"));
+        // 大小不同同样拒绝
+        let bytes = rewrite(archive(), &[(DB_ENTRY, Some(b"longer".to_vec()))]);
+        assert!(read_archive(bytes, None).unwrap_err().contains("Checksum mismatch"));
+    }
+
+    #[test]
+    fn rejects_missing_or_invalid_manifest() {
+        let bytes = rewrite(archive(), &[(MANIFEST_ENTRY, None)]);
+        assert!(read_archive(bytes, None).unwrap_err().contains("manifest.json not found"));
+        let bytes = rewrite(archive(), &[(MANIFEST_ENTRY, Some(b"not json".to_vec()))]);
+        assert!(read_archive(bytes, None).unwrap_err().contains("Failed to parse manifest"));
+    }
+
+    #[test]
+    fn rejects_newer_schema_version() {
+        let mut zip = zip::ZipArchive::new(Cursor::new(archive())).unwrap();
+        let data = read_zip_entry(&mut zip, MANIFEST_ENTRY).unwrap().unwrap();
+        let mut manifest: BackupManifest = serde_json::from_slice(&data).unwrap();
+        manifest.schema_version = SCHEMA_VERSION + 1;
+        let bytes = rewrite(archive(), &[(MANIFEST_ENTRY, Some(serde_json::to_vec(&manifest).unwrap()))]);
+        assert!(read_archive(bytes, None).unwrap_err().contains("please upgrade the app"));
+    }
+
+    #[test]
+    fn accepts_legacy_database_only_backup() {
+        let bytes = write_zip(&[(DB_ENTRY, b"db".to_vec())]).unwrap();
+        assert_eq!(read_archive(bytes, None).unwrap(), vec![(DB_ENTRY, b"db".to_vec())]);
+    }
+
+    fn entry(name: &str) -> BackupEntry {
+        BackupEntry { name: name.to_string(), size: None, modified: None }
+    }
+
+    fn names(entries: &[BackupEntry]) -> Vec<&str> {
+        entries.iter().map(|e| e.name.as_str()).collect()
+    }
+
+    #[test]
+    fn lists_and_prunes_backups() {
+        let entries = backup_entries(vec![
+            entry("todo-backup-2026-10-01.zip"),
+            entry("notes.txt"),
+            entry("todo-backup-2026-10-03.db.enc"),
+            entry(".todo-backup-2026-10-04.zip.tmp"),
+            entry("todo-backup-2026-10-05.zip.tmp"),
+            entry("todo-backup-2026-10-02.zip"),
+        ]);
+        assert_eq!(
+            names(&entries),
+            ["todo-backup-2026-10-03.db.enc", "todo-backup-2026-10-02.zip", "todo-backup-2026-10-01.zip"]
+        );
+
+        assert_eq!(names(expired_backups(&entries, 2)), ["todo-backup-2026-10-01.zip"]);
+        assert!(expired_backups(&entries, 3).is_empty());
+        assert!(expired_backups(&entries, 5).is_empty());
+        // max 为 0 表示不限制
+        assert!(expired_backups(&entries, 0).is_empty());
+    }
+
+    #[test]
+    fn rejects_paths_as_backup_names() {
+        assert!(validate_backup_name("todo-backup-1.zip").is_ok());
+        for name in ["", ".", "..", "../todo.db", "a/b.zip", "a\\b.zip"] {
+            assert!(validate_backup_name(name).is_err(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn finds_xml_elements_ignoring_namespaces() {
+        let xml = r#"<d:multistatus xmlns:d="DAV:"><d:response><d:href>/a.zip</d:href></d:response>
+            <D:response><D:href>/b.zip</D:href><D:resourcetype/></D:response><response><href>/c.zip</href></response></d:multistatus>"#;
+        assert_eq!(xml_elements(xml, "href"), ["/a.zip", "/b.zip", "/c.zip"]);
+        assert_eq!(xml_elements(xml, "response").len(), 3);
+        assert!(xml_elements(xml, "resourcetype").is_empty());
+        assert!(xml_elements("<Key>unterminated", "Key").is_empty());
+    }
+}
@@ -0,0 +1,126 @@
+use argon2::Argon2;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+
+// 加密备份文件头（用于识别加密备份及格式版本）
+const MAGIC: &[u8; 8] = b"MYTODOE1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+
+// 判断数据是否为加密备份
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+// 使用 Argon2id 从口令派生 256 位密钥
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Key, String> {
+    let mut key = Key::default();
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("Failed to derive key: {}", e))?;
+    Ok(key)
+}
+
+// 加密备份数据（格式：MAGIC | salt(16) | nonce(24) | 密文与认证标签）
+pub fn encrypt_backup(data: &[u8], passphrase: &str) -> Result<Vec<u8>, String> {
+    if passphrase.is_empty() {
+        return Err("Encryption passphrase is empty".to_string());
+    }
+
+    let mut salt = [0u8; SALT_LEN];
+    OsRng
+        .try_fill_bytes(&mut salt)
+        .map_err(|e| format!("Failed to generate salt: {}", e))?;
+    let key = derive_key(passphrase, &salt)?;
+
+    let cipher = XChaCha20Poly1305::new(&key);
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, data)
+        .map_err(|e| format!("Failed to encrypt backup: {}", e))?;
+
+    let mut out = Vec::with_capacity(MAGIC.len() + SALT_LEN + NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&salt);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+// 解密备份数据（口令错误或文件被篡改时返回明确错误）
+pub fn decrypt_backup(data: &[u8], passphrase: &str) -> Result<Vec<u8>, String> {
+    if !is_encrypted(data) {
+        return Err("Backup is not encrypted".to_string());
+    }
+    let header_len = MAGIC.len() + SALT_LEN + NONCE_LEN;
+    if data.len() < header_len {
+        return Err("Encrypted backup is truncated".to_string());
+    }
+
+    let salt = &data[MAGIC.len()..MAGIC.len() + SALT_LEN];
+    let nonce = XNonce::from_slice(&data[MAGIC.len() + SALT_LEN..header_len]);
+    let key = derive_key(passphrase, salt)?;
+
+    let cipher = XChaCha20Poly1305::new(&key);
+    cipher
+        .decrypt(nonce, &data[header_len..])
+        .map_err(|_| "Failed to decrypt backup: wrong passphrase or corrupted file".to_string())
+}
+
+// 比较令牌（耗时与内容无关，避免通过响应时间逐字节猜测）
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER_LEN: usize = MAGIC.len() + SALT_LEN + NONCE_LEN;
+
+    #[test]
+    fn round_trip() {
+        let encrypted = encrypt_backup(b"backup data", "secret").unwrap();
+        assert!(is_encrypted(&encrypted));
+        assert_eq!(decrypt_backup(&encrypted, "secret").unwrap(), b"backup data");
+        // 每次加密使用新的盐与 nonce
+        assert_ne!(encrypt_backup(b"backup data", "secret").unwrap(), encrypted);
+    }
+
+    #[test]
+    fn rejects_empty_and_wrong_passphrase() {
+        assert!(encrypt_backup(b"backup data", "").is_err());
+        let encrypted = encrypt_backup(b"backup data", "secret").unwrap();
+        let err = decrypt_backup(&encrypted, "wrong").unwrap_err();
+        assert!(err.contains("wrong passphrase"));
+    }
+
+    #[test]
+    fn rejects_tampered_data() {
+        let encrypted = encrypt_backup(b"backup data", "secret").unwrap();
+        // 依次篡改盐、nonce、密文与认证标签（最后一个字节）
+        for index in [MAGIC.len(), MAGIC.len() + SALT_LEN, HEADER_LEN, encrypted.len() - 1] {
+            let mut tampered = encrypted.clone();
+            tampered[index] ^= 0x01;
+            assert!(decrypt_backup(&tampered, "secret").is_err(), "byte {} was not authenticated", index);
+        }
+    }
+
+    #[test]
+    fn rejects_truncated_data() {
+        let encrypted = encrypt_backup(b"backup data", "secret").unwrap();
+        for len in [MAGIC.len(), MAGIC.len() + SALT_LEN, HEADER_LEN - 1, HEADER_LEN, encrypted.len() - 1] {
+            assert!(decrypt_backup(&encrypted[..len], "secret").is_err(), "length {} was accepted", len);
+        }
+        assert_eq!(decrypt_backup(b"plain json", "secret").unwrap_err(), "Backup is not encrypted");
+    }
+
+    #[test]
+    fn compares_tokens() {
+        assert!(constant_time_eq(b"token", b"token"));
+        assert!(!constant_time_eq(b"token", b"tokem"));
+        assert!(!constant_time_eq(b"token", b"token2"));
+        assert!(!constant_time_eq(b"", b"token"));
+    }
+}
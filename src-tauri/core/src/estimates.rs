@@ -0,0 +1,205 @@
+use chrono::{Datelike, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::model::{Task, TimeEntry};
+
+// 预估与实际用时对比的一组统计（时长单位为分钟）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EstimateRow {
+    // 清单 ID、标签 ID 或 ISO 周（例如 2026-W42）
+    pub key: String,
+    // 标签名称（仅按标签统计时有值）
+    pub name: Option<String>,
+    pub tasks: i64,
+    pub estimated_minutes: i64,
+    pub actual_minutes: i64,
+    pub story_points: f64,
+    // 实际用时超过预估的任务数
+    pub underestimated: i64,
+    // 实际 / 预估（大于 1 表示低估）
+    pub ratio: Option<f64>,
+}
+
+// 预估对比报表：只统计已完成、未删除且填写了预估时长的任务，实际用时为任务的计时记录之和
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EstimateReport {
+    pub total: EstimateRow,
+    // 按清单、标签统计（按 ratio 降序，最常低估的在前）
+    pub by_list: Vec<EstimateRow>,
+    pub by_tag: Vec<EstimateRow>,
+    // 按周统计（按周升序）；任务计入最后一次计时所在的周，没有计时的按创建时间
+    pub by_week: Vec<EstimateRow>,
+}
+
+impl EstimateRow {
+    fn new(key: String, name: Option<String>) -> EstimateRow {
+        EstimateRow { key, name, ..Default::default() }
+    }
+
+    fn add(&mut self, estimated: i64, actual: i64, story_points: f64) {
+        self.tasks += 1;
+        self.estimated_minutes += estimated;
+        self.actual_minutes += actual;
+        self.story_points += story_points;
+        if actual > estimated {
+            self.underestimated += 1;
+        }
+        self.ratio = (self.estimated_minutes > 0).then(|| self.actual_minutes as f64 / self.estimated_minutes as f64);
+    }
+}
+
+// 由任务与计时记录生成报表（正在计时的记录计算到 now）
+pub fn build_estimate_report(tasks: &[Task], entries: &[TimeEntry], now: i64) -> EstimateReport {
+    // 每个任务的实际用时（秒）与最后一次计时时间
+    let mut logged: HashMap<&str, (i64, i64)> = HashMap::new();
+    for entry in entries {
+        let (seconds, last) = logged.entry(entry.task_id.as_str()).or_default();
+        *seconds += entry.duration(now);
+        *last = (*last).max(entry.ended_at.unwrap_or(now));
+    }
+
+    let mut report = EstimateReport {
+        total: EstimateRow::new("total".to_string(), None),
+        ..Default::default()
+    };
+    let mut by_list: HashMap<String, EstimateRow> = HashMap::new();
+    let mut by_tag: HashMap<String, EstimateRow> = HashMap::new();
+    let mut by_week: HashMap<String, EstimateRow> = HashMap::new();
+
+    for task in tasks.iter().filter(|t| t.completed && !t.deleted) {
+        let Some(estimated) = task.estimate_minutes.filter(|m| *m > 0).map(i64::from) else {
+            continue;
+        };
+        let task_logged = logged.get(task.id.as_str()).copied();
+        let actual = task_logged.map(|(seconds, _)| (seconds + 30) / 60).unwrap_or_default();
+        let points = task.story_points.unwrap_or_default();
+
+        report.total.add(estimated, actual, points);
+        by_list
+            .entry(task.list_id.clone())
+            .or_insert_with(|| EstimateRow::new(task.list_id.clone(), None))
+            .add(estimated, actual, points);
+        for (id, name) in task.tag_entries() {
+            by_tag
+                .entry(id.clone())
+                .or_insert_with(|| EstimateRow::new(id, name))
+                .add(estimated, actual, points);
+        }
+
+        let week_of = match task_logged {
+            Some((_, last)) => Some(last),
+            None => chrono::DateTime::parse_from_rfc3339(&task.created_at).ok().map(|d| d.timestamp()),
+        };
+        if let Some(week) = week_of.and_then(iso_week) {
+            by_week
+                .entry(week.clone())
+                .or_insert_with(|| EstimateRow::new(week, None))
+                .add(estimated, actual, points);
+        }
+    }
+
+    report.by_list = sorted_by_ratio(by_list);
+    report.by_tag = sorted_by_ratio(by_tag);
+    report.by_week = by_week.into_values().collect();
+    report.by_week.sort_by(|a, b| a.key.cmp(&b.key));
+    report
+}
+
+// 时间戳所在的 ISO 周（UTC），例如 2026-W42
+pub fn iso_week(timestamp: i64) -> Option<String> {
+    Some(iso_week_of(chrono::Utc.timestamp_opt(timestamp, 0).single()?.date_naive()))
+}
+
+// 日期所在的 ISO 周
+pub fn iso_week_of(date: chrono::NaiveDate) -> String {
+    let week = date.iso_week();
+    format!("{}-W{:02}", week.year(), week.week())
+}
+
+fn sorted_by_ratio(rows: HashMap<String, EstimateRow>) -> Vec<EstimateRow> {
+    let mut rows: Vec<EstimateRow> = rows.into_values().collect();
+    rows.sort_by(|a, b| {
+        b.ratio
+            .unwrap_or_default()
+            .total_cmp(&a.ratio.unwrap_or_default())
+            .then_with(|| a.key.cmp(&b.key))
+    });
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{build_estimate_report, iso_week};
+    use crate::model::TimeEntry;
+    use crate::testing::task;
+
+    // 2024-01-01T00:00:00Z（周一，2024-W01）
+    const MONDAY: i64 = 1_704_067_200;
+
+    fn entry(task_id: &str, started_at: i64, ended_at: Option<i64>) -> TimeEntry {
+        TimeEntry {
+            id: format!("{}-{}", task_id, started_at),
+            task_id: task_id.to_string(),
+            started_at,
+            ended_at,
+            note: None,
+            manual: false,
+            created_at: "2024-01-01T00:00:00Z".to_string(),
+        }
+    }
+
+    fn done(id: &str, extra: serde_json::Value) -> crate::model::Task {
+        let mut value = json!({ "completed": true, "status": "done", "createdAt": "2024-01-01T08:00:00Z" });
+        value.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+        task(id, value)
+    }
+
+    #[test]
+    fn tasks_without_estimate_are_skipped() {
+        let tasks = [
+            done("none", json!({})),
+            done("zero", json!({ "estimateMinutes": 0 })),
+            task("open", json!({ "estimateMinutes": 30 })),
+            done("trashed", json!({ "estimateMinutes": 30, "deleted": true })),
+        ];
+        let report = build_estimate_report(&tasks, &[entry("none", MONDAY, Some(MONDAY + 600))], MONDAY);
+
+        assert_eq!(report.total.tasks, 0);
+        assert!(report.total.ratio.is_none());
+        assert!(report.by_list.is_empty() && report.by_tag.is_empty() && report.by_week.is_empty());
+    }
+
+    #[test]
+    fn zero_tracked_time_uses_creation_week() {
+        let tasks = [done("a", json!({ "estimateMinutes": 30, "storyPoints": 2.0, "listId": "work" }))];
+        let report = build_estimate_report(&tasks, &[], MONDAY + 7 * 86_400);
+
+        assert_eq!((report.total.estimated_minutes, report.total.actual_minutes), (30, 0));
+        assert_eq!(report.total.ratio, Some(0.0));
+        assert_eq!(report.total.underestimated, 0);
+        assert_eq!(report.total.story_points, 2.0);
+        assert_eq!(report.by_list[0].key, "work");
+        assert_eq!(report.by_week[0].key, "2024-W01");
+    }
+
+    #[test]
+    fn running_timer_counts_until_now() {
+        let tasks = [done("a", json!({ "estimateMinutes": 60, "tags": "[{\"id\":\"t1\",\"name\":\"deep\"}]" }))];
+        let now = MONDAY + 8 * 86_400;
+        let entries = [entry("a", MONDAY, Some(MONDAY + 1800)), entry("a", now - 3600, None)];
+        let report = build_estimate_report(&tasks, &entries, now);
+
+        assert_eq!(report.total.actual_minutes, 90);
+        assert_eq!(report.total.underestimated, 1);
+        assert_eq!(report.total.ratio, Some(1.5));
+        assert_eq!((report.by_tag[0].key.as_str(), report.by_tag[0].name.as_deref()), ("t1", Some("deep")));
+        // 按最后一次计时（计算到 now）所在的周统计
+        assert_eq!(report.by_week[0].key, iso_week(now).unwrap());
+        assert_eq!(report.by_week[0].key, "2024-W02");
+    }
+}
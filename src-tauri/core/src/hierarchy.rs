@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::model::Task;
+
+// 任务树节点
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskNode {
+    pub task: Task,
+    pub children: Vec<TaskNode>,
+}
+
+// 由任务列表构建森林：父任务不在列表中的任务作为根（保持传入顺序）
+pub fn build_forest(tasks: Vec<Task>) -> Vec<TaskNode> {
+    let ids: HashSet<String> = tasks.iter().map(|t| t.id.clone()).collect();
+    let mut children: HashMap<String, Vec<Task>> = HashMap::new();
+    let mut roots = Vec::new();
+
+    for task in tasks {
+        match task.parent_id.clone().filter(|p| ids.contains(p) && *p != task.id) {
+            Some(parent_id) => children.entry(parent_id).or_default().push(task),
+            None => roots.push(task),
+        }
+    }
+
+    roots.into_iter().map(|task| build_node(task, &mut children)).collect()
+}
+
+// 把任务挂到 parent_id 下是否会形成环：从 parent_id 沿 parent_of 向上是否会经过任务自身
+pub(crate) fn creates_cycle(task_id: &str, parent_id: &str, parent_of: impl Fn(&str) -> Option<String>) -> bool {
+    let mut seen = HashSet::new();
+    let mut next = Some(parent_id.to_string());
+    while let Some(id) = next {
+        if id == task_id {
+            return true;
+        }
+        if !seen.insert(id.clone()) {
+            return false;
+        }
+        next = parent_of(&id);
+    }
+    false
+}
+
+// 从 children 中取出子节点，已取出的不会重复出现（数据中存在环时也能结束）
+fn build_node(task: Task, children: &mut HashMap<String, Vec<Task>>) -> TaskNode {
+    let kids = children.remove(&task.id).unwrap_or_default();
+    TaskNode {
+        children: kids.into_iter().map(|child| build_node(child, children)).collect(),
+        task,
+    }
+}
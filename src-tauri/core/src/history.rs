@@ -0,0 +1,109 @@
+use crate::model::{Task, TaskEvent};
+
+// 记录历史的字段（事件 field 取值）；新建任务记为 created，彻底删除记为 deleted（new_value 为空，区别于移入回收站）
+pub const EVENT_CREATED: &str = "created";
+pub const EVENT_DELETED: &str = "deleted";
+const TRACKED_FIELDS: &[&str] = &["title", "status", "priority", "dueDate", "listId", "completed", "deleted"];
+
+// 比较保存前后的任务，生成字段级变更事件（id 由存储分配）
+pub fn task_events(previous: Option<&Task>, task: &Task, at: &str) -> Vec<TaskEvent> {
+    let event = |field: &str, old_value: Option<String>, new_value: Option<String>| TaskEvent {
+        id: 0,
+        task_id: task.id.clone(),
+        field: field.to_string(),
+        old_value,
+        new_value,
+        created_at: at.to_string(),
+    };
+
+    let Some(previous) = previous else {
+        return vec![event(EVENT_CREATED, None, Some(task.title.clone()))];
+    };
+    TRACKED_FIELDS
+        .iter()
+        .filter_map(|field| {
+            let old_value = field_value(previous, field);
+            let new_value = field_value(task, field);
+            (old_value != new_value).then(|| event(field, old_value, new_value))
+        })
+        .collect()
+}
+
+// 任务被彻底删除时的最后一条事件（删除后历史仍保留）
+pub fn deleted_event(task_id: &str, title: &str, at: &str) -> TaskEvent {
+    TaskEvent {
+        id: 0,
+        task_id: task_id.to_string(),
+        field: EVENT_DELETED.to_string(),
+        old_value: Some(title.to_string()),
+        new_value: None,
+        created_at: at.to_string(),
+    }
+}
+
+fn field_value(task: &Task, field: &str) -> Option<String> {
+    match field {
+        "title" => Some(task.title.clone()),
+        "status" => Some(task.status.clone()),
+        "priority" => Some(task.priority.clone()),
+        "dueDate" => task.due_date.clone(),
+        "listId" => Some(task.list_id.clone()),
+        "completed" => Some(task.completed.to_string()),
+        "deleted" => Some(task.deleted.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{EVENT_CREATED, EVENT_DELETED};
+    use crate::model::Task;
+    use crate::repository::TaskRepository;
+    use crate::testing::{save, with_repositories};
+
+    fn fields(repo: &dyn TaskRepository, id: &str) -> Vec<(String, Option<String>, Option<String>)> {
+        repo.task_events(id)
+            .unwrap()
+            .into_iter()
+            .map(|e| (e.field, e.old_value, e.new_value))
+            .collect()
+    }
+
+    #[test]
+    fn edits_and_status_changes_are_recorded() {
+        with_repositories(|repo| {
+            save(repo, "a", json!({ "title": "first", "priority": "low" }));
+            let task = repo.task("a").unwrap().unwrap();
+            repo.save_task(&Task { title: "second".to_string(), priority: "high".to_string(), ..task.clone() }).unwrap();
+            // 未修改跟踪字段时不记录
+            repo.save_task(&Task { title: "second".to_string(), priority: "high".to_string(), ..task }).unwrap();
+            repo.set_task_completed("a", true).unwrap();
+
+            let events = fields(repo, "a");
+            assert_eq!(events[0], (EVENT_CREATED.to_string(), None, Some("first".to_string())));
+            assert!(events.contains(&("title".to_string(), Some("first".to_string()), Some("second".to_string()))));
+            assert!(events.contains(&("priority".to_string(), Some("low".to_string()), Some("high".to_string()))));
+            assert!(events.contains(&("completed".to_string(), Some("false".to_string()), Some("true".to_string()))));
+            assert!(events.iter().any(|(field, _, new)| field == "status" && new.as_deref() == Some("done")));
+            assert_eq!(events.iter().filter(|(field, _, _)| field == "title").count(), 1);
+        });
+    }
+
+    #[test]
+    fn deleting_keeps_history_and_records_deleted() {
+        with_repositories(|repo| {
+            save(repo, "a", json!({ "title": "parent" }));
+            save(repo, "b", json!({ "title": "child", "parentId": "a" }));
+
+            repo.delete_task("a").unwrap();
+
+            for (id, title) in [("a", "parent"), ("b", "child")] {
+                let events = fields(repo, id);
+                assert_eq!(events.first().unwrap().0, EVENT_CREATED);
+                assert_eq!(events.last().unwrap(), &(EVENT_DELETED.to_string(), Some(title.to_string()), None));
+            }
+        });
+    }
+}
@@ -0,0 +1,46 @@
+// My Todo 核心库：任务与清单模型、存储（SQLite / 内存）、任务查询与全文搜索、提醒计算、计时与番茄钟、统计报表、撤销日志、WebDAV 同步合并、备份格式与 S3 签名
+// 不依赖 Tauri，桌面端、命令行与自托管同步服务共用
+
+pub mod backup;
+pub mod crypto;
+pub mod estimates;
+pub mod hierarchy;
+pub mod history;
+pub mod memory;
+pub mod merge;
+pub mod model;
+pub mod pomodoro;
+pub mod query;
+pub mod reminders;
+pub mod repository;
+pub mod s3;
+pub mod search;
+pub mod statistics;
+#[cfg(feature = "sqlite")]
+pub mod store;
+pub mod subtasks;
+pub mod sync;
+#[cfg(test)]
+mod testing;
+pub mod time_tracking;
+pub mod undo;
+
+pub use model::{
+    batch_changes, db_path_in, list_from_partial, task_from_partial, update_list_from_partial, update_task_from_partial,
+    ChangeRecord, PomodoroRecord, SubTask, Task, TaskBatch, TaskConflict, TaskDependency, TaskEvent, TaskList, TaskUpdate,
+    TimeEntry, APP_IDENTIFIER, SCHEMA_VERSION,
+};
+pub use estimates::{EstimateReport, EstimateRow};
+pub use hierarchy::TaskNode;
+pub use memory::MemoryStore;
+pub use pomodoro::{PomodoroPhase, PomodoroSettings, PomodoroState, PomodoroStatus, PomodoroTransition};
+pub use query::{TaskPage, TaskQuery, TaskSort};
+pub use reminders::ReminderTask;
+pub use repository::TaskRepository;
+pub use search::SearchResult;
+pub use statistics::{Statistics, StatisticsQuery};
+pub use subtasks::TaskProgress;
+pub use time_tracking::{TimeGroup, TimeTotal};
+pub use undo::{UndoEntry, UndoState};
+#[cfg(feature = "sqlite")]
+pub use store::Store;
@@ -0,0 +1,737 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, MutexGuard};
+
+use crate::hierarchy::creates_cycle;
+use crate::history::{deleted_event, task_events};
+use crate::model::{default_lists, ChangeRecord, PomodoroRecord, Task, TaskConflict, TaskDependency, TaskEvent, TaskList, TimeEntry};
+use crate::query::{TaskPage, TaskQuery};
+use crate::repository::TaskRepository;
+use crate::search::{match_score, search_result, search_terms, SearchResult};
+use crate::subtasks::{normalize_task_subtasks, parse_subtasks, TaskProgress};
+use crate::time_tracking::{sort_totals, timer_entry, TimeGroup, TimeTotal};
+use crate::undo::{check_replay, restored_task, TaskRecords, TaskSnapshot, UndoEntry, UndoOperation, UndoState, UNDO_LIMIT};
+
+// 已发送提醒记录
+#[derive(Clone)]
+struct SentReminder {
+    task_id: String,
+    reminder_time: i64,
+    sent_at: i64,
+}
+
+#[derive(Default, Clone)]
+struct MemoryData {
+    tasks: HashMap<String, Task>,
+    lists: HashMap<String, TaskList>,
+    sent_reminders: HashMap<String, SentReminder>,
+    dependencies: Vec<TaskDependency>,
+    time_entries: Vec<TimeEntry>,
+    pomodoros: Vec<PomodoroRecord>,
+    events: Vec<TaskEvent>,
+    last_event_id: i64,
+    undo_log: Vec<UndoOperation>,
+    last_operation_id: i64,
+    changes: Vec<ChangeRecord>,
+    last_seq: i64,
+    sync_state: HashMap<String, String>,
+    conflicts: Vec<TaskConflict>,
+}
+
+impl MemoryData {
+    // 记录实体变更（与 SQLite 实现一致：同一实体只保留最新一条）
+    fn record_change(&mut self, entity: &str, entity_id: &str, op: &str) {
+        self.changes.retain(|c| !(c.entity == entity && c.entity_id == entity_id));
+        self.last_seq += 1;
+        self.changes.push(ChangeRecord {
+            seq: self.last_seq,
+            entity: entity.to_string(),
+            entity_id: entity_id.to_string(),
+            op: op.to_string(),
+            changed_at: chrono::Utc::now().to_rfc3339(),
+        });
+    }
+
+    // 写入任务（与 SQLite 实现一致：整理子任务）
+    fn upsert_task(&mut self, task: &Task) {
+        let stored = self.tasks.get(&task.id);
+        let previous = stored.map(|t| parse_subtasks(&t.sub_tasks)).unwrap_or_default();
+        let completed_at = stored.and_then(|t| t.completed_at.clone());
+        let mut task = task.clone();
+        normalize_task_subtasks(&mut task, &previous);
+        task.normalize_completed_at(completed_at);
+        let now = chrono::Utc::now().to_rfc3339();
+        for mut event in task_events(stored, &task, &now) {
+            self.last_event_id += 1;
+            event.id = self.last_event_id;
+            self.events.push(event);
+        }
+        self.tasks.insert(task.id.clone(), task);
+    }
+
+    // 把任务挂到 parent_id 下是否会形成环（parent_id 为任务自身或其后代）
+    fn creates_cycle(&self, task_id: &str, parent_id: &str) -> bool {
+        creates_cycle(task_id, parent_id, |id| self.tasks.get(id).and_then(|t| t.parent_id.clone()))
+    }
+
+    // 任务及其所有后代的 ID
+    fn subtree_ids(&self, id: &str) -> Vec<String> {
+        let mut ids = vec![id.to_string()];
+        let mut index = 0;
+        while index < ids.len() {
+            let parent_id = ids[index].clone();
+            for task in self.tasks.values() {
+                if task.parent_id.as_deref() == Some(parent_id.as_str()) && !ids.contains(&task.id) {
+                    ids.push(task.id.clone());
+                }
+            }
+            index += 1;
+        }
+        ids
+    }
+
+    // 返回任务副本并计算 blocked（与 SQLite 实现一致：存在未完成且未删除的阻塞任务）
+    fn view(&self, task: &Task) -> Task {
+        let blocked = self.dependencies.iter().any(|d| {
+            d.task_id == task.id
+                && self.tasks.get(&d.blocked_by).is_some_and(|b| !b.completed && !b.deleted)
+        });
+        Task { blocked, ..task.clone() }
+    }
+
+    // 撤销（写回操作前的状态）或重做（写回操作后的状态）
+    // 与 SQLite 实现一致：任务在操作之后又被修改时返回冲突错误，不写回任何操作，日志保持不变
+    fn replay(&mut self, steps: usize, undo: bool) -> Result<Vec<UndoEntry>, String> {
+        let mut ids: Vec<i64> = self.undo_log.iter().filter(|o| o.undone != undo).map(|o| o.id).collect();
+        if undo {
+            ids.reverse();
+        }
+        ids.truncate(steps);
+
+        // 先按顺序检查全部操作（pending 为前面的操作写回后的状态）
+        let mut pending: HashMap<String, Option<Task>> = HashMap::new();
+        for id in &ids {
+            let Some(operation) = self.undo_log.iter().find(|o| o.id == *id) else {
+                continue;
+            };
+            let current = |task_id: &str| {
+                Ok(match pending.get(task_id) {
+                    Some(task) => task.clone(),
+                    None => self.tasks.get(task_id).cloned(),
+                })
+            };
+            check_replay(operation, undo, current)?;
+            for snapshot in if undo { &operation.before } else { &operation.after } {
+                pending.insert(snapshot.id.clone(), snapshot.task.clone());
+            }
+        }
+
+        let now = chrono::Utc::now().to_rfc3339();
+        let mut entries = Vec::new();
+        for id in ids {
+            let Some(index) = self.undo_log.iter().position(|o| o.id == id) else {
+                continue;
+            };
+            let snapshots = if undo { self.undo_log[index].before.clone() } else { self.undo_log[index].after.clone() };
+            for snapshot in &snapshots {
+                match &snapshot.task {
+                    Some(task) => {
+                        self.upsert_task(&restored_task(task, &now));
+                        self.record_change("task", &snapshot.id, "upsert");
+                    }
+                    None => {
+                        self.delete_task(&snapshot.id);
+                        self.record_change("task", &snapshot.id, "delete");
+                    }
+                }
+            }
+            for snapshot in snapshots.iter().filter(|s| s.task.is_some()) {
+                self.restore_records(&snapshot.records);
+            }
+            self.undo_log[index].undone = undo;
+            entries.push(self.undo_log[index].entry());
+        }
+        Ok(entries)
+    }
+
+    // 写回撤销删除时保存的关联记录（已存在的记录保持不变，另一端任务不存在的依赖不写回）
+    fn restore_records(&mut self, records: &TaskRecords) {
+        for entry in &records.time_entries {
+            if !self.time_entries.iter().any(|e| e.id == entry.id) {
+                self.time_entries.push(entry.clone());
+            }
+        }
+        for record in &records.pomodoros {
+            if !self.pomodoros.iter().any(|p| p.id == record.id) {
+                self.pomodoros.push(record.clone());
+            }
+        }
+        for dependency in &records.dependencies {
+            let exists = self.tasks.contains_key(&dependency.task_id) && self.tasks.contains_key(&dependency.blocked_by);
+            let duplicate = self
+                .dependencies
+                .iter()
+                .any(|d| d.task_id == dependency.task_id && d.blocked_by == dependency.blocked_by);
+            if exists && !duplicate {
+                self.dependencies.push(dependency.clone());
+            }
+        }
+    }
+
+    fn delete_task(&mut self, id: &str) {
+        let Some(task) = self.tasks.remove(id) else {
+            return;
+        };
+        // 历史保留，以 deleted 事件结束
+        let mut event = deleted_event(id, &task.title, &chrono::Utc::now().to_rfc3339());
+        self.last_event_id += 1;
+        event.id = self.last_event_id;
+        self.events.push(event);
+        self.dependencies.retain(|d| d.task_id != id && d.blocked_by != id);
+        self.time_entries.retain(|e| e.task_id != id);
+        self.pomodoros.retain(|p| p.task_id != id);
+        self.sent_reminders.retain(|_, r| r.task_id != id);
+    }
+}
+
+// 内存数据存储（进程退出后数据丢失，用于未启用 SQLite 的构建与测试）
+pub struct MemoryStore {
+    data: Mutex<MemoryData>,
+}
+
+impl MemoryStore {
+    // 创建包含默认清单的空存储
+    pub fn new() -> MemoryStore {
+        let data = MemoryData {
+            lists: default_lists().into_iter().map(|l| (l.id.clone(), l)).collect(),
+            ..Default::default()
+        };
+        MemoryStore { data: Mutex::new(data) }
+    }
+
+    fn data(&self) -> Result<MutexGuard<'_, MemoryData>, String> {
+        self.data.lock().map_err(|_| "Memory store lock poisoned".to_string())
+    }
+}
+
+impl Default for MemoryStore {
+    fn default() -> Self {
+        MemoryStore::new()
+    }
+}
+
+impl TaskRepository for MemoryStore {
+    fn init(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    // 在数据副本上执行 f（期间持有锁），成功后整体替换
+    fn transaction(&self, f: &mut dyn FnMut(&dyn TaskRepository) -> Result<(), String>) -> Result<(), String> {
+        let mut data = self.data()?;
+        let view = MemoryStore { data: Mutex::new(data.clone()) };
+        f(&view)?;
+        *data = view.data.into_inner().map_err(|_| "Memory store lock poisoned".to_string())?;
+        Ok(())
+    }
+
+    // ========== 任务 ==========
+
+    fn tasks(&self) -> Result<Vec<Task>, String> {
+        let data = self.data()?;
+        let mut tasks: Vec<Task> = data.tasks.values().map(|t| data.view(t)).collect();
+        tasks.sort_by(|a, b| a.order.cmp(&b.order).then_with(|| b.created_at.cmp(&a.created_at)));
+        Ok(tasks)
+    }
+
+    fn task(&self, id: &str) -> Result<Option<Task>, String> {
+        let data = self.data()?;
+        Ok(data.tasks.get(id).map(|t| data.view(t)))
+    }
+
+    fn query_tasks(&self, query: &TaskQuery) -> Result<TaskPage, String> {
+        let due_range = query.due_range()?;
+        let data = self.data()?;
+        let mut tasks: Vec<Task> = data
+            .tasks
+            .values()
+            .map(|t| data.view(t))
+            .filter(|t| query.matches(t, due_range))
+            .collect();
+        query.sort_tasks(&mut tasks);
+        let total = tasks.len() as i64;
+        Ok(TaskPage { tasks: query.paginate(tasks), total })
+    }
+
+    fn search_tasks(&self, text: &str, limit: usize) -> Result<Vec<SearchResult>, String> {
+        let terms = search_terms(text);
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let data = self.data()?;
+        let mut hits: Vec<(Task, f64)> = data
+            .tasks
+            .values()
+            .filter(|t| !t.deleted)
+            .filter_map(|t| match_score(t, &terms).map(|score| (data.view(t), score)))
+            .collect();
+        hits.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| b.0.updated_at.cmp(&a.0.updated_at)));
+
+        Ok(hits
+            .into_iter()
+            .take(limit)
+            .map(|(task, score)| search_result(task, &terms, score))
+            .collect())
+    }
+
+    fn save_task(&self, task: &Task) -> Result<(), String> {
+        let mut data = self.data()?;
+        if let Some(parent_id) = &task.parent_id {
+            if !data.tasks.contains_key(parent_id) {
+                return Err(format!("Parent task not found: {}", parent_id));
+            }
+            if data.creates_cycle(&task.id, parent_id) {
+                return Err("Cannot move a task under itself or its own descendant".to_string());
+            }
+        }
+        data.upsert_task(task);
+        data.record_change("task", &task.id, "upsert");
+        Ok(())
+    }
+
+    fn delete_task(&self, id: &str) -> Result<(), String> {
+        let mut data = self.data()?;
+        for id in data.subtree_ids(id) {
+            data.delete_task(&id);
+            data.record_change("task", &id, "delete");
+        }
+        Ok(())
+    }
+
+    fn apply_task_changes(&self, upserts: &[Task], deletes: &[String], record: bool) -> Result<(), String> {
+        let mut data = self.data()?;
+        // 先检查写入后的层级（与 SQLite 实现一致：父任务可在同一批中写入，任一任务的父任务不存在或形成环时都不写入）
+        let parents: HashMap<&str, Option<String>> = upserts.iter().map(|t| (t.id.as_str(), t.parent_id.clone())).collect();
+        let parent_of = |id: &str| match parents.get(id) {
+            Some(parent_id) => parent_id.clone(),
+            None => data.tasks.get(id).and_then(|t| t.parent_id.clone()),
+        };
+        for task in upserts {
+            if let Some(parent_id) = &task.parent_id {
+                if !parents.contains_key(parent_id.as_str()) && !data.tasks.contains_key(parent_id) {
+                    return Err(format!("Parent task not found: {}", parent_id));
+                }
+                if creates_cycle(&task.id, parent_id, parent_of) {
+                    return Err("Cannot move a task under itself or its own descendant".to_string());
+                }
+            }
+        }
+
+        for task in upserts {
+            data.upsert_task(task);
+            if record {
+                data.record_change("task", &task.id, "upsert");
+            }
+        }
+        for id in deletes {
+            for id in data.subtree_ids(id) {
+                data.delete_task(&id);
+                if record {
+                    data.record_change("task", &id, "delete");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn task_events(&self, task_id: &str) -> Result<Vec<TaskEvent>, String> {
+        Ok(self.data()?.events.iter().filter(|e| e.task_id == task_id).cloned().collect())
+    }
+
+    // ========== 撤销与重做 ==========
+
+    fn log_operation(&self, label: &str, before: &[TaskSnapshot], after: &[TaskSnapshot]) -> Result<(), String> {
+        let mut data = self.data()?;
+        data.undo_log.retain(|o| !o.undone);
+        data.last_operation_id += 1;
+        let operation = UndoOperation {
+            id: data.last_operation_id,
+            label: label.to_string(),
+            before: before.to_vec(),
+            after: after.to_vec(),
+            undone: false,
+            created_at: chrono::Utc::now().to_rfc3339(),
+        };
+        data.undo_log.push(operation);
+        let excess = data.undo_log.len().saturating_sub(UNDO_LIMIT);
+        data.undo_log.drain(..excess);
+        Ok(())
+    }
+
+    fn undo(&self, steps: usize) -> Result<Vec<UndoEntry>, String> {
+        self.data()?.replay(steps, true)
+    }
+
+    fn redo(&self, steps: usize) -> Result<Vec<UndoEntry>, String> {
+        self.data()?.replay(steps, false)
+    }
+
+    fn undo_state(&self) -> Result<UndoState, String> {
+        let data = self.data()?;
+        let undo: Vec<&UndoOperation> = data.undo_log.iter().filter(|o| !o.undone).collect();
+        let redo: Vec<&UndoOperation> = data.undo_log.iter().filter(|o| o.undone).collect();
+        Ok(UndoState {
+            undo_count: undo.len() as i64,
+            redo_count: redo.len() as i64,
+            next_undo: undo.last().map(|o| o.entry()),
+            next_redo: redo.first().map(|o| o.entry()),
+        })
+    }
+
+    // ========== 任务层级 ==========
+
+    fn descendants(&self, id: &str) -> Result<Vec<Task>, String> {
+        let data = self.data()?;
+        let mut seen = HashSet::from([id.to_string()]);
+        let mut frontier = vec![id.to_string()];
+        let mut descendants = Vec::new();
+        while let Some(parent_id) = frontier.pop() {
+            for task in data.tasks.values() {
+                if task.parent_id.as_deref() == Some(parent_id.as_str()) && seen.insert(task.id.clone()) {
+                    frontier.push(task.id.clone());
+                    descendants.push(data.view(task));
+                }
+            }
+        }
+        descendants.sort_by(|a, b| a.order.cmp(&b.order).then_with(|| b.created_at.cmp(&a.created_at)));
+        Ok(descendants)
+    }
+
+    fn ancestors(&self, id: &str) -> Result<Vec<Task>, String> {
+        let data = self.data()?;
+        let mut seen = HashSet::from([id.to_string()]);
+        let mut next = data.tasks.get(id).and_then(|t| t.parent_id.clone());
+        let mut ancestors = Vec::new();
+        while let Some(task) = next.and_then(|parent_id| data.tasks.get(&parent_id)) {
+            if !seen.insert(task.id.clone()) {
+                break;
+            }
+            next = task.parent_id.clone();
+            ancestors.push(data.view(task));
+        }
+        Ok(ancestors)
+    }
+
+    // ========== 任务依赖 ==========
+
+    fn task_dependencies(&self, task_id: Option<&str>) -> Result<Vec<TaskDependency>, String> {
+        Ok(self
+            .data()?
+            .dependencies
+            .iter()
+            .filter(|d| task_id.is_none_or(|id| d.task_id == id || d.blocked_by == id))
+            .cloned()
+            .collect())
+    }
+
+    fn add_task_dependency(&self, task_id: &str, blocked_by: &str) -> Result<TaskDependency, String> {
+        if task_id == blocked_by {
+            return Err("A task cannot block itself".to_string());
+        }
+
+        let mut data = self.data()?;
+        for id in [task_id, blocked_by] {
+            if !data.tasks.contains_key(id) {
+                return Err(format!("Task not found: {}", id));
+            }
+        }
+        if let Some(existing) = data.dependencies.iter().find(|d| d.task_id == task_id && d.blocked_by == blocked_by) {
+            return Ok(existing.clone());
+        }
+
+        // blocked_by 直接或间接被 task_id 阻塞时会形成环
+        let mut seen = HashSet::new();
+        let mut frontier = vec![blocked_by.to_string()];
+        while let Some(id) = frontier.pop() {
+            if id == task_id {
+                return Err("Adding this dependency would create a cycle".to_string());
+            }
+            if seen.insert(id.clone()) {
+                frontier.extend(data.dependencies.iter().filter(|d| d.task_id == id).map(|d| d.blocked_by.clone()));
+            }
+        }
+
+        let dependency = TaskDependency {
+            task_id: task_id.to_string(),
+            blocked_by: blocked_by.to_string(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+        };
+        data.dependencies.push(dependency.clone());
+        Ok(dependency)
+    }
+
+    fn remove_task_dependency(&self, task_id: &str, blocked_by: &str) -> Result<bool, String> {
+        let mut data = self.data()?;
+        let before = data.dependencies.len();
+        data.dependencies.retain(|d| !(d.task_id == task_id && d.blocked_by == blocked_by));
+        Ok(data.dependencies.len() < before)
+    }
+
+    // ========== 计时 ==========
+
+    fn time_entries(&self, task_id: Option<&str>) -> Result<Vec<TimeEntry>, String> {
+        let mut entries: Vec<TimeEntry> = self
+            .data()?
+            .time_entries
+            .iter()
+            .filter(|e| task_id.is_none_or(|id| e.task_id == id))
+            .cloned()
+            .collect();
+        entries.sort_by(|a, b| b.started_at.cmp(&a.started_at).then_with(|| b.created_at.cmp(&a.created_at)));
+        Ok(entries)
+    }
+
+    fn running_timer(&self) -> Result<Option<TimeEntry>, String> {
+        Ok(self.data()?.time_entries.iter().find(|e| e.is_running()).cloned())
+    }
+
+    fn start_timer(&self, task_id: &str, now: i64) -> Result<TimeEntry, String> {
+        let mut data = self.data()?;
+        if !data.tasks.contains_key(task_id) {
+            return Err(format!("Task not found: {}", task_id));
+        }
+        if let Some(running) = data.time_entries.iter_mut().find(|e| e.is_running()) {
+            if running.task_id == task_id {
+                return Ok(running.clone());
+            }
+            running.ended_at = Some(now.max(running.started_at));
+        }
+
+        let entry = timer_entry(task_id, now);
+        data.time_entries.push(entry.clone());
+        Ok(entry)
+    }
+
+    fn stop_timer(&self, now: i64) -> Result<Option<TimeEntry>, String> {
+        let mut data = self.data()?;
+        Ok(data.time_entries.iter_mut().find(|e| e.is_running()).map(|running| {
+            running.ended_at = Some(now.max(running.started_at));
+            running.clone()
+        }))
+    }
+
+    fn add_time_entry(&self, entry: &TimeEntry) -> Result<(), String> {
+        if entry.is_running() {
+            return Err("Use start_timer to start a running timer".to_string());
+        }
+        let mut data = self.data()?;
+        if !data.tasks.contains_key(&entry.task_id) {
+            return Err(format!("Task not found: {}", entry.task_id));
+        }
+        data.time_entries.retain(|e| e.id != entry.id);
+        data.time_entries.push(entry.clone());
+        Ok(())
+    }
+
+    fn time_totals(&self, group: TimeGroup, now: i64) -> Result<Vec<TimeTotal>, String> {
+        let data = self.data()?;
+        let mut totals: HashMap<String, TimeTotal> = HashMap::new();
+        for entry in &data.time_entries {
+            let id = match group {
+                TimeGroup::Task => entry.task_id.clone(),
+                TimeGroup::List => match data.tasks.get(&entry.task_id) {
+                    Some(task) => task.list_id.clone(),
+                    None => continue,
+                },
+            };
+            let total = totals.entry(id.clone()).or_insert(TimeTotal { id, seconds: 0, entries: 0 });
+            total.seconds += entry.duration(now);
+            total.entries += 1;
+        }
+
+        let mut totals: Vec<TimeTotal> = totals.into_values().collect();
+        sort_totals(&mut totals);
+        Ok(totals)
+    }
+
+    fn pomodoros(&self, task_id: Option<&str>) -> Result<Vec<PomodoroRecord>, String> {
+        let mut records: Vec<PomodoroRecord> = self
+            .data()?
+            .pomodoros
+            .iter()
+            .filter(|p| task_id.is_none_or(|id| p.task_id == id))
+            .cloned()
+            .collect();
+        records.sort_by_key(|p| std::cmp::Reverse(p.started_at));
+        Ok(records)
+    }
+
+    fn log_pomodoro(&self, record: &PomodoroRecord) -> Result<(), String> {
+        let mut data = self.data()?;
+        if !data.tasks.contains_key(&record.task_id) {
+            return Err(format!("Task not found: {}", record.task_id));
+        }
+        data.pomodoros.retain(|p| p.id != record.id);
+        data.pomodoros.push(record.clone());
+        Ok(())
+    }
+
+    // ========== 子任务 ==========
+
+    fn subtask_parent(&self, subtask_id: &str) -> Result<Option<String>, String> {
+        Ok(self
+            .data()?
+            .tasks
+            .values()
+            .find(|t| parse_subtasks(&t.sub_tasks).iter().any(|s| s.id == subtask_id))
+            .map(|t| t.id.clone()))
+    }
+
+    fn subtask_progress(&self) -> Result<Vec<TaskProgress>, String> {
+        let mut progress: Vec<TaskProgress> = self
+            .data()?
+            .tasks
+            .values()
+            .filter(|t| !t.deleted)
+            .filter_map(|t| {
+                let subtasks = parse_subtasks(&t.sub_tasks);
+                let completed = subtasks.iter().filter(|s| s.completed).count() as i64;
+                (!subtasks.is_empty()).then(|| TaskProgress::new(t.id.clone(), subtasks.len() as i64, completed))
+            })
+            .collect();
+        progress.sort_by(|a, b| a.task_id.cmp(&b.task_id));
+        Ok(progress)
+    }
+
+    // ========== 清单 ==========
+
+    fn lists(&self) -> Result<Vec<TaskList>, String> {
+        let mut lists: Vec<TaskList> = self.data()?.lists.values().cloned().collect();
+        lists.sort_by_key(|l| l.order);
+        Ok(lists)
+    }
+
+    fn save_list(&self, list: &TaskList) -> Result<(), String> {
+        let mut data = self.data()?;
+        data.lists.insert(list.id.clone(), list.clone());
+        data.record_change("list", &list.id, "upsert");
+        Ok(())
+    }
+
+    fn delete_list(&self, id: &str) -> Result<bool, String> {
+        let mut data = self.data()?;
+        let deleted = data.lists.remove(id).is_some();
+        if deleted {
+            data.record_change("list", id, "delete");
+        }
+        Ok(deleted)
+    }
+
+    fn apply_list_changes(&self, upserts: &[TaskList], deletes: &[String], record: bool) -> Result<(), String> {
+        let mut data = self.data()?;
+        for list in upserts {
+            data.lists.insert(list.id.clone(), list.clone());
+            if record {
+                data.record_change("list", &list.id, "upsert");
+            }
+        }
+        for id in deletes {
+            if data.lists.remove(id).is_some() && record {
+                data.record_change("list", id, "delete");
+            }
+        }
+        Ok(())
+    }
+
+    fn count_rows(&self) -> Result<(i64, i64), String> {
+        let data = self.data()?;
+        Ok((data.tasks.len() as i64, data.lists.len() as i64))
+    }
+
+    // ========== 提醒记录 ==========
+
+    fn is_reminder_sent(&self, task_id: &str, reminder_time: i64) -> Result<bool, String> {
+        Ok(self
+            .data()?
+            .sent_reminders
+            .values()
+            .any(|r| r.task_id == task_id && r.reminder_time == reminder_time))
+    }
+
+    fn save_sent_reminder(&self, id: &str, task_id: &str, reminder_time: i64, _reminder_data: &str) -> Result<(), String> {
+        self.data()?.sent_reminders.insert(
+            id.to_string(),
+            SentReminder {
+                task_id: task_id.to_string(),
+                reminder_time,
+                sent_at: chrono::Utc::now().timestamp(),
+            },
+        );
+        Ok(())
+    }
+
+    fn cleanup_old_reminders(&self) -> Result<(), String> {
+        let thirty_days_ago = chrono::Utc::now().timestamp() - (30 * 24 * 60 * 60);
+        self.data()?.sent_reminders.retain(|_, r| r.sent_at >= thirty_days_ago);
+        Ok(())
+    }
+
+    fn delete_task_reminders(&self, task_id: &str) -> Result<(), String> {
+        self.data()?.sent_reminders.retain(|_, r| r.task_id != task_id);
+        Ok(())
+    }
+
+    // ========== 变更记录与同步状态 ==========
+
+    fn changes_since(&self, after_seq: i64) -> Result<Vec<ChangeRecord>, String> {
+        Ok(self.data()?.changes.iter().filter(|c| c.seq > after_seq).cloned().collect())
+    }
+
+    fn max_change_seq(&self) -> Result<i64, String> {
+        Ok(self.data()?.changes.last().map(|c| c.seq).unwrap_or(0))
+    }
+
+    fn sync_state(&self, key: &str) -> Result<Option<String>, String> {
+        Ok(self.data()?.sync_state.get(key).cloned())
+    }
+
+    fn set_sync_state(&self, key: &str, value: &str) -> Result<(), String> {
+        self.data()?.sync_state.insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+
+    // ========== 任务冲突 ==========
+
+    fn save_task_conflicts(&self, conflicts: &[TaskConflict]) -> Result<(), String> {
+        let mut data = self.data()?;
+        for conflict in conflicts {
+            data.conflicts.retain(|c| !(c.task_id == conflict.task_id && c.resolved_at.is_none()));
+            data.conflicts.push(conflict.clone());
+        }
+        Ok(())
+    }
+
+    fn task_conflicts(&self, include_resolved: bool) -> Result<Vec<TaskConflict>, String> {
+        let mut conflicts: Vec<TaskConflict> = self
+            .data()?
+            .conflicts
+            .iter()
+            .filter(|c| include_resolved || c.resolved_at.is_none())
+            .cloned()
+            .collect();
+        conflicts.sort_by(|a, b| b.detected_at.cmp(&a.detected_at));
+        Ok(conflicts)
+    }
+
+    fn task_conflict(&self, id: &str) -> Result<Option<TaskConflict>, String> {
+        Ok(self.data()?.conflicts.iter().find(|c| c.id == id).cloned())
+    }
+
+    fn resolve_task_conflict(&self, id: &str, resolution: &str) -> Result<(), String> {
+        let mut data = self.data()?;
+        if let Some(conflict) = data.conflicts.iter_mut().find(|c| c.id == id) {
+            conflict.resolved_at = Some(chrono::Utc::now().to_rfc3339());
+            conflict.resolution = Some(resolution.to_string());
+        }
+        Ok(())
+    }
+}
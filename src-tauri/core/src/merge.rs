@@ -0,0 +1,435 @@
+// WebDAV 同步的三方合并（按记录、按字段合并，删除以墓碑记录）：桌面端负责读写远端与本地，合并本身不做 I/O
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::model::{Task, TaskConflict};
+
+// 删除墓碑保留天数
+pub const TOMBSTONE_TTL_DAYS: i64 = 90;
+
+// 参与冲突比较与合并的任务字段（与 Task 的 camelCase 序列化名一致）
+pub const TASK_FIELDS: &[&str] = &[
+    "title",
+    "description",
+    "completed",
+    "priority",
+    "status",
+    "listId",
+    "tags",
+    "subTasks",
+    "reminders",
+    "dueDate",
+    "order",
+    "categoryId",
+    "deleted",
+    "deletedAt",
+    "parentId",
+    "estimateMinutes",
+    "storyPoints",
+    "completedAt",
+];
+
+// 构造一条冲突记录
+pub fn new_conflict(source: &str, local: &Task, remote: &Task, winner: &str) -> TaskConflict {
+    TaskConflict {
+        id: uuid::Uuid::new_v4().to_string(),
+        task_id: local.id.clone(),
+        source: source.to_string(),
+        local: local.clone(),
+        remote: remote.clone(),
+        winner: winner.to_string(),
+        detected_at: chrono::Utc::now().to_rfc3339(),
+        resolved_at: None,
+        resolution: None,
+    }
+}
+
+// 删除墓碑
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Tombstone {
+    pub deleted_at: String,
+}
+
+// WebDAV 上的同步文档：所有设备合并后的任务集合与删除记录
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncDocument {
+    pub version: u32,
+    pub updated_at: String,
+    #[serde(default)]
+    pub tasks: BTreeMap<String, Task>,
+    #[serde(default)]
+    pub tombstones: BTreeMap<String, Tombstone>,
+}
+
+// 本地的上次同步快照
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncBase {
+    #[serde(default)]
+    pub tasks: BTreeMap<String, Task>,
+}
+
+// 合并结果
+#[derive(Default)]
+pub struct MergeOutcome {
+    pub document: SyncDocument,
+    pub local_upserts: Vec<Task>,
+    pub local_deletes: Vec<String>,
+    pub pushed: usize,
+    // 双方改动了同一字段的任务（保留落败一方的版本供用户处理）
+    pub conflicts: Vec<TaskConflict>,
+}
+
+// 三方合并：base 为上次同步快照，local 为本地当前任务，remote 为远端同步文档
+pub fn merge(
+    base: &SyncBase,
+    local: &BTreeMap<String, Task>,
+    remote: SyncDocument,
+    now: chrono::DateTime<chrono::Utc>,
+) -> MergeOutcome {
+    let now_str = now.to_rfc3339();
+    let mut outcome = MergeOutcome {
+        document: SyncDocument {
+            version: 1,
+            updated_at: now_str.clone(),
+            tasks: BTreeMap::new(),
+            tombstones: remote.tombstones.clone(),
+        },
+        ..Default::default()
+    };
+
+    let ids: BTreeSet<&String> = base
+        .tasks
+        .keys()
+        .chain(local.keys())
+        .chain(remote.tasks.keys())
+        .collect();
+
+    for id in ids {
+        let base_task = base.tasks.get(id);
+        let local_task = local.get(id);
+        let remote_task = remote.tasks.get(id);
+        let tombstone = remote.tombstones.get(id);
+
+        match (local_task, remote_task) {
+            (Some(l), Some(r)) => {
+                let (merged, conflicted) = merge_task(base_task, l, r);
+                if conflicted {
+                    let winner = if merged.updated_at == l.updated_at { "local" } else { "remote" };
+                    outcome.conflicts.push(new_conflict("webdav", l, r, winner));
+                }
+                if !same_task(&merged, l) {
+                    outcome.local_upserts.push(merged.clone());
+                }
+                if !same_task(&merged, r) {
+                    outcome.pushed += 1;
+                }
+                outcome.document.tombstones.remove(id);
+                outcome.document.tasks.insert(id.clone(), merged);
+            }
+            (Some(l), None) => {
+                let deleted_remotely = match (tombstone, base_task) {
+                    // 删除晚于本地最后修改：接受删除；否则本地修改胜出（恢复任务）
+                    (Some(t), _) => timestamp(&t.deleted_at) >= timestamp(&l.updated_at),
+                    // 已同步过但远端没有且无墓碑（旧版文档），本地未改动时视为删除
+                    (None, Some(b)) => same_task(l, b),
+                    (None, None) => false,
+                };
+                if deleted_remotely {
+                    outcome.local_deletes.push(id.clone());
+                } else {
+                    outcome.document.tombstones.remove(id);
+                    outcome.document.tasks.insert(id.clone(), l.clone());
+                    outcome.pushed += 1;
+                }
+            }
+            (None, Some(r)) => {
+                let deleted_locally = match base_task {
+                    // 本地删除后远端又有修改：修改胜出
+                    Some(b) => timestamp(&r.updated_at) <= timestamp(&b.updated_at),
+                    None => false,
+                };
+                if deleted_locally {
+                    outcome.document.tombstones.insert(
+                        id.clone(),
+                        Tombstone {
+                            deleted_at: now_str.clone(),
+                        },
+                    );
+                    outcome.pushed += 1;
+                } else {
+                    outcome.local_upserts.push(r.clone());
+                    outcome.document.tombstones.remove(id);
+                    outcome.document.tasks.insert(id.clone(), r.clone());
+                }
+            }
+            (None, None) => {}
+        }
+    }
+
+    // 清理过期墓碑
+    let cutoff = (now - chrono::Duration::days(TOMBSTONE_TTL_DAYS)).timestamp_millis();
+    outcome
+        .document
+        .tombstones
+        .retain(|_, t| timestamp(&t.deleted_at) >= cutoff);
+
+    outcome
+}
+
+// 逐字段合并同一任务：只有一方改动的字段直接采用；双方都改动时取 updated_at 较新的一方，
+// 并返回 true 表示存在需要记录的冲突
+fn merge_task(base: Option<&Task>, local: &Task, remote: &Task) -> (Task, bool) {
+    let to_map = |task: &Task| match serde_json::to_value(task) {
+        Ok(serde_json::Value::Object(map)) => map,
+        _ => serde_json::Map::new(),
+    };
+    let base_map = base.map(to_map);
+    let local_map = to_map(local);
+    let remote_map = to_map(remote);
+    let local_newer = timestamp(&local.updated_at) >= timestamp(&remote.updated_at);
+
+    let mut merged = if local_newer { local_map.clone() } else { remote_map.clone() };
+    let mut conflicted = false;
+    for field in TASK_FIELDS {
+        let l = local_map.get(*field);
+        let r = remote_map.get(*field);
+        if l == r {
+            continue;
+        }
+        let b = base_map.as_ref().map(|m| m.get(*field));
+        let value = match b {
+            Some(b) if b == l => r,
+            Some(b) if b == r => l,
+            _ => {
+                conflicted = true;
+                if local_newer {
+                    l
+                } else {
+                    r
+                }
+            }
+        };
+        match value {
+            Some(v) => merged.insert(field.to_string(), v.clone()),
+            None => merged.remove(*field),
+        };
+    }
+
+    // 创建时间取较早者，更新时间取较晚者
+    let created_at = if timestamp(&local.created_at) <= timestamp(&remote.created_at) {
+        &local.created_at
+    } else {
+        &remote.created_at
+    };
+    let updated_at = if local_newer { &local.updated_at } else { &remote.updated_at };
+    merged.insert("createdAt".to_string(), created_at.clone().into());
+    merged.insert("updatedAt".to_string(), updated_at.clone().into());
+
+    let merged = serde_json::from_value(serde_json::Value::Object(merged)).unwrap_or_else(|_| {
+        if local_newer {
+            local.clone()
+        } else {
+            remote.clone()
+        }
+    });
+    (merged, conflicted)
+}
+
+fn same_task(a: &Task, b: &Task) -> bool {
+    serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
+}
+
+// 解析 RFC 3339 时间为毫秒时间戳（无法解析时视为最早）
+fn timestamp(value: &str) -> i64 {
+    chrono::DateTime::parse_from_rfc3339(value)
+        .map(|d| d.timestamp_millis())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{task_from_partial, update_task_from_partial};
+
+    const NOW: &str = "2026-10-19T12:00:00Z";
+
+    fn now() -> chrono::DateTime<chrono::Utc> {
+        chrono::DateTime::parse_from_rfc3339(NOW).unwrap().into()
+    }
+
+    fn task(id: &str, title: &str, updated_at: &str) -> Task {
+        task_from_partial(serde_json::json!({
+            "id": id,
+            "title": title,
+            "createdAt": "2026-10-01T00:00:00Z",
+            "updatedAt": updated_at,
+        }))
+        .unwrap()
+    }
+
+    fn with(task: &Task, changes: serde_json::Value, updated_at: &str) -> Task {
+        let mut updated = update_task_from_partial(task, changes).unwrap();
+        updated.updated_at = updated_at.to_string();
+        updated
+    }
+
+    fn tasks(list: &[&Task]) -> BTreeMap<String, Task> {
+        list.iter().map(|t| (t.id.clone(), (*t).clone())).collect()
+    }
+
+    fn tombstone(deleted_at: &str) -> Tombstone {
+        Tombstone { deleted_at: deleted_at.to_string() }
+    }
+
+    fn remote(list: &[&Task], tombstones: &[(&str, &str)]) -> SyncDocument {
+        SyncDocument {
+            version: 1,
+            updated_at: NOW.to_string(),
+            tasks: tasks(list),
+            tombstones: tombstones.iter().map(|(id, at)| (id.to_string(), tombstone(at))).collect(),
+        }
+    }
+
+    #[test]
+    fn tombstone_loses_to_later_local_edit() {
+        let base = task("a", "A", "2026-10-10T00:00:00Z");
+        let local = with(&base, serde_json::json!({ "title": "A2" }), "2026-10-12T00:00:00Z");
+        let outcome = merge(
+            &SyncBase { tasks: tasks(&[&base]) },
+            &tasks(&[&local]),
+            remote(&[], &[("a", "2026-10-11T00:00:00Z")]),
+            now(),
+        );
+
+        assert!(outcome.local_deletes.is_empty());
+        assert_eq!(outcome.document.tasks["a"].title, "A2");
+        assert!(!outcome.document.tombstones.contains_key("a"));
+        assert_eq!(outcome.pushed, 1);
+    }
+
+    #[test]
+    fn tombstone_after_local_edit_deletes_locally() {
+        let base = task("a", "A", "2026-10-10T00:00:00Z");
+        let local = with(&base, serde_json::json!({ "title": "A2" }), "2026-10-12T00:00:00Z");
+        let outcome = merge(
+            &SyncBase { tasks: tasks(&[&base]) },
+            &tasks(&[&local]),
+            remote(&[], &[("a", "2026-10-13T00:00:00Z")]),
+            now(),
+        );
+
+        assert_eq!(outcome.local_deletes, vec!["a".to_string()]);
+        assert!(!outcome.document.tasks.contains_key("a"));
+        assert!(outcome.document.tombstones.contains_key("a"));
+    }
+
+    #[test]
+    fn missing_remotely_without_tombstone() {
+        // 已同步过、本地未改动：视为远端删除
+        let synced = task("synced", "S", "2026-10-10T00:00:00Z");
+        // 已同步过、本地又有改动：推送回远端
+        let edited_base = task("edited", "E", "2026-10-10T00:00:00Z");
+        let edited = with(&edited_base, serde_json::json!({ "title": "E2" }), "2026-10-12T00:00:00Z");
+        // 从未同步过的新任务：推送
+        let fresh = task("fresh", "F", "2026-10-12T00:00:00Z");
+
+        let outcome = merge(
+            &SyncBase { tasks: tasks(&[&synced, &edited_base]) },
+            &tasks(&[&synced, &edited, &fresh]),
+            remote(&[], &[]),
+            now(),
+        );
+
+        assert_eq!(outcome.local_deletes, vec!["synced".to_string()]);
+        let pushed: Vec<&String> = outcome.document.tasks.keys().collect();
+        assert_eq!(pushed, vec!["edited", "fresh"]);
+        assert_eq!(outcome.pushed, 2);
+    }
+
+    #[test]
+    fn local_delete_against_remote_edit() {
+        let base_edited = task("edited", "E", "2026-10-10T00:00:00Z");
+        let remote_edited = with(&base_edited, serde_json::json!({ "title": "E2" }), "2026-10-12T00:00:00Z");
+        let base_same = task("same", "S", "2026-10-10T00:00:00Z");
+
+        let outcome = merge(
+            &SyncBase { tasks: tasks(&[&base_edited, &base_same]) },
+            &BTreeMap::new(),
+            remote(&[&remote_edited, &base_same], &[]),
+            now(),
+        );
+
+        // 远端在本地删除后修改过的任务恢复到本地
+        assert_eq!(outcome.local_upserts.len(), 1);
+        assert_eq!(outcome.local_upserts[0].title, "E2");
+        assert_eq!(outcome.document.tasks["edited"].title, "E2");
+        // 远端未修改的任务：写入墓碑并从文档中移除
+        assert!(!outcome.document.tasks.contains_key("same"));
+        assert_eq!(outcome.document.tombstones["same"].deleted_at, now().to_rfc3339());
+        assert_eq!(outcome.pushed, 1);
+    }
+
+    #[test]
+    fn merges_fields_and_picks_conflict_winner() {
+        let base = task("a", "A", "2026-10-10T00:00:00Z");
+        let local = with(&base, serde_json::json!({ "title": "Local", "priority": "high" }), "2026-10-12T00:00:00Z");
+        let remote_task = with(&base, serde_json::json!({ "title": "Remote", "status": "doing" }), "2026-10-11T00:00:00Z");
+
+        let outcome = merge(
+            &SyncBase { tasks: tasks(&[&base]) },
+            &tasks(&[&local]),
+            remote(&[&remote_task], &[]),
+            now(),
+        );
+
+        let merged = &outcome.document.tasks["a"];
+        // 只有一方改动的字段直接采用
+        assert_eq!(merged.priority, "high");
+        assert_eq!(merged.status, "doing");
+        // 双方都改动的字段取较新的一方，并记录冲突
+        assert_eq!(merged.title, "Local");
+        assert_eq!(merged.updated_at, local.updated_at);
+        assert_eq!(outcome.conflicts.len(), 1);
+        assert_eq!(outcome.conflicts[0].winner, "local");
+        assert_eq!(outcome.conflicts[0].source, "webdav");
+
+        // 远端较新时远端胜出
+        let remote_newer = with(&remote_task, serde_json::json!({}), "2026-10-13T00:00:00Z");
+        let (merged, conflicted) = merge_task(Some(&base), &local, &remote_newer);
+        assert!(conflicted);
+        assert_eq!(merged.title, "Remote");
+        assert_eq!(merged.priority, "high");
+
+        // 没有快照时，不同的字段都视为冲突
+        let (_, conflicted) = merge_task(None, &local, &remote_task);
+        assert!(conflicted);
+        // 只有一方改动时没有冲突
+        let (merged, conflicted) = merge_task(Some(&base), &local, &base);
+        assert!(!conflicted);
+        assert_eq!(merged.title, "Local");
+    }
+
+    #[test]
+    fn prunes_expired_tombstones() {
+        let outcome = merge(
+            &SyncBase::default(),
+            &BTreeMap::new(),
+            remote(
+                &[],
+                &[
+                    ("expired", "2026-07-01T00:00:00Z"),
+                    ("recent", "2026-09-01T00:00:00Z"),
+                    ("invalid", "not a date"),
+                ],
+            ),
+            now(),
+        );
+
+        let kept: Vec<&String> = outcome.document.tombstones.keys().collect();
+        assert_eq!(kept, vec!["recent"]);
+        assert!(outcome.local_deletes.is_empty());
+        assert_eq!(outcome.pushed, 0);
+    }
+}
@@ -0,0 +1,365 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+// 任务数据结构
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Task {
+    pub id: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub completed: bool,
+    pub priority: String,
+    pub status: String,
+    #[serde(alias = "list_id")]
+    pub list_id: String,
+    pub tags: String,
+    #[serde(alias = "sub_tasks")]
+    pub sub_tasks: String,
+    pub reminders: String,
+    #[serde(alias = "due_date")]
+    pub due_date: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+    pub order: i32,
+    // 所属分类
+    #[serde(default, alias = "category_id")]
+    pub category_id: Option<String>,
+    // 软删除（回收站）
+    #[serde(default)]
+    pub deleted: bool,
+    #[serde(default, alias = "deleted_at")]
+    pub deleted_at: Option<String>,
+    // 父任务（为空时是顶层任务）
+    #[serde(default, alias = "parent_id")]
+    pub parent_id: Option<String>,
+    // 预估时长（分钟）
+    #[serde(default, alias = "estimate_minutes")]
+    pub estimate_minutes: Option<i32>,
+    // 故事点
+    #[serde(default, alias = "story_points")]
+    pub story_points: Option<f64>,
+    // 完成时间（未完成时为空）
+    #[serde(default, alias = "completed_at")]
+    pub completed_at: Option<String>,
+    // 是否被未完成的任务阻塞（读取时计算，保存时忽略）
+    #[serde(default, skip_deserializing)]
+    pub blocked: bool,
+}
+
+impl Task {
+    // 整理完成时间：未完成时清空；已完成但没有完成时间时沿用之前保存的值（previous），否则取更新时间
+    pub fn normalize_completed_at(&mut self, previous: Option<String>) {
+        if !self.completed {
+            self.completed_at = None;
+        } else if self.completed_at.is_none() {
+            self.completed_at = previous.or_else(|| Some(self.updated_at.clone()));
+        }
+    }
+
+    // 标签 ID 与名称（tags 为 JSON 数组，无法解析的条目忽略）
+    pub fn tag_entries(&self) -> Vec<(String, Option<String>)> {
+        serde_json::from_str::<Vec<serde_json::Value>>(&self.tags)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|tag| {
+                let id = tag.get("id")?.as_str()?.to_string();
+                let name = tag.get("name").and_then(|n| n.as_str()).map(str::to_string);
+                Some((id, name))
+            })
+            .collect()
+    }
+}
+
+// 子任务（subtasks 表中的一行；Task::sub_tasks 为同一数据的 JSON 副本）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SubTask {
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub completed: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due_date: Option<String>,
+    #[serde(default)]
+    pub order: i32,
+}
+
+// 任务依赖：task_id 被 blocked_by 阻塞，blocked_by 完成前 task_id 不会提醒
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskDependency {
+    pub task_id: String,
+    pub blocked_by: String,
+    pub created_at: String,
+}
+
+// 计时记录（时间为 Unix 时间戳，单位秒）；ended_at 为空表示正在计时，同一时间最多只有一条
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeEntry {
+    pub id: String,
+    pub task_id: String,
+    pub started_at: i64,
+    pub ended_at: Option<i64>,
+    pub note: Option<String>,
+    // 是否为手动补录
+    pub manual: bool,
+    pub created_at: String,
+}
+
+impl TimeEntry {
+    // 时长（秒），正在计时的记录计算到 now
+    pub fn duration(&self, now: i64) -> i64 {
+        (self.ended_at.unwrap_or(now) - self.started_at).max(0)
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.ended_at.is_none()
+    }
+}
+
+// 任务历史：一次字段变更（field 为 created 或字段名，值均为字符串，布尔字段为 true / false）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskEvent {
+    pub id: i64,
+    pub task_id: String,
+    pub field: String,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+    pub created_at: String,
+}
+
+// 完成的番茄（时间为 Unix 时间戳，单位秒）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PomodoroRecord {
+    pub id: String,
+    pub task_id: String,
+    pub started_at: i64,
+    pub ended_at: i64,
+}
+
+// 本地变更记录（用于与 REST 同步服务增量同步）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeRecord {
+    pub seq: i64,
+    pub entity: String,
+    pub entity_id: String,
+    pub op: String,
+    pub changed_at: String,
+}
+
+// 同步或恢复时产生的任务冲突（同时保留双方版本）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskConflict {
+    pub id: String,
+    pub task_id: String,
+    // 冲突来源：webdav / rest / restore
+    pub source: String,
+    pub local: Task,
+    pub remote: Task,
+    // 自动采用的一方：local / remote
+    pub winner: String,
+    pub detected_at: String,
+    pub resolved_at: Option<String>,
+    pub resolution: Option<String>,
+}
+
+// 清单数据结构
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TaskList {
+    pub id: String,
+    pub name: String,
+    pub icon: Option<String>,
+    pub color: Option<String>,
+    pub order: i32,
+    pub created_at: String,
+}
+
+// 默认清单（首次打开存储时创建）
+pub(crate) fn default_lists() -> Vec<TaskList> {
+    let now = chrono::Utc::now().to_rfc3339();
+    [("all", "全部", "Inbox"), ("today", "今天", "Sun"), ("week", "最近7天", "Calendar")]
+        .into_iter()
+        .enumerate()
+        .map(|(order, (id, name, icon))| TaskList {
+            id: id.to_string(),
+            name: name.to_string(),
+            icon: Some(icon.to_string()),
+            color: None,
+            order: order as i32,
+            created_at: now.clone(),
+        })
+        .collect()
+}
+
+// 由部分字段创建任务（未提供的字段使用默认值，供 HTTP 接口使用）
+pub fn task_from_partial(partial: serde_json::Value) -> Result<Task, String> {
+    let now = chrono::Utc::now().to_rfc3339();
+    let defaults = serde_json::json!({
+        "id": uuid::Uuid::new_v4().to_string(),
+        "title": "",
+        "completed": false,
+        "priority": "none",
+        "status": "todo",
+        "listId": "all",
+        "tags": "[]",
+        "subTasks": "[]",
+        "reminders": "[]",
+        "createdAt": now,
+        "updatedAt": now,
+        "order": 0,
+    });
+    let task: Task = merge_partial(defaults, partial)?;
+    if task.title.trim().is_empty() {
+        return Err("Task title is required".to_string());
+    }
+    Ok(task)
+}
+
+// 批量修改中的单个任务：changes 为部分字段（ID 不存在时按 task_from_partial 创建）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskUpdate {
+    pub id: String,
+    pub changes: serde_json::Value,
+}
+
+// 批量修改：更新或创建、按顺序重排（order 依次设为 0, 1, 2...）与永久删除（连同后代）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TaskBatch {
+    pub updates: Vec<TaskUpdate>,
+    pub order: Vec<String>,
+    pub deletes: Vec<String>,
+}
+
+// 计算批量修改需要写入与删除的任务（tasks 为当前所有任务），返回 (写入, 删除)：
+// 删除包含后代；updates 中不存在的任务按部分字段创建；order 中顺序变化的任务一并写入；
+// 父任务必须存在（已保存或在同一批中创建）且没有在同一批中删除
+pub fn batch_changes(tasks: &[Task], batch: &TaskBatch) -> Result<(Vec<Task>, Vec<String>), String> {
+    let stored: HashMap<&str, &Task> = tasks.iter().map(|t| (t.id.as_str(), t)).collect();
+
+    let mut deletes: Vec<String> = Vec::new();
+    for id in batch.deletes.iter().filter(|id| stored.contains_key(id.as_str())) {
+        if deletes.contains(id) {
+            continue;
+        }
+        // 按层级展开后代（层级数据存在环时也能结束）
+        let start = deletes.len();
+        deletes.push(id.clone());
+        let mut index = start;
+        while index < deletes.len() {
+            let parent_id = deletes[index].clone();
+            for task in tasks.iter().filter(|t| t.parent_id.as_deref() == Some(parent_id.as_str())) {
+                if !deletes.contains(&task.id) {
+                    deletes.push(task.id.clone());
+                }
+            }
+            index += 1;
+        }
+    }
+
+    let mut upserts: Vec<Task> = Vec::new();
+    for update in &batch.updates {
+        let existing = match upserts.iter().position(|t| t.id == update.id) {
+            Some(index) => Some(upserts.remove(index)),
+            None => stored.get(update.id.as_str()).map(|t| (*t).clone()),
+        };
+        let task = match existing {
+            Some(existing) => update_task_from_partial(&existing, update.changes.clone())?,
+            None => {
+                let mut changes = update.changes.clone();
+                if let Some(map) = changes.as_object_mut() {
+                    map.insert("id".to_string(), serde_json::Value::String(update.id.clone()));
+                }
+                task_from_partial(changes)?
+            }
+        };
+        upserts.push(task);
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    for (index, id) in batch.order.iter().enumerate() {
+        let order = index as i32;
+        if let Some(task) = upserts.iter_mut().find(|t| t.id == *id) {
+            task.order = order;
+        } else if let Some(task) = stored.get(id.as_str()).filter(|t| t.order != order) {
+            upserts.push(Task { order, updated_at: now.clone(), ..(*task).clone() });
+        }
+    }
+    upserts.retain(|t| !deletes.contains(&t.id));
+
+    for parent_id in upserts.iter().filter_map(|t| t.parent_id.as_ref()) {
+        let exists = stored.contains_key(parent_id.as_str()) || upserts.iter().any(|t| t.id == *parent_id);
+        if !exists || deletes.contains(parent_id) {
+            return Err(format!("Parent task not found: {}", parent_id));
+        }
+    }
+    Ok((upserts, deletes))
+}
+
+// 由部分字段创建清单
+pub fn list_from_partial(partial: serde_json::Value) -> Result<TaskList, String> {
+    let defaults = serde_json::json!({
+        "id": uuid::Uuid::new_v4().to_string(),
+        "name": "",
+        "order": 0,
+        "created_at": chrono::Utc::now().to_rfc3339(),
+    });
+    let list: TaskList = merge_partial(defaults, partial)?;
+    if list.name.trim().is_empty() {
+        return Err("List name is required".to_string());
+    }
+    Ok(list)
+}
+
+// 将部分字段覆盖到已有任务上（id 与创建时间不可修改，更新时间取当前时间）
+pub fn update_task_from_partial(task: &Task, changes: serde_json::Value) -> Result<Task, String> {
+    let base = serde_json::to_value(task).map_err(|e| format!("Failed to serialize task: {}", e))?;
+    let mut updated: Task = merge_partial(base, changes)?;
+    updated.id = task.id.clone();
+    updated.created_at = task.created_at.clone();
+    updated.updated_at = chrono::Utc::now().to_rfc3339();
+    Ok(updated)
+}
+
+// 将部分字段覆盖到已有清单上
+pub fn update_list_from_partial(list: &TaskList, changes: serde_json::Value) -> Result<TaskList, String> {
+    let base = serde_json::to_value(list).map_err(|e| format!("Failed to serialize list: {}", e))?;
+    let mut updated: TaskList = merge_partial(base, changes)?;
+    updated.id = list.id.clone();
+    updated.created_at = list.created_at.clone();
+    Ok(updated)
+}
+
+fn merge_partial<T: serde::de::DeserializeOwned>(base: serde_json::Value, changes: serde_json::Value) -> Result<T, String> {
+    let mut base = match base {
+        serde_json::Value::Object(map) => map,
+        _ => serde_json::Map::new(),
+    };
+    match changes {
+        serde_json::Value::Object(map) => base.extend(map),
+        _ => return Err("Request body must be a JSON object".to_string()),
+    }
+    serde_json::from_value(serde_json::Value::Object(base))
+        .map_err(|e| format!("Invalid request body: {}", e))
+}
+
+// 数据库结构版本（表结构变更时递增，用于备份清单校验）
+pub const SCHEMA_VERSION: u32 = 13;
+
+// 应用标识（与 tauri.conf.json 一致，应用数据目录为 <系统数据目录>/<标识>）
+pub const APP_IDENTIFIER: &str = "com.linfr.my-todo";
+
+// 应用数据目录下的数据库文件
+pub fn db_path_in(app_data_dir: &Path) -> PathBuf {
+    app_data_dir.join("todo.db")
+}
@@ -0,0 +1,280 @@
+use serde::{Deserialize, Serialize};
+
+use crate::model::PomodoroRecord;
+
+// 番茄钟阶段
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PomodoroPhase {
+    #[default]
+    Work,
+    ShortBreak,
+    LongBreak,
+}
+
+// 番茄钟设置（时长单位为分钟）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PomodoroSettings {
+    pub work_minutes: u32,
+    pub short_break_minutes: u32,
+    pub long_break_minutes: u32,
+    // 每完成几个番茄进入一次长休息
+    pub long_break_interval: u32,
+}
+
+impl Default for PomodoroSettings {
+    fn default() -> Self {
+        Self {
+            work_minutes: 25,
+            short_break_minutes: 5,
+            long_break_minutes: 15,
+            long_break_interval: 4,
+        }
+    }
+}
+
+impl PomodoroSettings {
+    // 阶段时长（秒，至少 1 分钟）
+    pub fn phase_seconds(&self, phase: PomodoroPhase) -> i64 {
+        let minutes = match phase {
+            PomodoroPhase::Work => self.work_minutes,
+            PomodoroPhase::ShortBreak => self.short_break_minutes,
+            PomodoroPhase::LongBreak => self.long_break_minutes,
+        };
+        i64::from(minutes.max(1)) * 60
+    }
+}
+
+// 番茄钟状态机（时间为 Unix 时间戳，单位秒）：专注 -> 短休息 / 长休息 -> 专注 ...
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PomodoroState {
+    pub task_id: String,
+    pub phase: PomodoroPhase,
+    pub phase_started_at: i64,
+    pub phase_ends_at: i64,
+    // 暂停时间，为空表示正在进行
+    pub paused_at: Option<i64>,
+    // 本次专注已完成的番茄数
+    pub completed: u32,
+    pub settings: PomodoroSettings,
+}
+
+// 番茄钟当前状态（每秒推送给前端）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PomodoroStatus {
+    pub task_id: String,
+    pub phase: PomodoroPhase,
+    // 当前阶段剩余秒数
+    pub remaining: i64,
+    pub duration: i64,
+    pub paused: bool,
+    pub completed: u32,
+}
+
+// 阶段切换（finished 结束、next 开始）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PomodoroTransition {
+    pub task_id: String,
+    pub finished: PomodoroPhase,
+    pub next: PomodoroPhase,
+    pub started_at: i64,
+    pub ended_at: i64,
+    // 是否为手动跳过（跳过的专注不计入番茄数）
+    pub skipped: bool,
+    pub completed: u32,
+}
+
+impl PomodoroState {
+    // 从专注阶段开始
+    pub fn new(task_id: &str, settings: PomodoroSettings, now: i64) -> PomodoroState {
+        PomodoroState {
+            task_id: task_id.to_string(),
+            phase: PomodoroPhase::Work,
+            phase_started_at: now,
+            phase_ends_at: now + settings.phase_seconds(PomodoroPhase::Work),
+            paused_at: None,
+            completed: 0,
+            settings,
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+
+    // 当前阶段剩余秒数（暂停期间不减少）
+    pub fn remaining(&self, now: i64) -> i64 {
+        (self.phase_ends_at - self.paused_at.unwrap_or(now)).max(0)
+    }
+
+    pub fn status(&self, now: i64) -> PomodoroStatus {
+        PomodoroStatus {
+            task_id: self.task_id.clone(),
+            phase: self.phase,
+            remaining: self.remaining(now),
+            duration: self.settings.phase_seconds(self.phase),
+            paused: self.is_paused(),
+            completed: self.completed,
+        }
+    }
+
+    pub fn pause(&mut self, now: i64) {
+        if self.paused_at.is_none() {
+            self.paused_at = Some(now);
+        }
+    }
+
+    // 继续计时，结束时间顺延暂停的时长
+    pub fn resume(&mut self, now: i64) {
+        if let Some(paused_at) = self.paused_at.take() {
+            self.phase_ends_at += (now - paused_at).max(0);
+        }
+    }
+
+    // 当前阶段到时则切换到下一阶段（暂停时不切换）
+    pub fn tick(&mut self, now: i64) -> Option<PomodoroTransition> {
+        if self.is_paused() || now < self.phase_ends_at {
+            return None;
+        }
+        Some(self.advance(now, false))
+    }
+
+    // 跳过当前阶段
+    pub fn skip(&mut self, now: i64) -> PomodoroTransition {
+        self.paused_at = None;
+        self.advance(now, true)
+    }
+
+    fn advance(&mut self, now: i64, skipped: bool) -> PomodoroTransition {
+        let finished = self.phase;
+        let next = match finished {
+            PomodoroPhase::Work => {
+                if !skipped {
+                    self.completed += 1;
+                }
+                let interval = self.settings.long_break_interval.max(1);
+                if !skipped && self.completed % interval == 0 {
+                    PomodoroPhase::LongBreak
+                } else {
+                    PomodoroPhase::ShortBreak
+                }
+            }
+            PomodoroPhase::ShortBreak | PomodoroPhase::LongBreak => PomodoroPhase::Work,
+        };
+
+        let transition = PomodoroTransition {
+            task_id: self.task_id.clone(),
+            finished,
+            next,
+            started_at: self.phase_started_at,
+            ended_at: now,
+            skipped,
+            completed: self.completed,
+        };
+
+        self.phase = next;
+        self.phase_started_at = now;
+        self.phase_ends_at = now + self.settings.phase_seconds(next);
+        transition
+    }
+}
+
+impl PomodoroTransition {
+    // 完成的专注阶段（跳过的不算）
+    pub fn completed_work(&self) -> bool {
+        self.finished == PomodoroPhase::Work && !self.skipped
+    }
+
+    // 完成的专注阶段对应的番茄记录
+    pub fn record(&self) -> Option<PomodoroRecord> {
+        self.completed_work().then(|| PomodoroRecord {
+            id: uuid::Uuid::new_v4().to_string(),
+            task_id: self.task_id.clone(),
+            started_at: self.started_at,
+            ended_at: self.ended_at,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{PomodoroPhase, PomodoroSettings, PomodoroState};
+    use crate::testing::{save, with_repositories};
+
+    fn settings() -> PomodoroSettings {
+        PomodoroSettings { work_minutes: 25, short_break_minutes: 5, long_break_minutes: 15, long_break_interval: 2 }
+    }
+
+    #[test]
+    fn work_short_break_and_long_break() {
+        let mut state = PomodoroState::new("a", settings(), 0);
+        assert_eq!(state.status(60).remaining, 24 * 60);
+        // 未到时不切换
+        assert!(state.tick(25 * 60 - 1).is_none());
+
+        let first = state.tick(25 * 60).unwrap();
+        assert_eq!((first.finished, first.next, first.completed), (PomodoroPhase::Work, PomodoroPhase::ShortBreak, 1));
+        assert!(first.completed_work());
+        assert_eq!(state.phase_ends_at, 30 * 60);
+
+        let back = state.tick(30 * 60).unwrap();
+        assert_eq!((back.finished, back.next), (PomodoroPhase::ShortBreak, PomodoroPhase::Work));
+        assert!(back.record().is_none());
+
+        // 每完成 long_break_interval 个番茄进入长休息
+        let second = state.tick(55 * 60).unwrap();
+        assert_eq!((second.next, second.completed), (PomodoroPhase::LongBreak, 2));
+        assert_eq!(state.status(55 * 60).duration, 15 * 60);
+    }
+
+    #[test]
+    fn pause_resume_and_skip() {
+        let mut state = PomodoroState::new("a", settings(), 0);
+        state.pause(60);
+        // 暂停期间剩余时间不减少，也不切换阶段
+        assert_eq!(state.remaining(600), 24 * 60);
+        assert!(state.tick(3600).is_none());
+        state.resume(3600);
+        assert_eq!(state.phase_ends_at, 25 * 60 + 3540);
+
+        // 跳过的专注不计入番茄数，也不进入长休息
+        let skipped = state.skip(3700);
+        assert_eq!((skipped.next, skipped.completed), (PomodoroPhase::ShortBreak, 0));
+        assert!(skipped.record().is_none());
+        assert!(!state.is_paused());
+    }
+
+    #[test]
+    fn restart_begins_a_new_work_phase() {
+        let mut state = PomodoroState::new("a", settings(), 0);
+        state.tick(25 * 60).unwrap();
+
+        // 停止后重新开始：从专注阶段开始，番茄数清零
+        let state = PomodoroState::new("b", settings(), 2000);
+        let status = state.status(2000);
+        assert_eq!((status.task_id.as_str(), status.phase, status.completed), ("b", PomodoroPhase::Work, 0));
+        assert_eq!(status.remaining, 25 * 60);
+        assert!(!status.paused);
+    }
+
+    #[test]
+    fn finished_work_is_logged_to_the_task() {
+        with_repositories(|repo| {
+            save(repo, "a", json!({}));
+            let mut state = PomodoroState::new("a", settings(), 1000);
+            let record = state.tick(1000 + 25 * 60).unwrap().record().unwrap();
+            repo.log_pomodoro(&record).unwrap();
+
+            let logged = repo.pomodoros(Some("a")).unwrap();
+            assert_eq!(logged.len(), 1);
+            assert_eq!((logged[0].started_at, logged[0].ended_at), (1000, 1000 + 25 * 60));
+            assert!(repo.pomodoros(Some("other")).unwrap().is_empty());
+        });
+    }
+}
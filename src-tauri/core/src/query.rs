@@ -0,0 +1,302 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+use crate::model::Task;
+
+// 任务查询条件（未设置的条件不参与筛选），SQLite 与内存存储的筛选、排序语义一致
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TaskQuery {
+    // 清单 ID（"all" 表示不限清单）
+    pub list_id: Option<String>,
+    pub category_id: Option<String>,
+    // 标签 ID，包含任意一个即匹配
+    pub tags: Vec<String>,
+    // 优先级集合，为空时不限
+    pub priorities: Vec<String>,
+    // 状态集合，为空时不限
+    pub statuses: Vec<String>,
+    // 截止时间范围（RFC 3339，包含 dueFrom、不包含 dueTo），设置后没有截止日期的任务不会返回
+    pub due_from: Option<String>,
+    pub due_to: Option<String>,
+    // 仅返回有（true）或没有（false）截止日期的任务
+    pub has_due_date: Option<bool>,
+    pub completed: Option<bool>,
+    // 仅返回被阻塞（true）或未被阻塞（false）的任务
+    pub blocked: Option<bool>,
+    // false 只返回未删除的任务，true 只返回回收站中的任务
+    pub deleted: bool,
+    // 在标题与描述中查找（不区分大小写）
+    pub text: Option<String>,
+    pub sort: TaskSort,
+    pub descending: bool,
+    // 分页，未设置 limit 时返回全部
+    pub limit: Option<u32>,
+    pub offset: u32,
+}
+
+// 排序字段，相同时按 order 升序、创建时间降序
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TaskSort {
+    #[default]
+    Order,
+    // 没有截止日期的任务排在最后
+    DueDate,
+    Priority,
+    CreatedAt,
+    UpdatedAt,
+    Title,
+}
+
+// 查询结果（total 为分页前的总数）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskPage {
+    pub tasks: Vec<Task>,
+    pub total: i64,
+}
+
+impl TaskQuery {
+    // 截止时间范围转换为时间戳
+    pub fn due_range(&self) -> Result<(Option<i64>, Option<i64>), String> {
+        let parse = |value: &Option<String>| {
+            value
+                .as_deref()
+                .map(|v| due_timestamp(v).ok_or_else(|| format!("Invalid due date: {}", v)))
+                .transpose()
+        };
+        Ok((parse(&self.due_from)?, parse(&self.due_to)?))
+    }
+
+    // 去掉首尾空白后的搜索文本
+    pub fn search_text(&self) -> Option<&str> {
+        self.text.as_deref().map(str::trim).filter(|t| !t.is_empty())
+    }
+
+    // 清单筛选（"all" 视为不限）
+    pub fn list_filter(&self) -> Option<&str> {
+        self.list_id.as_deref().filter(|l| *l != "all")
+    }
+
+    // 判断任务是否满足筛选条件（due_range 为 due_range() 的结果）
+    pub fn matches(&self, task: &Task, due_range: (Option<i64>, Option<i64>)) -> bool {
+        let due = task.due_date.as_deref().and_then(due_timestamp);
+        let (due_from, due_to) = due_range;
+
+        task.deleted == self.deleted
+            && self.list_filter().is_none_or(|l| task.list_id == l)
+            && self.category_id.as_ref().is_none_or(|c| task.category_id.as_ref() == Some(c))
+            && (self.tags.is_empty() || task_tag_ids(task).iter().any(|t| self.tags.contains(t)))
+            && (self.priorities.is_empty() || self.priorities.contains(&task.priority))
+            && (self.statuses.is_empty() || self.statuses.contains(&task.status))
+            && due_from.is_none_or(|from| due.is_some_and(|d| d >= from))
+            && due_to.is_none_or(|to| due.is_some_and(|d| d < to))
+            && self.has_due_date.is_none_or(|has| task.due_date.is_some() == has)
+            && self.completed.is_none_or(|c| task.completed == c)
+            && self.blocked.is_none_or(|b| task.blocked == b)
+            && self.search_text().is_none_or(|text| {
+                let text = text.to_lowercase();
+                task.title.to_lowercase().contains(&text)
+                    || task.description.as_deref().is_some_and(|d| d.to_lowercase().contains(&text))
+            })
+    }
+
+    // 按查询的排序方式排序
+    pub fn sort_tasks(&self, tasks: &mut [Task]) {
+        tasks.sort_by(|a, b| {
+            let primary = match self.sort {
+                TaskSort::Order => a.order.cmp(&b.order),
+                TaskSort::DueDate => {
+                    let a_due = a.due_date.as_deref().and_then(due_timestamp);
+                    let b_due = b.due_date.as_deref().and_then(due_timestamp);
+                    // 没有截止日期的任务始终排在最后（不受 descending 影响）
+                    match (a_due, b_due) {
+                        (Some(a), Some(b)) => a.cmp(&b),
+                        (Some(_), None) => return Ordering::Less,
+                        (None, Some(_)) => return Ordering::Greater,
+                        (None, None) => Ordering::Equal,
+                    }
+                }
+                TaskSort::Priority => priority_rank(&a.priority).cmp(&priority_rank(&b.priority)),
+                TaskSort::CreatedAt => a.created_at.cmp(&b.created_at),
+                TaskSort::UpdatedAt => a.updated_at.cmp(&b.updated_at),
+                TaskSort::Title => a.title.to_lowercase().cmp(&b.title.to_lowercase()),
+            };
+            let primary = if self.descending { primary.reverse() } else { primary };
+            primary
+                .then_with(|| a.order.cmp(&b.order))
+                .then_with(|| b.created_at.cmp(&a.created_at))
+        });
+    }
+
+    // 对已排序的结果分页
+    pub fn paginate(&self, tasks: Vec<Task>) -> Vec<Task> {
+        let limit = self.limit.map(|l| l as usize).unwrap_or(usize::MAX);
+        tasks.into_iter().skip(self.offset as usize).take(limit).collect()
+    }
+}
+
+// 截止日期转换为时间戳（无法解析时返回 None）
+pub fn due_timestamp(due_date: &str) -> Option<i64> {
+    chrono::DateTime::parse_from_rfc3339(due_date).ok().map(|d| d.timestamp())
+}
+
+// 优先级排序权重（none < low < medium < high）
+pub fn priority_rank(priority: &str) -> i32 {
+    match priority {
+        "low" => 1,
+        "medium" => 2,
+        "high" => 3,
+        _ => 0,
+    }
+}
+
+// 任务的标签 ID 列表
+fn task_tag_ids(task: &Task) -> Vec<String> {
+    task.tag_entries().into_iter().map(|(id, _)| id).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{TaskQuery, TaskSort};
+    use crate::repository::TaskRepository;
+    use crate::testing::{save, with_repositories};
+
+    // 保存一组覆盖各筛选与排序字段的任务
+    fn seed(repo: &dyn TaskRepository) {
+        save(repo, "a", json!({
+            "title": "Alpha report", "listId": "work", "categoryId": "c1", "priority": "high", "status": "todo",
+            "dueDate": "2024-03-01T09:00:00Z", "tags": "[{\"id\":\"t1\",\"name\":\"urgent\"}]", "order": 2,
+            "description": "quarterly numbers", "createdAt": "2024-01-01T00:00:00Z", "updatedAt": "2024-01-05T00:00:00Z",
+        }));
+        save(repo, "b", json!({
+            "title": "beta", "listId": "work", "priority": "low", "status": "in-progress",
+            "dueDate": "2024-03-10T09:00:00Z", "tags": "[{\"id\":\"t2\"}]", "order": 0,
+            "createdAt": "2024-01-02T00:00:00Z", "updatedAt": "2024-01-04T00:00:00Z",
+        }));
+        save(repo, "c", json!({
+            "title": "Gamma", "listId": "home", "priority": "medium", "status": "done", "completed": true, "order": 1,
+            "createdAt": "2024-01-03T00:00:00Z", "updatedAt": "2024-01-03T00:00:00Z",
+        }));
+        save(repo, "d", json!({
+            "title": "deleted", "listId": "work", "deleted": true, "deletedAt": "2024-01-06T00:00:00Z",
+            "createdAt": "2024-01-04T00:00:00Z", "updatedAt": "2024-01-06T00:00:00Z",
+        }));
+    }
+
+    fn ids(repo: &dyn TaskRepository, query: TaskQuery) -> Vec<String> {
+        repo.query_tasks(&query).unwrap().tasks.into_iter().map(|t| t.id).collect()
+    }
+
+    // 不关心顺序时排序后比较
+    fn sorted_ids(repo: &dyn TaskRepository, query: TaskQuery) -> Vec<String> {
+        let mut ids = ids(repo, query);
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn filters() {
+        with_repositories(|repo| {
+            seed(repo);
+            let q = TaskQuery::default;
+
+            assert_eq!(sorted_ids(repo, q()), ["a", "b", "c"]);
+            assert_eq!(sorted_ids(repo, TaskQuery { list_id: Some("work".into()), ..q() }), ["a", "b"]);
+            assert_eq!(sorted_ids(repo, TaskQuery { list_id: Some("all".into()), ..q() }), ["a", "b", "c"]);
+            assert_eq!(sorted_ids(repo, TaskQuery { category_id: Some("c1".into()), ..q() }), ["a"]);
+            assert_eq!(sorted_ids(repo, TaskQuery { tags: vec!["t2".into(), "x".into()], ..q() }), ["b"]);
+            assert_eq!(sorted_ids(repo, TaskQuery { priorities: vec!["high".into(), "medium".into()], ..q() }), ["a", "c"]);
+            assert_eq!(sorted_ids(repo, TaskQuery { statuses: vec!["in-progress".into()], ..q() }), ["b"]);
+            assert_eq!(sorted_ids(repo, TaskQuery { has_due_date: Some(false), ..q() }), ["c"]);
+            assert_eq!(sorted_ids(repo, TaskQuery { has_due_date: Some(true), ..q() }), ["a", "b"]);
+            assert_eq!(sorted_ids(repo, TaskQuery { completed: Some(true), ..q() }), ["c"]);
+            assert_eq!(sorted_ids(repo, TaskQuery { completed: Some(false), ..q() }), ["a", "b"]);
+            // 标题与描述，不区分大小写
+            assert_eq!(sorted_ids(repo, TaskQuery { text: Some(" ALPHA ".into()), ..q() }), ["a"]);
+            assert_eq!(sorted_ids(repo, TaskQuery { text: Some("Quarterly".into()), ..q() }), ["a"]);
+            assert_eq!(sorted_ids(repo, TaskQuery { text: Some("%".into()), ..q() }), Vec::<String>::new());
+        });
+    }
+
+    #[test]
+    fn due_range_includes_start_and_excludes_end() {
+        with_repositories(|repo| {
+            seed(repo);
+            let range = |from: &str, to: &str| TaskQuery {
+                due_from: Some(from.into()),
+                due_to: Some(to.into()),
+                ..TaskQuery::default()
+            };
+
+            assert_eq!(sorted_ids(repo, range("2024-03-01T09:00:00Z", "2024-03-10T09:00:00Z")), ["a"]);
+            assert_eq!(sorted_ids(repo, range("2024-03-01T09:00:01Z", "2024-03-10T09:00:01Z")), ["b"]);
+            assert!(repo.query_tasks(&TaskQuery { due_from: Some("soon".into()), ..TaskQuery::default() }).is_err());
+        });
+    }
+
+    #[test]
+    fn blocked_filter() {
+        with_repositories(|repo| {
+            seed(repo);
+            repo.add_task_dependency("a", "b").unwrap();
+            // 已完成的任务不再阻塞
+            repo.add_task_dependency("b", "c").unwrap();
+
+            assert_eq!(sorted_ids(repo, TaskQuery { blocked: Some(true), ..TaskQuery::default() }), ["a"]);
+            assert_eq!(sorted_ids(repo, TaskQuery { blocked: Some(false), ..TaskQuery::default() }), ["b", "c"]);
+        });
+    }
+
+    #[test]
+    fn deleted_tasks_only_in_trash() {
+        with_repositories(|repo| {
+            seed(repo);
+            assert!(!ids(repo, TaskQuery::default()).contains(&"d".to_string()));
+            assert_eq!(ids(repo, TaskQuery { deleted: true, ..TaskQuery::default() }), ["d"]);
+            assert_eq!(repo.query_tasks(&TaskQuery { deleted: true, ..TaskQuery::default() }).unwrap().total, 1);
+        });
+    }
+
+    #[test]
+    fn sorts() {
+        with_repositories(|repo| {
+            seed(repo);
+            let sorted = |sort: TaskSort, descending: bool| ids(repo, TaskQuery { sort, descending, ..TaskQuery::default() });
+
+            assert_eq!(sorted(TaskSort::Order, false), ["b", "c", "a"]);
+            assert_eq!(sorted(TaskSort::Order, true), ["a", "c", "b"]);
+            // 没有截止日期的任务在两个方向上都排在最后
+            assert_eq!(sorted(TaskSort::DueDate, false), ["a", "b", "c"]);
+            assert_eq!(sorted(TaskSort::DueDate, true), ["b", "a", "c"]);
+            assert_eq!(sorted(TaskSort::Priority, false), ["b", "c", "a"]);
+            assert_eq!(sorted(TaskSort::Priority, true), ["a", "c", "b"]);
+            assert_eq!(sorted(TaskSort::CreatedAt, false), ["a", "b", "c"]);
+            assert_eq!(sorted(TaskSort::CreatedAt, true), ["c", "b", "a"]);
+            assert_eq!(sorted(TaskSort::UpdatedAt, false), ["c", "b", "a"]);
+            // 标题不区分大小写
+            assert_eq!(sorted(TaskSort::Title, false), ["a", "b", "c"]);
+            assert_eq!(sorted(TaskSort::Title, true), ["c", "b", "a"]);
+        });
+    }
+
+    #[test]
+    fn pagination_boundaries() {
+        with_repositories(|repo| {
+            seed(repo);
+            let page = |limit: Option<u32>, offset: u32| {
+                let page = repo.query_tasks(&TaskQuery { limit, offset, ..TaskQuery::default() }).unwrap();
+                (page.tasks.into_iter().map(|t| t.id).collect::<Vec<_>>(), page.total)
+            };
+
+            assert_eq!(page(Some(2), 0), (vec!["b".to_string(), "c".to_string()], 3));
+            assert_eq!(page(Some(2), 2), (vec!["a".to_string()], 3));
+            assert_eq!(page(Some(2), 3), (vec![], 3));
+            assert_eq!(page(Some(0), 0), (vec![], 3));
+            assert_eq!(page(None, 1), (vec!["c".to_string(), "a".to_string()], 3));
+        });
+    }
+}
@@ -0,0 +1,128 @@
+use serde::{Deserialize, Serialize};
+
+use crate::model::Task;
+use crate::repository::TaskRepository;
+
+// 提醒任务结构
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReminderTask {
+    pub id: String,
+    pub task_id: String,
+    pub task_title: String,
+    pub reminder_time: i64,
+    pub repeat: String,
+    pub sent: bool,
+}
+
+// 解析任务的提醒设置，返回 (提醒时间戳, 重复方式)，无法解析的条目会被忽略
+pub fn task_reminder_times(task: &Task) -> Vec<(i64, String)> {
+    let Ok(reminders) = serde_json::from_str::<Vec<serde_json::Value>>(&task.reminders) else {
+        return Vec::new();
+    };
+
+    reminders
+        .iter()
+        .filter_map(|reminder| {
+            let date = reminder.get("date").and_then(|d| d.as_str())?;
+            let timestamp = chrono::DateTime::parse_from_rfc3339(date).ok()?.timestamp();
+            let repeat = reminder.get("repeat").and_then(|r| r.as_str()).unwrap_or("none");
+            Some((timestamp, repeat.to_string()))
+        })
+        .collect()
+}
+
+// 找出已到期但尚未发送的提醒（跳过已完成、已删除与被阻塞的任务，阻塞解除后再提醒）
+pub fn due_reminders(repo: &dyn TaskRepository, now: i64) -> Result<Vec<ReminderTask>, String> {
+    let mut due = Vec::new();
+
+    for task in repo.tasks()?.iter().filter(|t| !t.completed && !t.deleted && !t.blocked) {
+        for (reminder_time, repeat) in task_reminder_times(task) {
+            if reminder_time > now || repo.is_reminder_sent(&task.id, reminder_time)? {
+                continue;
+            }
+            due.push(ReminderTask {
+                id: uuid::Uuid::new_v4().to_string(),
+                task_id: task.id.clone(),
+                task_title: task.title.clone(),
+                reminder_time,
+                repeat,
+                sent: false,
+            });
+        }
+    }
+
+    Ok(due)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::MemoryStore;
+    use crate::model::task_from_partial;
+
+    const NOW: i64 = 1_790_000_000;
+
+    fn reminder_at(timestamp: i64, repeat: &str) -> String {
+        let date = chrono::DateTime::from_timestamp(timestamp, 0).unwrap().to_rfc3339();
+        serde_json::json!([{ "date": date, "repeat": repeat }]).to_string()
+    }
+
+    fn save(repo: &MemoryStore, id: &str, extra: serde_json::Value) {
+        let mut task = serde_json::json!({ "id": id, "title": id, "reminders": reminder_at(NOW - 60, "daily") });
+        task.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+        repo.save_task(&task_from_partial(task).unwrap()).unwrap();
+    }
+
+    fn due_ids(repo: &MemoryStore) -> Vec<String> {
+        let mut ids: Vec<String> = due_reminders(repo, NOW).unwrap().into_iter().map(|r| r.task_id).collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn returns_past_reminders_with_repeat() {
+        let repo = MemoryStore::new();
+        save(&repo, "due", serde_json::json!({}));
+        save(&repo, "future", serde_json::json!({ "reminders": reminder_at(NOW + 60, "none") }));
+        save(&repo, "invalid", serde_json::json!({ "reminders": "not json" }));
+
+        let due = due_reminders(&repo, NOW).unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].task_id, "due");
+        assert_eq!(due[0].reminder_time, NOW - 60);
+        assert_eq!(due[0].repeat, "daily");
+        assert!(!due[0].sent);
+    }
+
+    #[test]
+    fn skips_completed_and_deleted_tasks() {
+        let repo = MemoryStore::new();
+        save(&repo, "open", serde_json::json!({}));
+        save(&repo, "completed", serde_json::json!({ "completed": true, "status": "done" }));
+        save(&repo, "deleted", serde_json::json!({ "deleted": true }));
+
+        assert_eq!(due_ids(&repo), vec!["open"]);
+    }
+
+    #[test]
+    fn skips_blocked_tasks_until_unblocked() {
+        let repo = MemoryStore::new();
+        save(&repo, "blocker", serde_json::json!({ "reminders": "[]" }));
+        save(&repo, "blocked", serde_json::json!({}));
+        repo.add_task_dependency("blocked", "blocker").unwrap();
+        assert!(due_ids(&repo).is_empty());
+
+        repo.set_task_completed("blocker", true).unwrap();
+        assert_eq!(due_ids(&repo), vec!["blocked"]);
+    }
+
+    #[test]
+    fn skips_already_notified_reminders() {
+        let repo = MemoryStore::new();
+        save(&repo, "notified", serde_json::json!({}));
+        save(&repo, "pending", serde_json::json!({}));
+        repo.save_sent_reminder("r1", "notified", NOW - 60, "{}").unwrap();
+
+        assert_eq!(due_ids(&repo), vec!["pending"]);
+    }
+}
@@ -0,0 +1,695 @@
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+use crate::estimates::{build_estimate_report, EstimateReport};
+use crate::hierarchy::{build_forest, creates_cycle, TaskNode};
+use crate::model::{
+    batch_changes, task_from_partial, ChangeRecord, PomodoroRecord, SubTask, Task, TaskBatch, TaskConflict,
+    TaskDependency, TaskEvent, TaskList, TimeEntry,
+};
+use crate::query::{TaskPage, TaskQuery};
+use crate::search::SearchResult;
+use crate::statistics::{compute_statistics, Statistics, StatisticsQuery};
+use crate::subtasks::{normalize_subtasks, parse_subtasks, subtasks_json, TaskProgress};
+use crate::time_tracking::{manual_entry, TimeGroup, TimeTotal};
+use crate::undo::{TaskSnapshot, UndoEntry, UndoState};
+
+// 任务数据存储接口（SQLite 与内存实现），桌面端在启动时选择具体实现
+pub trait TaskRepository: Send + Sync {
+    // 确保存储结构完整（例如数据库文件被备份替换后补齐缺少的表）
+    fn init(&self) -> Result<(), String>;
+    // 数据库文件路径（用于备份与恢复），内存存储没有数据库文件
+    fn database_path(&self) -> Option<PathBuf> {
+        None
+    }
+
+    // 在单个事务内执行 f：f 通过传入的 repo 进行的读写要么全部生效，要么（f 返回错误时）全部回滚；
+    // 执行期间其他写入等待（SQLite 为 BEGIN IMMEDIATE，内存实现持有锁）
+    fn transaction(&self, f: &mut dyn FnMut(&dyn TaskRepository) -> Result<(), String>) -> Result<(), String>;
+
+    // ========== 任务 ==========
+
+    // 获取所有任务（按 order 升序、创建时间降序）
+    fn tasks(&self) -> Result<Vec<Task>, String>;
+    // 获取单个任务
+    fn task(&self, id: &str) -> Result<Option<Task>, String>;
+    // 按条件筛选、排序并分页
+    fn query_tasks(&self, query: &TaskQuery) -> Result<TaskPage, String>;
+    // 全文搜索标题、描述与子任务标题（按相关度排序，不含回收站中的任务）
+    fn search_tasks(&self, text: &str, limit: usize) -> Result<Vec<SearchResult>, String>;
+    // 保存任务（记录变更）；父任务必须存在且不能形成环
+    fn save_task(&self, task: &Task) -> Result<(), String>;
+    // 删除任务及其所有后代（单次写入，同时删除相关的提醒与子任务记录）
+    fn delete_task(&self, id: &str) -> Result<(), String>;
+    // 批量写入与删除（单次写入，删除时一并删除后代）；record 为 false 时不写入变更记录（用于应用 REST 服务下发的变更，避免回传）
+    // 父任务不存在（可在同一批中写入）或形成环时返回错误且不写入；同步数据应通过 apply_synced_changes 写入
+    fn apply_task_changes(&self, upserts: &[Task], deletes: &[String], record: bool) -> Result<(), String>;
+
+    // 任务历史（按时间顺序）：保存任务时记录标题、状态、优先级、截止日期、清单、完成与删除的变更，任务删除时一并删除
+    fn task_events(&self, task_id: &str) -> Result<Vec<TaskEvent>, String>;
+
+    // ========== 撤销与重做 ==========
+
+    // 写入撤销日志（清空可重做的操作，最多保留 UNDO_LIMIT 条）；一般通过 undo::undoable 调用
+    fn log_operation(&self, label: &str, before: &[TaskSnapshot], after: &[TaskSnapshot]) -> Result<(), String>;
+    // 撤销最近的 steps 个操作（单个事务内按从新到旧写回操作前的状态），返回被撤销的操作；
+    // 任务在操作之后又被修改时返回冲突错误且不写回，操作保留在日志中
+    fn undo(&self, steps: usize) -> Result<Vec<UndoEntry>, String>;
+    // 重做最近撤销的 steps 个操作（按从旧到新写回操作后的状态），返回被重做的操作
+    fn redo(&self, steps: usize) -> Result<Vec<UndoEntry>, String>;
+    // 撤销 / 重做栈状态
+    fn undo_state(&self) -> Result<UndoState, String>;
+
+    // ========== 任务层级 ==========
+
+    // 任务的所有后代（不含自身）
+    fn descendants(&self, id: &str) -> Result<Vec<Task>, String>;
+    // 任务的所有祖先（从父任务到根）
+    fn ancestors(&self, id: &str) -> Result<Vec<Task>, String>;
+
+    // 任务树：指定 root_id 时返回以该任务为根的树，否则返回所有未删除任务组成的森林
+    fn task_tree(&self, root_id: Option<&str>) -> Result<Vec<TaskNode>, String> {
+        let tasks = match root_id {
+            Some(id) => {
+                let root = require_task(self, id)?;
+                let mut tasks = vec![root.clone()];
+                tasks.extend(self.descendants(id)?.into_iter().filter(|t| t.deleted == root.deleted));
+                tasks
+            }
+            None => self.tasks()?.into_iter().filter(|t| !t.deleted).collect(),
+        };
+        Ok(build_forest(tasks))
+    }
+
+    // 移动任务及其子树：指定 parent_id 时移到该任务下并使用其清单，否则成为顶层任务（可同时指定清单）
+    fn move_task(&self, id: &str, parent_id: Option<&str>, list_id: Option<&str>) -> Result<Vec<Task>, String> {
+        let mut task = require_task(self, id)?;
+        let list_id = match parent_id {
+            Some(parent_id) => Some(check_parent(self, id, parent_id)?.list_id),
+            None => list_id.map(str::to_string),
+        };
+        task.parent_id = parent_id.map(str::to_string);
+
+        let mut changed = vec![task];
+        if let Some(list_id) = list_id {
+            changed.extend(self.descendants(id)?);
+            for t in &mut changed {
+                t.list_id = list_id.clone();
+            }
+        }
+        save_all(self, changed)
+    }
+
+    // 按给定顺序重排任务（order 依次设为 0, 1, 2...），返回 order 有变化的任务；不存在的 ID 被忽略
+    fn reorder_tasks(&self, ids: &[String]) -> Result<Vec<Task>, String> {
+        let mut changed = Vec::new();
+        for (index, id) in ids.iter().enumerate() {
+            if let Some(task) = self.task(id)?.filter(|t| t.order != index as i32) {
+                changed.push(Task { order: index as i32, ..task });
+            }
+        }
+        save_all(self, changed)
+    }
+
+    // 设置完成状态：完成时连同所有后代一起完成，取消完成时连同已完成的祖先一起恢复
+    fn set_task_completed(&self, id: &str, completed: bool) -> Result<Vec<Task>, String> {
+        let mut changed = vec![require_task(self, id)?];
+        if completed {
+            changed.extend(self.descendants(id)?.into_iter().filter(|t| !t.completed));
+        } else {
+            changed.extend(self.ancestors(id)?.into_iter().filter(|t| t.completed));
+        }
+        for t in &mut changed {
+            t.completed = completed;
+            if completed {
+                t.status = "done".to_string();
+            } else if t.status == "done" {
+                t.status = "todo".to_string();
+            }
+        }
+        save_all(self, changed)
+    }
+
+    // 移到回收站（连同所有后代）
+    fn trash_task(&self, id: &str) -> Result<Vec<Task>, String> {
+        let now = chrono::Utc::now().to_rfc3339();
+        let mut changed = vec![require_task(self, id)?];
+        changed.extend(self.descendants(id)?.into_iter().filter(|t| !t.deleted));
+        for t in &mut changed {
+            t.deleted = true;
+            t.deleted_at = Some(now.clone());
+        }
+        save_all(self, changed)
+    }
+
+    // 从回收站恢复（连同后代，以及同在回收站中的祖先，保证恢复后可见）
+    fn restore_task(&self, id: &str) -> Result<Vec<Task>, String> {
+        let mut changed = vec![require_task(self, id)?];
+        changed.extend(self.descendants(id)?.into_iter().filter(|t| t.deleted));
+        changed.extend(self.ancestors(id)?.into_iter().filter(|t| t.deleted));
+        for t in &mut changed {
+            t.deleted = false;
+            t.deleted_at = None;
+        }
+        save_all(self, changed)
+    }
+
+    // 永久删除任务及其所有后代，返回被删除的任务 ID
+    fn delete_task_tree(&self, id: &str) -> Result<Vec<String>, String> {
+        let mut ids = vec![require_task(self, id)?.id];
+        ids.extend(self.descendants(id)?.into_iter().map(|t| t.id));
+        self.apply_task_changes(&[], &ids, true)?;
+        Ok(ids)
+    }
+
+    // 写入同步下发的变更：父任务不存在或会形成环时清除父任务关系（父任务已在本地删除、多端同时移动任务时可能出现），
+    // 其余同 apply_task_changes
+    fn apply_synced_changes(&self, upserts: &[Task], deletes: &[String], record: bool) -> Result<(), String> {
+        let mut parents: HashMap<String, Option<String>> =
+            self.tasks()?.into_iter().map(|t| (t.id, t.parent_id)).collect();
+        let upsert_ids: HashSet<&str> = upserts.iter().map(|t| t.id.as_str()).collect();
+        let upserts: Vec<Task> = upserts
+            .iter()
+            .map(|task| {
+                let parent_of = |id: &str| parents.get(id).cloned().flatten();
+                let parent_id = task.parent_id.clone().filter(|p| {
+                    (parents.contains_key(p) || upsert_ids.contains(p.as_str())) && !creates_cycle(&task.id, p, parent_of)
+                });
+                parents.insert(task.id.clone(), parent_id.clone());
+                Task { parent_id, ..task.clone() }
+            })
+            .collect();
+        self.apply_task_changes(&upserts, deletes, record)
+    }
+
+    // 批量修改：先应用 updates，再按 order 重排，最后删除 deletes 中的任务及其后代；
+    // 全部在一次批量写入中完成（SQLite 为单个事务，任一项失败时都不写入），返回写入后的任务
+    fn batch_update_tasks(&self, batch: &TaskBatch) -> Result<Vec<Task>, String> {
+        let (upserts, deletes) = batch_changes(&self.tasks()?, batch)?;
+        self.apply_task_changes(&upserts, &deletes, true)?;
+        let ids: HashSet<&str> = upserts.iter().map(|t| t.id.as_str()).collect();
+        Ok(self.tasks()?.into_iter().filter(|t| ids.contains(t.id.as_str())).collect())
+    }
+
+    // ========== 任务依赖 ==========
+
+    // 依赖关系；指定 task_id 时只返回该任务阻塞或被阻塞的关系
+    fn task_dependencies(&self, task_id: Option<&str>) -> Result<Vec<TaskDependency>, String>;
+    // 添加依赖：task_id 被 blocked_by 阻塞（两个任务都必须存在，且不能形成环；已存在时返回原记录）
+    fn add_task_dependency(&self, task_id: &str, blocked_by: &str) -> Result<TaskDependency, String>;
+    // 删除依赖（返回是否存在）
+    fn remove_task_dependency(&self, task_id: &str, blocked_by: &str) -> Result<bool, String>;
+
+    // ========== 计时 ==========
+    // 时间均为 Unix 时间戳（秒），同一时间最多只有一条正在计时的记录；任务删除时其计时与番茄记录一并删除
+
+    // 计时记录（按开始时间倒序），指定 task_id 时只返回该任务的
+    fn time_entries(&self, task_id: Option<&str>) -> Result<Vec<TimeEntry>, String>;
+    // 正在计时的记录
+    fn running_timer(&self) -> Result<Option<TimeEntry>, String>;
+    // 开始为任务计时（先在 now 停止正在进行的其他计时；该任务已在计时时返回原记录）
+    fn start_timer(&self, task_id: &str, now: i64) -> Result<TimeEntry, String>;
+    // 在 now 停止计时，返回停止的记录（没有正在计时的记录时返回 None）
+    fn stop_timer(&self, now: i64) -> Result<Option<TimeEntry>, String>;
+    // 保存已结束的计时记录（任务必须存在）
+    fn add_time_entry(&self, entry: &TimeEntry) -> Result<(), String>;
+    // 按任务或清单汇总时长，正在计时的记录计算到 now
+    fn time_totals(&self, group: TimeGroup, now: i64) -> Result<Vec<TimeTotal>, String>;
+
+    // 番茄记录（按开始时间倒序），指定 task_id 时只返回该任务的
+    fn pomodoros(&self, task_id: Option<&str>) -> Result<Vec<PomodoroRecord>, String>;
+    // 记录完成的番茄（任务必须存在）
+    fn log_pomodoro(&self, record: &PomodoroRecord) -> Result<(), String>;
+
+    // 预估与实际用时对比（按清单、标签与周统计）
+    fn estimate_report(&self, now: i64) -> Result<EstimateReport, String> {
+        Ok(build_estimate_report(&self.tasks()?, &self.time_entries(None)?, now))
+    }
+
+    // 效率统计（完成趋势、完成耗时、逾期率、连续天数与分组统计）
+    fn statistics(&self, query: &StatisticsQuery, now: i64) -> Result<Statistics, String> {
+        compute_statistics(&self.tasks()?, query, now)
+    }
+
+    // 手动补录一段时间
+    fn add_manual_entry(&self, task_id: &str, started_at: i64, ended_at: i64, note: Option<String>) -> Result<TimeEntry, String> {
+        let entry = manual_entry(task_id, started_at, ended_at, note)?;
+        self.add_time_entry(&entry)?;
+        Ok(entry)
+    }
+
+    // ========== 子任务 ==========
+    // 保存任务时按 sub_tasks 整理子任务（记录完成时间、重新编号），下面的默认实现都通过 save_task 写入
+
+    // 子任务所属任务的 ID
+    fn subtask_parent(&self, subtask_id: &str) -> Result<Option<String>, String>;
+    // 子任务完成进度（只包含有子任务且未删除的任务）
+    fn subtask_progress(&self) -> Result<Vec<TaskProgress>, String>;
+
+    // 获取任务的子任务（按 order 排序）
+    fn subtasks(&self, task_id: &str) -> Result<Vec<SubTask>, String> {
+        Ok(parse_subtasks(&require_task(self, task_id)?.sub_tasks))
+    }
+
+    // 在末尾添加子任务
+    fn add_subtask(&self, task_id: &str, title: &str, due_date: Option<String>) -> Result<SubTask, String> {
+        let title = title.trim();
+        if title.is_empty() {
+            return Err("Subtask title is required".to_string());
+        }
+
+        let mut task = require_task(self, task_id)?;
+        let mut subtasks = parse_subtasks(&task.sub_tasks);
+        let id = uuid::Uuid::new_v4().to_string();
+        subtasks.push(SubTask {
+            id: id.clone(),
+            title: title.to_string(),
+            completed: false,
+            completed_at: None,
+            due_date,
+            order: 0,
+        });
+
+        let subtasks = save_subtasks(self, &mut task, subtasks)?;
+        find_subtask(subtasks, &id)
+    }
+
+    // 切换子任务完成状态
+    fn toggle_subtask(&self, subtask_id: &str) -> Result<SubTask, String> {
+        let mut task = require_subtask_parent(self, subtask_id)?;
+        let mut subtasks = parse_subtasks(&task.sub_tasks);
+        if let Some(sub) = subtasks.iter_mut().find(|s| s.id == subtask_id) {
+            sub.completed = !sub.completed;
+        }
+
+        let subtasks = save_subtasks(self, &mut task, subtasks)?;
+        find_subtask(subtasks, subtask_id)
+    }
+
+    // 按给定 id 顺序重排子任务（未列出的子任务保持原顺序排在后面）
+    fn reorder_subtasks(&self, task_id: &str, ids: &[String]) -> Result<Vec<SubTask>, String> {
+        let mut task = require_task(self, task_id)?;
+        let mut remaining = parse_subtasks(&task.sub_tasks);
+        let mut ordered = Vec::with_capacity(remaining.len());
+        for id in ids {
+            let index = remaining
+                .iter()
+                .position(|s| &s.id == id)
+                .ok_or_else(|| format!("Subtask not found: {}", id))?;
+            ordered.push(remaining.remove(index));
+        }
+        ordered.extend(remaining);
+
+        save_subtasks(self, &mut task, ordered)
+    }
+
+    // 将子任务提升为独立任务（沿用父任务的清单与分类），并从父任务中移除
+    fn promote_subtask(&self, subtask_id: &str) -> Result<Task, String> {
+        let mut parent = require_subtask_parent(self, subtask_id)?;
+        let mut subtasks = parse_subtasks(&parent.sub_tasks);
+        let index = subtasks
+            .iter()
+            .position(|s| s.id == subtask_id)
+            .ok_or_else(|| format!("Subtask not found: {}", subtask_id))?;
+        let sub = subtasks.remove(index);
+
+        let task = task_from_partial(serde_json::json!({
+            "title": sub.title,
+            "completed": sub.completed,
+            "status": if sub.completed { "done" } else { "todo" },
+            "listId": parent.list_id,
+            "categoryId": parent.category_id,
+            "dueDate": sub.due_date,
+        }))?;
+        self.save_task(&task)?;
+        save_subtasks(self, &mut parent, subtasks)?;
+        Ok(task)
+    }
+
+    // ========== 清单 ==========
+
+    // 获取所有清单（按 order 升序）
+    fn lists(&self) -> Result<Vec<TaskList>, String>;
+    // 保存清单
+    fn save_list(&self, list: &TaskList) -> Result<(), String>;
+    // 删除清单（返回是否存在）
+    fn delete_list(&self, id: &str) -> Result<bool, String>;
+    // 批量写入与删除清单（单次写入）；record 为 false 时不写入变更记录（用于应用 REST 服务下发的变更，避免回传）
+    fn apply_list_changes(&self, upserts: &[TaskList], deletes: &[String], record: bool) -> Result<(), String>;
+    // 统计任务与清单数量
+    fn count_rows(&self) -> Result<(i64, i64), String>;
+
+    // ========== 提醒记录 ==========
+
+    // 检查提醒是否已发送
+    fn is_reminder_sent(&self, task_id: &str, reminder_time: i64) -> Result<bool, String>;
+    // 记录已发送的提醒
+    fn save_sent_reminder(&self, id: &str, task_id: &str, reminder_time: i64, reminder_data: &str) -> Result<(), String>;
+    // 清理 30 天前的提醒记录
+    fn cleanup_old_reminders(&self) -> Result<(), String>;
+    // 删除任务的提醒记录
+    fn delete_task_reminders(&self, task_id: &str) -> Result<(), String>;
+
+    // ========== 变更记录与同步状态 ==========
+
+    // 获取 seq 大于指定值的变更
+    fn changes_since(&self, after_seq: i64) -> Result<Vec<ChangeRecord>, String>;
+    // 获取当前最大的变更序号
+    fn max_change_seq(&self) -> Result<i64, String>;
+    // 读取同步状态
+    fn sync_state(&self, key: &str) -> Result<Option<String>, String>;
+    // 写入同步状态
+    fn set_sync_state(&self, key: &str, value: &str) -> Result<(), String>;
+
+    // ========== 任务冲突 ==========
+
+    // 记录冲突（同一任务的未解决冲突只保留最新一条）
+    fn save_task_conflicts(&self, conflicts: &[TaskConflict]) -> Result<(), String>;
+    // 获取冲突列表（按检测时间倒序，默认只返回未解决的）
+    fn task_conflicts(&self, include_resolved: bool) -> Result<Vec<TaskConflict>, String>;
+    // 获取单个冲突
+    fn task_conflict(&self, id: &str) -> Result<Option<TaskConflict>, String>;
+    // 标记冲突已解决
+    fn resolve_task_conflict(&self, id: &str, resolution: &str) -> Result<(), String>;
+}
+
+fn require_task<R: TaskRepository + ?Sized>(repo: &R, task_id: &str) -> Result<Task, String> {
+    repo.task(task_id)?.ok_or_else(|| format!("Task not found: {}", task_id))
+}
+
+// 检查能否把任务移到 parent_id 下（父任务存在且不是任务自身或其后代），返回父任务
+fn check_parent<R: TaskRepository + ?Sized>(repo: &R, task_id: &str, parent_id: &str) -> Result<Task, String> {
+    if parent_id == task_id {
+        return Err("A task cannot be its own parent".to_string());
+    }
+    let parent = repo.task(parent_id)?.ok_or_else(|| format!("Parent task not found: {}", parent_id))?;
+    if repo.ancestors(parent_id)?.iter().any(|t| t.id == task_id) {
+        return Err("Cannot move a task under its own descendant".to_string());
+    }
+    Ok(parent)
+}
+
+// 更新修改时间后在一次批量写入中保存
+fn save_all<R: TaskRepository + ?Sized>(repo: &R, mut tasks: Vec<Task>) -> Result<Vec<Task>, String> {
+    let now = chrono::Utc::now().to_rfc3339();
+    for t in &mut tasks {
+        t.updated_at = now.clone();
+    }
+    repo.apply_task_changes(&tasks, &[], true)?;
+    Ok(tasks)
+}
+
+fn require_subtask_parent<R: TaskRepository + ?Sized>(repo: &R, subtask_id: &str) -> Result<Task, String> {
+    let task_id = repo
+        .subtask_parent(subtask_id)?
+        .ok_or_else(|| format!("Subtask not found: {}", subtask_id))?;
+    require_task(repo, &task_id)
+}
+
+// 写回子任务并更新任务的修改时间，返回整理后的子任务
+fn save_subtasks<R: TaskRepository + ?Sized>(repo: &R, task: &mut Task, subtasks: Vec<SubTask>) -> Result<Vec<SubTask>, String> {
+    let subtasks = normalize_subtasks(&parse_subtasks(&task.sub_tasks), subtasks);
+    task.sub_tasks = subtasks_json(&subtasks);
+    task.updated_at = chrono::Utc::now().to_rfc3339();
+    repo.save_task(task)?;
+    Ok(subtasks)
+}
+
+fn find_subtask(subtasks: Vec<SubTask>, id: &str) -> Result<SubTask, String> {
+    subtasks
+        .into_iter()
+        .find(|s| s.id == id)
+        .ok_or_else(|| format!("Subtask not found: {}", id))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::testing::{save, task, with_repositories};
+
+    #[test]
+    fn delete_task_removes_descendants() {
+        with_repositories(|repo| {
+            save(repo, "root", json!({}));
+            save(repo, "child", json!({ "parentId": "root" }));
+            save(repo, "grandchild", json!({ "parentId": "child" }));
+            save(repo, "other", json!({}));
+
+            repo.delete_task("root").unwrap();
+
+            let ids: Vec<String> = repo.tasks().unwrap().into_iter().map(|t| t.id).collect();
+            assert_eq!(ids, vec!["other".to_string()]);
+            let deleted: Vec<String> = repo
+                .changes_since(0)
+                .unwrap()
+                .into_iter()
+                .filter(|c| c.op == "delete")
+                .map(|c| c.entity_id)
+                .collect();
+            assert_eq!(deleted.len(), 3);
+            for id in ["root", "child", "grandchild"] {
+                assert!(deleted.contains(&id.to_string()));
+            }
+        });
+    }
+
+    #[test]
+    fn apply_task_changes_rejects_cycles_without_writing() {
+        with_repositories(|repo| {
+            save(repo, "a", json!({}));
+            save(repo, "b", json!({ "parentId": "a" }));
+
+            let moved = task("a", json!({ "parentId": "b", "title": "moved" }));
+            let created = task("c", json!({}));
+            let err = repo.apply_task_changes(&[created, moved], &[], true).unwrap_err();
+            assert!(err.contains("its own descendant"));
+
+            assert!(repo.task("c").unwrap().is_none());
+            let a = repo.task("a").unwrap().unwrap();
+            assert_eq!(a.title, "a");
+            assert_eq!(a.parent_id, None);
+        });
+    }
+
+    #[test]
+    fn batch_update_rejects_cycles() {
+        with_repositories(|repo| {
+            save(repo, "a", json!({}));
+            save(repo, "b", json!({ "parentId": "a" }));
+
+            let batch = serde_json::from_value(json!({
+                "updates": [{ "id": "a", "changes": { "parentId": "b" } }]
+            }))
+            .unwrap();
+            assert!(repo.batch_update_tasks(&batch).is_err());
+            assert_eq!(repo.task("a").unwrap().unwrap().parent_id, None);
+        });
+    }
+
+    #[test]
+    fn apply_synced_changes_detaches_cycles() {
+        with_repositories(|repo| {
+            save(repo, "a", json!({}));
+            save(repo, "b", json!({ "parentId": "a" }));
+
+            // 另一端把 a 移到了 b 下
+            repo.apply_synced_changes(&[task("a", json!({ "parentId": "b" }))], &[], false).unwrap();
+
+            assert_eq!(repo.task("a").unwrap().unwrap().parent_id, None);
+            assert_eq!(repo.task("b").unwrap().unwrap().parent_id.as_deref(), Some("a"));
+        });
+    }
+
+    #[test]
+    fn apply_task_changes_cascades_deletes_and_checks_parents() {
+        with_repositories(|repo| {
+            save(repo, "a", json!({}));
+            save(repo, "b", json!({ "parentId": "a" }));
+            save(repo, "c", json!({ "parentId": "b" }));
+
+            // 父任务不存在时不写入
+            let err = repo.apply_task_changes(&[task("d", json!({})), task("e", json!({ "parentId": "missing" }))], &[], true);
+            assert!(err.unwrap_err().contains("Parent task not found"));
+            assert!(repo.task("d").unwrap().is_none());
+
+            // 父任务可以在同一批中稍后写入
+            repo.apply_task_changes(&[task("g", json!({ "parentId": "f" })), task("f", json!({}))], &[], true).unwrap();
+            assert_eq!(repo.task("g").unwrap().unwrap().parent_id.as_deref(), Some("f"));
+
+            // 删除时一并删除后代，并为每个任务记录删除变更
+            repo.apply_task_changes(&[], &["a".to_string()], true).unwrap();
+            for id in ["a", "b", "c"] {
+                assert!(repo.task(id).unwrap().is_none());
+            }
+            let changes = repo.changes_since(0).unwrap();
+            for id in ["a", "b", "c"] {
+                assert!(changes.iter().any(|c| c.entity_id == id && c.op == "delete"));
+            }
+        });
+    }
+
+    #[test]
+    fn apply_synced_changes_detaches_missing_parents() {
+        with_repositories(|repo| {
+            repo.apply_synced_changes(&[task("a", json!({ "parentId": "deleted" }))], &[], false).unwrap();
+            assert_eq!(repo.task("a").unwrap().unwrap().parent_id, None);
+        });
+    }
+
+    #[test]
+    fn apply_list_changes_without_recording() {
+        with_repositories(|repo| {
+            let list = crate::TaskList {
+                id: "l".to_string(),
+                name: "Remote".to_string(),
+                icon: None,
+                color: None,
+                order: 0,
+                created_at: "2024-01-01T00:00:00Z".to_string(),
+            };
+            let before = repo.max_change_seq().unwrap();
+
+            repo.apply_list_changes(std::slice::from_ref(&list), &[], false).unwrap();
+            assert!(repo.lists().unwrap().iter().any(|l| l.id == "l" && l.name == "Remote"));
+            repo.apply_list_changes(&[], &["l".to_string()], false).unwrap();
+            assert!(repo.lists().unwrap().iter().all(|l| l.id != "l"));
+            assert_eq!(repo.max_change_seq().unwrap(), before);
+
+            repo.apply_list_changes(&[list], &[], true).unwrap();
+            assert!(repo.changes_since(before).unwrap().iter().any(|c| c.entity == "list" && c.entity_id == "l"));
+        });
+    }
+
+    #[test]
+    fn dependencies_reject_self_and_cycles() {
+        with_repositories(|repo| {
+            for id in ["a", "b", "c"] {
+                save(repo, id, json!({}));
+            }
+            repo.add_task_dependency("b", "a").unwrap();
+            repo.add_task_dependency("c", "b").unwrap();
+
+            assert!(repo.add_task_dependency("a", "a").unwrap_err().contains("cannot block itself"));
+            assert!(repo.add_task_dependency("a", "b").unwrap_err().contains("cycle"));
+            // 间接环：a 被 c 阻塞，而 c 经 b 被 a 阻塞
+            assert!(repo.add_task_dependency("a", "c").unwrap_err().contains("cycle"));
+            assert!(repo.add_task_dependency("a", "missing").unwrap_err().contains("not found"));
+            // 重复添加返回已有依赖
+            repo.add_task_dependency("b", "a").unwrap();
+            assert_eq!(repo.task_dependencies(None).unwrap().len(), 2);
+
+            // 去掉 b -> a 后环不再存在
+            assert!(repo.remove_task_dependency("b", "a").unwrap());
+            repo.add_task_dependency("a", "c").unwrap();
+        });
+    }
+
+    #[test]
+    fn blocked_until_blockers_are_completed_or_deleted() {
+        with_repositories(|repo| {
+            save(repo, "blocker", json!({}));
+            save(repo, "other", json!({}));
+            save(repo, "task", json!({}));
+            repo.add_task_dependency("task", "blocker").unwrap();
+            repo.add_task_dependency("task", "other").unwrap();
+            assert!(repo.task("task").unwrap().unwrap().blocked);
+
+            repo.set_task_completed("blocker", true).unwrap();
+            assert!(repo.task("task").unwrap().unwrap().blocked);
+            repo.trash_task("other").unwrap();
+            assert!(!repo.task("task").unwrap().unwrap().blocked);
+
+            repo.set_task_completed("blocker", false).unwrap();
+            assert!(repo.task("task").unwrap().unwrap().blocked);
+            repo.delete_task("blocker").unwrap();
+            assert!(!repo.task("task").unwrap().unwrap().blocked);
+            assert!(repo.task_dependencies(Some("task")).unwrap().iter().all(|d| d.blocked_by == "other"));
+        });
+    }
+
+    fn batch(value: serde_json::Value) -> crate::model::TaskBatch {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn batch_update_applies_updates_order_and_deletes() {
+        with_repositories(|repo| {
+            save(repo, "a", json!({ "order": 0 }));
+            save(repo, "b", json!({ "order": 1 }));
+            save(repo, "gone", json!({}));
+            save(repo, "gone-child", json!({ "parentId": "gone" }));
+
+            let saved = repo
+                .batch_update_tasks(&batch(json!({
+                    "updates": [
+                        { "id": "a", "changes": { "status": "done" } },
+                        { "id": "new", "changes": { "title": "created in batch", "parentId": "a" } }
+                    ],
+                    "order": ["b", "a"],
+                    "deletes": ["gone"]
+                })))
+                .unwrap();
+
+            let mut ids: Vec<&str> = saved.iter().map(|t| t.id.as_str()).collect();
+            ids.sort();
+            assert_eq!(ids, vec!["a", "b", "new"]);
+            let a = repo.task("a").unwrap().unwrap();
+            assert_eq!((a.status.as_str(), a.order), ("done", 1));
+            assert_eq!(repo.task("b").unwrap().unwrap().order, 0);
+            assert_eq!(repo.task("new").unwrap().unwrap().parent_id.as_deref(), Some("a"));
+            assert!(repo.task("gone").unwrap().is_none());
+            assert!(repo.task("gone-child").unwrap().is_none());
+        });
+    }
+
+    #[test]
+    fn batch_update_writes_nothing_when_one_item_fails() {
+        with_repositories(|repo| {
+            save(repo, "a", json!({ "order": 0 }));
+            save(repo, "b", json!({ "order": 1, "parentId": "a" }));
+            save(repo, "c", json!({ "order": 2 }));
+            let before = repo.tasks().unwrap();
+            let seq = repo.max_change_seq().unwrap();
+
+            let failures = [
+                // 父任务不存在
+                json!({ "updates": [{ "id": "a", "changes": { "title": "x" } }, { "id": "c", "changes": { "parentId": "missing" } }] }),
+                // 父任务在同一批中删除
+                json!({ "updates": [{ "id": "c", "changes": { "parentId": "a" } }], "order": ["c", "a"], "deletes": ["a"] }),
+                // 字段类型错误
+                json!({ "updates": [{ "id": "a", "changes": { "title": "x" } }, { "id": "c", "changes": { "completed": "yes" } }] }),
+                // 写入过程中才发现的环（前面的任务已写入事务）
+                json!({ "updates": [{ "id": "new", "changes": { "title": "x" } }, { "id": "a", "changes": { "parentId": "b" } }], "order": ["c", "b"] }),
+            ];
+            for failure in failures {
+                assert!(repo.batch_update_tasks(&batch(failure.clone())).is_err(), "{}", failure);
+                let after = repo.tasks().unwrap();
+                assert_eq!(serde_json::to_value(&after).unwrap(), serde_json::to_value(&before).unwrap(), "{}", failure);
+                assert_eq!(repo.max_change_seq().unwrap(), seq);
+            }
+        });
+    }
+
+    #[test]
+    fn undoable_batch_is_a_single_undo_step() {
+        with_repositories(|repo| {
+            save(repo, "a", json!({ "order": 0 }));
+            save(repo, "b", json!({ "order": 1 }));
+            let request = batch(json!({
+                "updates": [{ "id": "a", "changes": { "status": "inProgress" } }],
+                "order": ["b", "a"],
+                "deletes": ["b"]
+            }));
+            let ids = crate::undo::batch_ids(repo, &request).unwrap();
+            crate::undo::undoable(repo, "batch", &ids, |repo| repo.batch_update_tasks(&request)).unwrap();
+            assert!(repo.task("b").unwrap().is_none());
+            assert_eq!(repo.undo_state().unwrap().undo_count, 1);
+
+            repo.undo(1).unwrap();
+            let a = repo.task("a").unwrap().unwrap();
+            assert_eq!((a.status.as_str(), a.order), ("todo", 0));
+            assert_eq!(repo.task("b").unwrap().unwrap().order, 1);
+        });
+    }
+}
@@ -0,0 +1,207 @@
+use serde::{Deserialize, Serialize};
+
+use crate::model::Task;
+
+// 高亮标记（文本已做 HTML 转义，可直接作为 HTML 渲染）
+pub const HIGHLIGHT_START: &str = "<mark>";
+pub const HIGHLIGHT_END: &str = "</mark>";
+
+// 摘要截取的前后字符数
+const SNIPPET_CONTEXT: usize = 24;
+
+// 全文搜索结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResult {
+    pub task: Task,
+    // 相关度（越大越相关）
+    pub score: f64,
+    // 高亮后的标题
+    pub title: String,
+    // 描述或子任务中命中位置附近的高亮摘要（仅标题命中时为空）
+    pub snippet: Option<String>,
+}
+
+// 拆分搜索词（按空白分隔，全部命中才算匹配）
+pub fn search_terms(text: &str) -> Vec<String> {
+    text.split_whitespace().map(str::to_string).collect()
+}
+
+// 子任务标题（每行一个），与任务标题、描述一起参与搜索
+pub fn subtask_titles(task: &Task) -> String {
+    serde_json::from_str::<Vec<serde_json::Value>>(&task.sub_tasks)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|sub| sub.get("title").and_then(|t| t.as_str()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// 内存存储使用的相关度：所有搜索词都命中时按出现次数加权（标题 > 描述 > 子任务），否则返回 None
+pub fn match_score(task: &Task, terms: &[String]) -> Option<f64> {
+    let fields = [
+        (task.title.to_lowercase(), 10.0),
+        (task.description.as_deref().unwrap_or_default().to_lowercase(), 4.0),
+        (subtask_titles(task).to_lowercase(), 2.0),
+    ];
+    let mut score = 0.0;
+    for term in terms {
+        let term = term.to_lowercase();
+        let hits: f64 = fields.iter().map(|(text, weight)| text.matches(&term).count() as f64 * weight).sum();
+        if hits == 0.0 {
+            return None;
+        }
+        score += hits;
+    }
+    Some(score)
+}
+
+// 由任务与搜索词生成结果（高亮标题，并从描述或子任务中截取摘要）
+pub fn search_result(task: Task, terms: &[String], score: f64) -> SearchResult {
+    let title = highlight(&task.title, terms);
+    let snippet = [task.description.clone().unwrap_or_default(), subtask_titles(&task)]
+        .iter()
+        .find_map(|text| snippet(text, terms));
+    SearchResult { task, score, title, snippet }
+}
+
+// 高亮文本中出现的搜索词（不区分大小写）
+pub fn highlight(text: &str, terms: &[String]) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let marked = match_mask(&chars, terms);
+
+    let mut out = String::new();
+    let mut in_mark = false;
+    for (c, mark) in chars.iter().zip(&marked) {
+        if *mark != in_mark {
+            out.push_str(if *mark { HIGHLIGHT_START } else { HIGHLIGHT_END });
+            in_mark = *mark;
+        }
+        push_escaped(&mut out, *c);
+    }
+    if in_mark {
+        out.push_str(HIGHLIGHT_END);
+    }
+    out
+}
+
+// 截取第一个命中位置附近的文本并高亮，未命中时返回 None
+pub fn snippet(text: &str, terms: &[String]) -> Option<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let first = match_mask(&chars, terms).iter().position(|m| *m)?;
+    let start = first.saturating_sub(SNIPPET_CONTEXT);
+    let end = (first + SNIPPET_CONTEXT * 2).min(chars.len());
+
+    let window: String = chars[start..end].iter().map(|c| if *c == '\n' { ' ' } else { *c }).collect();
+    let prefix = if start > 0 { "…" } else { "" };
+    let suffix = if end < chars.len() { "…" } else { "" };
+    Some(format!("{}{}{}", prefix, highlight(window.trim(), terms), suffix))
+}
+
+// 标记每个字符是否位于某个搜索词的命中范围内
+fn match_mask(chars: &[char], terms: &[String]) -> Vec<bool> {
+    let lower: Vec<char> = chars.iter().map(|c| c.to_lowercase().next().unwrap_or(*c)).collect();
+    let mut marked = vec![false; chars.len()];
+    for term in terms {
+        let term: Vec<char> = term.chars().map(|c| c.to_lowercase().next().unwrap_or(c)).collect();
+        if term.is_empty() || term.len() > lower.len() {
+            continue;
+        }
+        for i in 0..=lower.len() - term.len() {
+            if lower[i..i + term.len()] == term[..] {
+                marked[i..i + term.len()].iter_mut().for_each(|m| *m = true);
+            }
+        }
+    }
+    marked
+}
+
+fn push_escaped(out: &mut String, c: char) {
+    match c {
+        '&' => out.push_str("&amp;"),
+        '<' => out.push_str("&lt;"),
+        '>' => out.push_str("&gt;"),
+        '"' => out.push_str("&quot;"),
+        _ => out.push(c),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing::{save, with_repositories};
+
+    fn terms(text: &str) -> Vec<String> {
+        search_terms(text)
+    }
+
+    fn ids(results: &[SearchResult]) -> Vec<&str> {
+        results.iter().map(|r| r.task.id.as_str()).collect()
+    }
+
+    #[test]
+    fn highlights_case_insensitively_and_escapes_html() {
+        assert_eq!(highlight("Review <b>Budget</b>", &terms("budget")), "Review &lt;b&gt;<mark>Budget</mark>&lt;/b&gt;");
+        assert_eq!(highlight("买牛奶和面包", &terms("牛奶 面包")), "买<mark>牛奶</mark>和<mark>面包</mark>");
+        // 相邻的命中合并为一段
+        assert_eq!(highlight("abcd", &terms("ab cd")), "<mark>abcd</mark>");
+    }
+
+    #[test]
+    fn snippet_keeps_context_around_first_match() {
+        let text = format!("{}needle{}", "a".repeat(40), "b".repeat(60));
+        let window = snippet(&text, &terms("needle")).unwrap();
+        assert!(window.starts_with('…') && window.ends_with('…'));
+        assert!(window.contains(&format!("{}<mark>needle</mark>", "a".repeat(SNIPPET_CONTEXT))));
+
+        assert_eq!(snippet("line one\nneedle", &terms("needle")).unwrap(), "line one <mark>needle</mark>");
+        assert!(snippet("nothing here", &terms("needle")).is_none());
+    }
+
+    #[test]
+    fn ranks_title_above_description_above_subtasks() {
+        with_repositories(|repo| {
+            save(repo, "subtask", json!({ "title": "other", "subTasks": r#"[{"id":"s1","title":"quarterly report","completed":false}]"# }));
+            save(repo, "description", json!({ "title": "other", "description": "draft the quarterly report" }));
+            save(repo, "title", json!({ "title": "Quarterly report" }));
+            save(repo, "unrelated", json!({ "title": "groceries" }));
+
+            let results = repo.search_tasks("quarterly report", 10).unwrap();
+            assert_eq!(ids(&results), vec!["title", "description", "subtask"]);
+            assert!(results.windows(2).all(|w| w[0].score >= w[1].score));
+
+            assert_eq!(results[0].title, "<mark>Quarterly</mark> <mark>report</mark>");
+            assert!(results[0].snippet.is_none());
+            assert_eq!(results[1].snippet.as_deref(), Some("draft the <mark>quarterly</mark> <mark>report</mark>"));
+            assert_eq!(results[2].snippet.as_deref(), Some("<mark>quarterly</mark> <mark>report</mark>"));
+        });
+    }
+
+    #[test]
+    fn requires_every_term_and_skips_deleted_tasks() {
+        with_repositories(|repo| {
+            save(repo, "both", json!({ "title": "pay rent", "description": "before friday" }));
+            save(repo, "one", json!({ "title": "pay taxes" }));
+            save(repo, "trashed", json!({ "title": "pay rent friday", "deleted": true }));
+
+            assert_eq!(ids(&repo.search_tasks("pay friday", 10).unwrap()), vec!["both"]);
+            assert_eq!(repo.search_tasks("pay", 1).unwrap().len(), 1);
+            assert!(repo.search_tasks("   ", 10).unwrap().is_empty());
+        });
+    }
+
+    #[test]
+    fn matches_short_chinese_terms() {
+        with_repositories(|repo| {
+            save(repo, "milk", json!({ "title": "买牛奶" }));
+            save(repo, "bread", json!({ "title": "买面包", "description": "全麦牛奶面包" }));
+
+            let results = repo.search_tasks("牛奶", 10).unwrap();
+            assert_eq!(ids(&results), vec!["milk", "bread"]);
+            assert_eq!(results[0].title, "买<mark>牛奶</mark>");
+            assert_eq!(results[1].snippet.as_deref(), Some("全麦<mark>牛奶</mark>面包"));
+        });
+    }
+}
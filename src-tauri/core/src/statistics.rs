@@ -0,0 +1,358 @@
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::estimates::iso_week_of;
+use crate::model::Task;
+
+// 统计条件：时间范围为 RFC 3339（包含 from、不包含 to），默认最近 30 天；
+// 按天、周统计时使用 utc_offset_minutes 指定的时区（前端传入 -new Date().getTimezoneOffset()）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct StatisticsQuery {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub utc_offset_minutes: i32,
+    // 清单 ID（"all" 或为空表示全部清单）
+    pub list_id: Option<String>,
+}
+
+// 某天或某周完成的任务数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeriodCount {
+    // 日期（2026-10-19）或 ISO 周（2026-W42）
+    pub period: String,
+    pub completed: i64,
+}
+
+// 按优先级、清单或标签分组的统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatisticsGroup {
+    pub key: String,
+    // 标签名称（仅按标签分组时有值）
+    pub name: Option<String>,
+    pub completed: i64,
+    pub average_completion_hours: Option<f64>,
+}
+
+// 效率统计（只统计未删除的任务，完成相关的数据以 completed_at 落在范围内为准）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Statistics {
+    pub from: String,
+    pub to: String,
+    pub created: i64,
+    pub completed: i64,
+    // 每天完成数（范围内每天一条，没有完成的为 0）
+    pub completed_per_day: Vec<PeriodCount>,
+    pub completed_per_week: Vec<PeriodCount>,
+    // 从创建到完成的平均小时数
+    pub average_completion_hours: Option<f64>,
+    // 截止时间在范围内且已过期的任务中，逾期完成或仍未完成的比例
+    pub due: i64,
+    pub overdue: i64,
+    pub overdue_rate: Option<f64>,
+    // 连续有完成任务的天数（当前连续截止到范围最后一天，当天还没有完成时从前一天算起）
+    pub current_streak: i64,
+    pub longest_streak: i64,
+    pub by_priority: Vec<StatisticsGroup>,
+    pub by_list: Vec<StatisticsGroup>,
+    pub by_tag: Vec<StatisticsGroup>,
+}
+
+// 范围内单个分组的累计值
+#[derive(Default)]
+struct GroupAcc {
+    name: Option<String>,
+    completed: i64,
+    hours: Vec<f64>,
+}
+
+// 由任务计算统计（now 为当前时间戳，用于默认范围与逾期判断）
+pub fn compute_statistics(tasks: &[Task], query: &StatisticsQuery, now: i64) -> Result<Statistics, String> {
+    let offset = FixedOffset::east_opt(query.utc_offset_minutes.clamp(-14 * 60, 14 * 60) * 60)
+        .ok_or_else(|| "Invalid UTC offset".to_string())?;
+    let parse = |value: &Option<String>, name: &str| {
+        value
+            .as_deref()
+            .map(|v| parse_time(v).map(|t| t.timestamp()).ok_or_else(|| format!("Invalid {} date: {}", name, v)))
+            .transpose()
+    };
+    let to = parse(&query.to, "to")?.unwrap_or(now);
+    let from = parse(&query.from, "from")?.unwrap_or(to - 30 * 86400);
+    if from >= to {
+        return Err("Statistics range is empty".to_string());
+    }
+    let in_range = |t: i64| t >= from && t < to;
+    let local_date = |t: i64| offset.timestamp_opt(t, 0).single().map(|d| d.date_naive());
+
+    let tasks: Vec<&Task> = tasks
+        .iter()
+        .filter(|t| !t.deleted)
+        .filter(|t| query.list_id.as_deref().filter(|l| *l != "all").is_none_or(|l| t.list_id == l))
+        .collect();
+
+    let mut created = 0;
+    let mut completed_hours = Vec::new();
+    let mut per_day: BTreeMap<NaiveDate, i64> = BTreeMap::new();
+    let mut per_week: BTreeMap<String, i64> = BTreeMap::new();
+    let mut by_priority: HashMap<String, GroupAcc> = HashMap::new();
+    let mut by_list: HashMap<String, GroupAcc> = HashMap::new();
+    let mut by_tag: HashMap<String, GroupAcc> = HashMap::new();
+    let (mut due, mut overdue) = (0, 0);
+
+    for task in &tasks {
+        let created_at = parse_time(&task.created_at).map(|t| t.timestamp());
+        if created_at.is_some_and(in_range) {
+            created += 1;
+        }
+
+        let completed_at = task
+            .completed_at
+            .as_deref()
+            .filter(|_| task.completed)
+            .and_then(parse_time)
+            .map(|t| t.timestamp());
+
+        // 截止时间在范围内且已经过去的任务：逾期完成或仍未完成即为逾期
+        if let Some(due_at) = task.due_date.as_deref().and_then(parse_time).map(|t| t.timestamp()) {
+            if in_range(due_at) && due_at < now {
+                due += 1;
+                if completed_at.is_none_or(|c| c > due_at) {
+                    overdue += 1;
+                }
+            }
+        }
+
+        let Some(completed_at) = completed_at.filter(|c| in_range(*c)) else {
+            continue;
+        };
+        let hours = created_at.map(|c| (completed_at - c).max(0) as f64 / 3600.0);
+        completed_hours.extend(hours);
+
+        if let Some(date) = local_date(completed_at) {
+            *per_day.entry(date).or_default() += 1;
+            *per_week.entry(iso_week_of(date)).or_default() += 1;
+        }
+
+        let add = |groups: &mut HashMap<String, GroupAcc>, key: String, name: Option<String>| {
+            let acc = groups.entry(key).or_default();
+            acc.name = acc.name.take().or(name);
+            acc.completed += 1;
+            acc.hours.extend(hours);
+        };
+        add(&mut by_priority, task.priority.clone(), None);
+        add(&mut by_list, task.list_id.clone(), None);
+        for (id, name) in task.tag_entries() {
+            add(&mut by_tag, id, name);
+        }
+    }
+
+    // 范围内每一天（包含没有完成任务的天）
+    let first_day = local_date(from).ok_or_else(|| "Invalid from date".to_string())?;
+    let last_day = local_date(to - 1).ok_or_else(|| "Invalid to date".to_string())?;
+    let days: Vec<NaiveDate> = first_day.iter_days().take_while(|d| *d <= last_day).collect();
+    let completed_per_day = days
+        .iter()
+        .map(|d| PeriodCount {
+            period: d.format("%Y-%m-%d").to_string(),
+            completed: per_day.get(d).copied().unwrap_or_default(),
+        })
+        .collect();
+    let weeks: BTreeSet<String> = days.iter().map(|d| iso_week_of(*d)).collect();
+    let completed_per_week = weeks
+        .into_iter()
+        .map(|w| PeriodCount { completed: per_week.get(&w).copied().unwrap_or_default(), period: w })
+        .collect();
+
+    let (current_streak, longest_streak) = streaks(&per_day, last_day);
+
+    Ok(Statistics {
+        from: to_rfc3339(from, &offset),
+        to: to_rfc3339(to, &offset),
+        created,
+        completed: per_day.values().sum(),
+        completed_per_day,
+        completed_per_week,
+        average_completion_hours: average(&completed_hours),
+        due,
+        overdue,
+        overdue_rate: (due > 0).then(|| overdue as f64 / due as f64),
+        current_streak,
+        longest_streak,
+        by_priority: into_groups(by_priority),
+        by_list: into_groups(by_list),
+        by_tag: into_groups(by_tag),
+    })
+}
+
+// 当前连续天数与最长连续天数
+fn streaks(per_day: &BTreeMap<NaiveDate, i64>, last_day: NaiveDate) -> (i64, i64) {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous: Option<NaiveDate> = None;
+    for day in per_day.keys() {
+        run = if previous.is_some_and(|p| *day - p == Duration::days(1)) { run + 1 } else { 1 };
+        longest = longest.max(run);
+        previous = Some(*day);
+    }
+
+    let mut day = if per_day.contains_key(&last_day) { last_day } else { last_day - Duration::days(1) };
+    let mut current = 0;
+    while per_day.contains_key(&day) {
+        current += 1;
+        day -= Duration::days(1);
+    }
+    (current, longest)
+}
+
+fn into_groups(groups: HashMap<String, GroupAcc>) -> Vec<StatisticsGroup> {
+    let mut groups: Vec<StatisticsGroup> = groups
+        .into_iter()
+        .map(|(key, acc)| StatisticsGroup {
+            key,
+            name: acc.name,
+            completed: acc.completed,
+            average_completion_hours: average(&acc.hours),
+        })
+        .collect();
+    groups.sort_by(|a, b| b.completed.cmp(&a.completed).then_with(|| a.key.cmp(&b.key)));
+    groups
+}
+
+fn average(values: &[f64]) -> Option<f64> {
+    (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+}
+
+fn parse_time(value: &str) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(value).ok()
+}
+
+fn to_rfc3339(timestamp: i64, offset: &FixedOffset) -> String {
+    offset.timestamp_opt(timestamp, 0).single().map(|d| d.to_rfc3339()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing::{save, task, with_repositories};
+
+    // 2026-10-08T00:00:00Z
+    const NOW: i64 = 1_791_417_600;
+
+    fn query(from: &str, to: &str, utc_offset_minutes: i32) -> StatisticsQuery {
+        StatisticsQuery {
+            from: Some(from.to_string()),
+            to: Some(to.to_string()),
+            utc_offset_minutes,
+            list_id: None,
+        }
+    }
+
+    fn week() -> StatisticsQuery {
+        query("2026-10-01T00:00:00+00:00", "2026-10-08T00:00:00+00:00", 0)
+    }
+
+    fn done(id: &str, completed_at: &str) -> Task {
+        task(id, json!({ "completed": true, "completedAt": completed_at, "createdAt": "2026-09-30T12:00:00+00:00" }))
+    }
+
+    fn per_day(stats: &Statistics) -> Vec<(&str, i64)> {
+        stats.completed_per_day.iter().map(|p| (p.period.as_str(), p.completed)).collect()
+    }
+
+    #[test]
+    fn groups_completions_by_local_day() {
+        let tasks = [done("late", "2026-10-03T23:30:00+00:00")];
+
+        let utc = compute_statistics(&tasks, &week(), NOW).unwrap();
+        assert_eq!(utc.completed_per_day.len(), 7);
+        assert!(per_day(&utc).contains(&("2026-10-03", 1)));
+
+        // UTC+2 时 23:30 已是次日；范围同样按本地日期展开
+        let plus_two = compute_statistics(&tasks, &StatisticsQuery { utc_offset_minutes: 120, ..week() }, NOW).unwrap();
+        assert_eq!(plus_two.completed_per_day.len(), 8);
+        assert!(per_day(&plus_two).contains(&("2026-10-04", 1)));
+        assert!(per_day(&plus_two).contains(&("2026-10-03", 0)));
+        assert!(plus_two.from.ends_with("+02:00"));
+    }
+
+    #[test]
+    fn range_includes_from_and_excludes_to() {
+        let tasks = [
+            done("at-from", "2026-10-01T00:00:00+00:00"),
+            done("before-to", "2026-10-07T23:59:59+00:00"),
+            done("at-to", "2026-10-08T00:00:00+00:00"),
+            task("reopened", json!({ "completed": false, "completedAt": "2026-10-02T00:00:00+00:00" })),
+            task("trashed", json!({ "completed": true, "completedAt": "2026-10-02T00:00:00+00:00", "deleted": true })),
+        ];
+        let stats = compute_statistics(&tasks, &week(), NOW).unwrap();
+        assert_eq!(stats.completed, 2);
+        assert_eq!(stats.completed_per_week.iter().map(|w| w.completed).sum::<i64>(), 2);
+
+        assert!(compute_statistics(&tasks, &query("2026-10-08T00:00:00Z", "2026-10-08T00:00:00Z", 0), NOW).is_err());
+        assert!(compute_statistics(&tasks, &query("yesterday", "2026-10-08T00:00:00Z", 0), NOW).is_err());
+    }
+
+    #[test]
+    fn current_streak_may_end_yesterday() {
+        let days = |days: &[u32]| -> Vec<Task> {
+            days.iter().map(|d| done(&format!("d{}", d), &format!("2026-10-0{}T12:00:00+00:00", d))).collect()
+        };
+
+        let stats = compute_statistics(&days(&[1, 2, 3, 5, 6]), &week(), NOW).unwrap();
+        assert_eq!((stats.current_streak, stats.longest_streak), (2, 3));
+
+        let stats = compute_statistics(&days(&[1, 2, 3, 5, 6, 7]), &week(), NOW).unwrap();
+        assert_eq!((stats.current_streak, stats.longest_streak), (3, 3));
+
+        let stats = compute_statistics(&days(&[1, 2, 3, 4, 5]), &week(), NOW).unwrap();
+        assert_eq!((stats.current_streak, stats.longest_streak), (0, 5));
+
+        let stats = compute_statistics(&[], &week(), NOW).unwrap();
+        assert_eq!((stats.current_streak, stats.longest_streak), (0, 0));
+        assert_eq!(stats.average_completion_hours, None);
+        assert_eq!(stats.overdue_rate, None);
+    }
+
+    #[test]
+    fn overdue_rate_and_average_latency() {
+        let tasks = [
+            task("on-time", json!({ "dueDate": "2026-10-03T00:00:00+00:00", "completed": true,
+                "completedAt": "2026-10-02T12:00:00+00:00", "createdAt": "2026-10-02T00:00:00+00:00" })),
+            task("late", json!({ "dueDate": "2026-10-03T00:00:00+00:00", "completed": true,
+                "completedAt": "2026-10-04T00:00:00+00:00", "createdAt": "2026-10-03T12:00:00+00:00" })),
+            task("missed", json!({ "dueDate": "2026-10-05T00:00:00+00:00" })),
+            // 截止时间未过（now 为 2026-10-07T12:00Z），不计入
+            task("upcoming", json!({ "dueDate": "2026-10-07T12:00:00+00:00" })),
+        ];
+        let stats = compute_statistics(&tasks, &week(), 1_791_374_400).unwrap();
+        assert_eq!((stats.due, stats.overdue), (3, 2));
+        assert_eq!(stats.overdue_rate, Some(2.0 / 3.0));
+        assert_eq!(stats.average_completion_hours, Some(12.0));
+    }
+
+    #[test]
+    fn completing_through_the_repository_records_completed_at() {
+        with_repositories(|repo| {
+            save(repo, "a", json!({ "listId": "work", "priority": "high" }));
+            save(repo, "b", json!({ "listId": "home" }));
+            repo.set_task_completed("a", true).unwrap();
+
+            let now = chrono::Utc::now().timestamp() + 1;
+            let stats = repo.statistics(&StatisticsQuery::default(), now).unwrap();
+            assert_eq!((stats.created, stats.completed), (2, 1));
+            assert_eq!(stats.current_streak, 1);
+            assert_eq!(stats.by_list[0].key, "work");
+            assert_eq!(stats.by_priority[0].key, "high");
+
+            // 重新打开后不再计入
+            repo.set_task_completed("a", false).unwrap();
+            assert_eq!(repo.statistics(&StatisticsQuery::default(), now).unwrap().completed, 0);
+        });
+    }
+}
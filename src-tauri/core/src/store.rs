@@ -0,0 +1,1841 @@
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use std::collections::HashMap;
+use std::fs;
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::model::{default_lists, ChangeRecord, PomodoroRecord, SubTask, Task, TaskConflict, TaskDependency, TaskEvent, TaskList, TimeEntry};
+use crate::history::{deleted_event, task_events};
+use crate::query::{due_timestamp, TaskPage, TaskQuery, TaskSort};
+use crate::repository::TaskRepository;
+use crate::search::{search_result, search_terms, SearchResult};
+use crate::subtasks::{normalize_task_subtasks, parse_subtasks, subtasks_json, TaskProgress};
+use crate::time_tracking::{sort_totals, timer_entry, TimeGroup, TimeTotal};
+use crate::undo::{check_replay, restored_task, TaskRecords, TaskSnapshot, UndoEntry, UndoOperation, UndoState, UNDO_LIMIT};
+
+// row_to_task 读取的任务列（顺序与 row_to_task 一致）
+const TASK_COLUMNS: &str = "id, title, description, completed, priority, status, list_id, tags, sub_tasks, reminders, due_date, created_at, updated_at, \"order\", category_id, deleted, deleted_at, parent_id, \
+    estimate_minutes, story_points, completed_at, \
+    EXISTS (SELECT 1 FROM task_dependencies d JOIN tasks b ON b.id = d.blocked_by \
+            WHERE d.task_id = tasks.id AND b.completed = 0 AND b.deleted = 0) AS blocked";
+// TASK_COLUMNS 的列数（查询中附加的列从该下标开始）
+const TASK_COLUMN_COUNT: usize = 22;
+
+const TIME_ENTRY_COLUMNS: &str = "id, task_id, started_at, ended_at, note, manual, created_at";
+
+// SQLite 数据存储（按路径打开，每次操作使用独立连接，可在线程间共享）
+#[derive(Debug, Clone)]
+pub struct Store {
+    path: PathBuf,
+    // 事务视图（transaction 创建）共享的连接，所有操作在同一个事务内执行
+    shared: Option<Arc<Mutex<Connection>>>,
+}
+
+// 数据库连接：独立打开的连接，或事务视图共享的连接
+enum Conn<'a> {
+    Owned(Connection),
+    Shared(MutexGuard<'a, Connection>),
+}
+
+impl Deref for Conn<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        match self {
+            Conn::Owned(conn) => conn,
+            Conn::Shared(conn) => conn,
+        }
+    }
+}
+
+impl DerefMut for Conn<'_> {
+    fn deref_mut(&mut self) -> &mut Connection {
+        match self {
+            Conn::Owned(conn) => conn,
+            Conn::Shared(conn) => conn,
+        }
+    }
+}
+
+impl Store {
+    // 打开数据库文件（不存在时创建），并补齐表结构与默认清单
+    pub fn open(path: impl Into<PathBuf>) -> Result<Store, String> {
+        let store = Store { path: path.into(), shared: None };
+        if let Some(parent) = store.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create data dir: {}", e))?;
+        }
+        store.init()?;
+        Ok(store)
+    }
+
+    // 数据库文件路径
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // 撤销（undo 为 true，写回操作前的状态）或重做（写回操作后的状态），单个事务内完成；
+    // 任务在操作之后又被修改时返回冲突错误，不写回任何操作，日志保持不变
+    fn replay(&self, steps: usize, undo: bool) -> Result<Vec<UndoEntry>, String> {
+        let mut conn = self.connect()?;
+        let tx = conn.savepoint()
+            .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+
+        let operations = select_operations(&tx, !undo, steps)?;
+        let now = chrono::Utc::now().to_rfc3339();
+        for operation in &operations {
+            let current = |id: &str| {
+                let sql = format!("SELECT {} FROM tasks WHERE id = ?1", TASK_COLUMNS);
+                Ok(select_tasks(&tx, &sql, id)?.pop())
+            };
+            // 冲突时事务回滚，已写回的操作与日志都保持不变
+            check_replay(operation, undo, current)?;
+
+            let snapshots = if undo { &operation.before } else { &operation.after };
+            for snapshot in snapshots {
+                match &snapshot.task {
+                    Some(task) => {
+                        upsert_task(&tx, &restored_task(task, &now))?;
+                        record_change(&tx, "task", &snapshot.id, "upsert")?;
+                    }
+                    None => {
+                        remove_task(&tx, &snapshot.id)?;
+                        record_change(&tx, "task", &snapshot.id, "delete")?;
+                    }
+                }
+            }
+            // 所有任务写回后再恢复关联记录（依赖两端可能都在本次恢复的任务中）
+            for snapshot in snapshots.iter().filter(|s| s.task.is_some()) {
+                restore_records(&tx, &snapshot.records)?;
+            }
+            tx.execute("UPDATE undo_log SET undone = ?1 WHERE id = ?2", params![undo as i32, operation.id])
+                .map_err(|e| format!("Failed to update undo log: {}", e))?;
+        }
+
+        tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+        Ok(operations.iter().map(UndoOperation::entry).collect())
+    }
+
+    // 事务视图返回共享连接，否则打开独立连接
+    fn connect(&self) -> Result<Conn<'_>, String> {
+        match &self.shared {
+            Some(conn) => conn.lock().map(Conn::Shared).map_err(|_| "Database connection lock poisoned".to_string()),
+            None => self.open_connection().map(Conn::Owned),
+        }
+    }
+
+    fn open_connection(&self) -> Result<Connection, String> {
+        Connection::open(&self.path).map_err(|e| format!("Failed to open database: {}", e))
+    }
+}
+
+impl TaskRepository for Store {
+    // 创建缺少的表与索引
+    fn init(&self) -> Result<(), String> {
+        let conn = self.connect()?;
+
+        // 创建任务表
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS tasks (
+                id TEXT PRIMARY KEY,
+                title TEXT NOT NULL,
+                description TEXT,
+                completed INTEGER NOT NULL DEFAULT 0,
+                priority TEXT NOT NULL DEFAULT 'none',
+                status TEXT NOT NULL DEFAULT 'todo',
+                list_id TEXT NOT NULL,
+                tags TEXT NOT NULL DEFAULT '[]',
+                sub_tasks TEXT NOT NULL DEFAULT '[]',
+                reminders TEXT NOT NULL DEFAULT '[]',
+                due_date TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                \"order\" INTEGER NOT NULL DEFAULT 0,
+                category_id TEXT,
+                deleted INTEGER NOT NULL DEFAULT 0,
+                deleted_at TEXT,
+                due_at INTEGER,
+                parent_id TEXT,
+                estimate_minutes INTEGER,
+                story_points REAL,
+                completed_at TEXT
+            )",
+            [],
+        ).map_err(|e| format!("Failed to create tasks table: {}", e))?;
+
+        // 旧版本数据库补齐分类、软删除与截止时间戳列（due_at 由 due_date 换算，用于范围查询与排序）
+        add_column_if_missing(&conn, "tasks", "category_id", "TEXT")?;
+        add_column_if_missing(&conn, "tasks", "deleted", "INTEGER NOT NULL DEFAULT 0")?;
+        add_column_if_missing(&conn, "tasks", "deleted_at", "TEXT")?;
+        if add_column_if_missing(&conn, "tasks", "due_at", "INTEGER")? {
+            backfill_due_at(&conn)?;
+        }
+        add_column_if_missing(&conn, "tasks", "parent_id", "TEXT")?;
+        add_column_if_missing(&conn, "tasks", "estimate_minutes", "INTEGER")?;
+        add_column_if_missing(&conn, "tasks", "story_points", "REAL")?;
+        // 旧数据没有完成时间，已完成的任务以最后更新时间近似
+        if add_column_if_missing(&conn, "tasks", "completed_at", "TEXT")? {
+            conn.execute("UPDATE tasks SET completed_at = updated_at WHERE completed = 1", [])
+                .map_err(|e| format!("Failed to backfill completed_at: {}", e))?;
+        }
+
+        // 任务查询索引
+        for (name, columns) in [
+            ("idx_tasks_list_id", "list_id"),
+            ("idx_tasks_category_id", "category_id"),
+            ("idx_tasks_parent_id", "parent_id"),
+            ("idx_tasks_due_at", "due_at"),
+            ("idx_tasks_deleted_completed", "deleted, completed"),
+            ("idx_tasks_order", "\"order\", created_at"),
+            ("idx_tasks_completed_at", "completed_at"),
+        ] {
+            conn.execute(&format!("CREATE INDEX IF NOT EXISTS {} ON tasks({})", name, columns), [])
+                .map_err(|e| format!("Failed to create index: {}", e))?;
+        }
+
+        // 创建任务依赖表（task_id 被 blocked_by 阻塞），任务删除时一并删除相关依赖
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS task_dependencies (
+                task_id TEXT NOT NULL,
+                blocked_by TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                PRIMARY KEY (task_id, blocked_by)
+            );
+            CREATE INDEX IF NOT EXISTS idx_task_dependencies_blocked_by ON task_dependencies(blocked_by);
+            CREATE TRIGGER IF NOT EXISTS task_dependencies_task_delete AFTER DELETE ON tasks BEGIN
+                DELETE FROM task_dependencies WHERE task_id = old.id OR blocked_by = old.id;
+            END;",
+        ).map_err(|e| format!("Failed to create task_dependencies table: {}", e))?;
+
+        // 创建任务历史表（任务删除后保留，以 deleted 事件结束）
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS task_events (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                task_id TEXT NOT NULL,
+                field TEXT NOT NULL,
+                old_value TEXT,
+                new_value TEXT,
+                created_at TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_task_events_task_id ON task_events(task_id, id);
+            DROP TRIGGER IF EXISTS task_events_task_delete;",
+        ).map_err(|e| format!("Failed to create task_events table: {}", e))?;
+
+        // 创建撤销日志表（before_state / after_state 为 TaskSnapshot 数组的 JSON，undone 为 1 表示可重做）
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS undo_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                label TEXT NOT NULL,
+                before_state TEXT NOT NULL,
+                after_state TEXT NOT NULL,
+                undone INTEGER NOT NULL DEFAULT 0,
+                created_at TEXT NOT NULL
+            );",
+        ).map_err(|e| format!("Failed to create undo_log table: {}", e))?;
+
+        // 创建计时记录表（ended_at 为空表示正在计时，唯一索引保证最多一条），任务删除时一并删除
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS time_entries (
+                id TEXT PRIMARY KEY,
+                task_id TEXT NOT NULL,
+                started_at INTEGER NOT NULL,
+                ended_at INTEGER,
+                note TEXT,
+                manual INTEGER NOT NULL DEFAULT 0,
+                created_at TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_time_entries_task_id ON time_entries(task_id, started_at);
+            CREATE UNIQUE INDEX IF NOT EXISTS idx_time_entries_running ON time_entries((ended_at IS NULL)) WHERE ended_at IS NULL;
+            CREATE TRIGGER IF NOT EXISTS time_entries_task_delete AFTER DELETE ON tasks BEGIN
+                DELETE FROM time_entries WHERE task_id = old.id;
+            END;",
+        ).map_err(|e| format!("Failed to create time_entries table: {}", e))?;
+
+        // 创建番茄记录表，任务删除时一并删除
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS pomodoros (
+                id TEXT PRIMARY KEY,
+                task_id TEXT NOT NULL,
+                started_at INTEGER NOT NULL,
+                ended_at INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_pomodoros_task_id ON pomodoros(task_id, started_at);
+            CREATE TRIGGER IF NOT EXISTS pomodoros_task_delete AFTER DELETE ON tasks BEGIN
+                DELETE FROM pomodoros WHERE task_id = old.id;
+            END;",
+        ).map_err(|e| format!("Failed to create pomodoros table: {}", e))?;
+
+        // 全文搜索索引（trigram 分词以支持中文，由触发器与 tasks 表保持同步，rowid 与 tasks 一致）
+        let fts_exists = conn
+            .prepare("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'tasks_fts'")
+            .and_then(|mut stmt| stmt.exists([]))
+            .map_err(|e| format!("Failed to inspect tasks_fts table: {}", e))?;
+
+        conn.execute_batch(&format!(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS tasks_fts USING fts5(title, description, subtasks, tokenize = 'trigram');
+            CREATE TRIGGER IF NOT EXISTS tasks_fts_insert AFTER INSERT ON tasks BEGIN
+                INSERT INTO tasks_fts (rowid, title, description, subtasks) VALUES ({new});
+            END;
+            CREATE TRIGGER IF NOT EXISTS tasks_fts_delete AFTER DELETE ON tasks BEGIN
+                DELETE FROM tasks_fts WHERE rowid = old.rowid;
+            END;
+            CREATE TRIGGER IF NOT EXISTS tasks_fts_update AFTER UPDATE ON tasks BEGIN
+                DELETE FROM tasks_fts WHERE rowid = old.rowid;
+                INSERT INTO tasks_fts (rowid, title, description, subtasks) VALUES ({new});
+            END;",
+            new = fts_values("new"),
+        )).map_err(|e| format!("Failed to create tasks_fts table: {}", e))?;
+
+        // 旧版本数据库首次建立索引
+        if !fts_exists {
+            conn.execute(
+                &format!("INSERT INTO tasks_fts (rowid, title, description, subtasks) SELECT {} FROM tasks", fts_values("tasks")),
+                [],
+            ).map_err(|e| format!("Failed to build tasks_fts index: {}", e))?;
+        }
+
+        // 创建子任务表（tasks.sub_tasks 保留为 JSON 副本，供前端、同步与全文索引使用）
+        let subtasks_exists = conn
+            .prepare("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'subtasks'")
+            .and_then(|mut stmt| stmt.exists([]))
+            .map_err(|e| format!("Failed to inspect subtasks table: {}", e))?;
+
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS subtasks (
+                id TEXT PRIMARY KEY,
+                task_id TEXT NOT NULL,
+                title TEXT NOT NULL,
+                completed INTEGER NOT NULL DEFAULT 0,
+                completed_at TEXT,
+                due_date TEXT,
+                \"order\" INTEGER NOT NULL DEFAULT 0
+            );
+            CREATE INDEX IF NOT EXISTS idx_subtasks_task_id ON subtasks(task_id, \"order\");
+            CREATE TRIGGER IF NOT EXISTS subtasks_task_delete AFTER DELETE ON tasks BEGIN
+                DELETE FROM subtasks WHERE task_id = old.id;
+            END;",
+        ).map_err(|e| format!("Failed to create subtasks table: {}", e))?;
+
+        // 旧版本数据库：从 sub_tasks JSON 导入子任务
+        if !subtasks_exists {
+            migrate_subtasks(&conn)?;
+        }
+
+        // 创建清单表
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS lists (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                icon TEXT,
+                color TEXT,
+                \"order\" INTEGER NOT NULL DEFAULT 0,
+                created_at TEXT NOT NULL
+            )",
+            [],
+        ).map_err(|e| format!("Failed to create lists table: {}", e))?;
+
+        // 创建已发送提醒记录表
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS sent_reminders (
+                id TEXT PRIMARY KEY,
+                task_id TEXT NOT NULL,
+                reminder_time INTEGER NOT NULL,
+                sent_at INTEGER NOT NULL,
+                reminder_data TEXT
+            )",
+            [],
+        ).map_err(|e| format!("Failed to create sent_reminders table: {}", e))?;
+
+        // 创建索引以提高查询性能
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_sent_reminders_task_id ON sent_reminders(task_id)",
+            [],
+        ).map_err(|e| format!("Failed to create index: {}", e))?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_sent_reminders_time ON sent_reminders(reminder_time)",
+            [],
+        ).map_err(|e| format!("Failed to create index: {}", e))?;
+
+        // 创建变更记录表（seq 单调递增，每个实体只保留最近一次变更）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS changes (
+                seq INTEGER PRIMARY KEY AUTOINCREMENT,
+                entity TEXT NOT NULL,
+                entity_id TEXT NOT NULL,
+                op TEXT NOT NULL,
+                changed_at TEXT NOT NULL
+            )",
+            [],
+        ).map_err(|e| format!("Failed to create changes table: {}", e))?;
+
+        conn.execute(
+            "CREATE UNIQUE INDEX IF NOT EXISTS idx_changes_entity ON changes(entity, entity_id)",
+            [],
+        ).map_err(|e| format!("Failed to create index: {}", e))?;
+
+        // 创建同步状态表（游标、设备 ID 等）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS sync_state (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            )",
+            [],
+        ).map_err(|e| format!("Failed to create sync_state table: {}", e))?;
+
+        // 创建任务冲突表
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS task_conflicts (
+                id TEXT PRIMARY KEY,
+                task_id TEXT NOT NULL,
+                source TEXT NOT NULL,
+                local_data TEXT NOT NULL,
+                remote_data TEXT NOT NULL,
+                winner TEXT NOT NULL,
+                detected_at TEXT NOT NULL,
+                resolved_at TEXT,
+                resolution TEXT
+            )",
+            [],
+        ).map_err(|e| format!("Failed to create task_conflicts table: {}", e))?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_task_conflicts_task_id ON task_conflicts(task_id)",
+            [],
+        ).map_err(|e| format!("Failed to create index: {}", e))?;
+
+        // 插入默认清单
+        for list in default_lists() {
+            conn.execute(
+                "INSERT OR IGNORE INTO lists (id, name, icon, color, \"order\", created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![list.id, list.name, list.icon, list.color, list.order, list.created_at],
+            ).map_err(|e| format!("Failed to insert default list: {}", e))?;
+        }
+
+        Ok(())
+    }
+
+    fn database_path(&self) -> Option<PathBuf> {
+        Some(self.path.clone())
+    }
+
+    // 在共享连接上开启事务并交给事务视图执行；已经是事务视图时直接执行
+    fn transaction(&self, f: &mut dyn FnMut(&dyn TaskRepository) -> Result<(), String>) -> Result<(), String> {
+        if self.shared.is_some() {
+            return f(self);
+        }
+        let conn = self.open_connection()?;
+        conn.execute_batch("BEGIN IMMEDIATE")
+            .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+        let view = Store { path: self.path.clone(), shared: Some(Arc::new(Mutex::new(conn))) };
+        let result = f(&view);
+        let conn = view.connect()?;
+        match result {
+            Ok(()) => conn.execute_batch("COMMIT").map_err(|e| format!("Failed to commit transaction: {}", e)),
+            Err(e) => {
+                // 回滚失败时连接随即关闭，未提交的写入同样被丢弃
+                let _ = conn.execute_batch("ROLLBACK");
+                Err(e)
+            }
+        }
+    }
+
+    // ========== 任务 ==========
+
+    // 获取所有任务
+    fn tasks(&self) -> Result<Vec<Task>, String> {
+        let conn = self.connect()?;
+
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM tasks ORDER BY \"order\" ASC, created_at DESC", TASK_COLUMNS))
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let tasks = stmt.query_map([], row_to_task)
+            .map_err(|e| format!("Failed to query tasks: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect tasks: {}", e))?;
+
+        Ok(tasks)
+    }
+
+    // 获取单个任务
+    fn task(&self, id: &str) -> Result<Option<Task>, String> {
+        let conn = self.connect()?;
+
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM tasks WHERE id = ?1", TASK_COLUMNS))
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let mut tasks = stmt.query_map(params![id], row_to_task)
+            .map_err(|e| format!("Failed to query task: {}", e))?;
+
+        tasks.next()
+            .transpose()
+            .map_err(|e| format!("Failed to read task: {}", e))
+    }
+
+    // 按条件筛选、排序并分页
+    fn query_tasks(&self, query: &TaskQuery) -> Result<TaskPage, String> {
+        use rusqlite::types::Value;
+
+        let (due_from, due_to) = query.due_range()?;
+        let mut conditions = vec!["deleted = ?".to_string()];
+        let mut values = vec![Value::Integer(query.deleted as i64)];
+
+        if let Some(list_id) = query.list_filter() {
+            conditions.push("list_id = ?".to_string());
+            values.push(Value::Text(list_id.to_string()));
+        }
+        if let Some(category_id) = &query.category_id {
+            conditions.push("category_id = ?".to_string());
+            values.push(Value::Text(category_id.clone()));
+        }
+        if !query.tags.is_empty() {
+            conditions.push(format!(
+                "EXISTS (SELECT 1 FROM json_each(CASE WHEN json_valid(tags) THEN tags ELSE '[]' END) WHERE json_extract(value, '$.id') IN ({}))",
+                placeholders(query.tags.len())
+            ));
+            values.extend(query.tags.iter().cloned().map(Value::Text));
+        }
+        if !query.priorities.is_empty() {
+            conditions.push(format!("priority IN ({})", placeholders(query.priorities.len())));
+            values.extend(query.priorities.iter().cloned().map(Value::Text));
+        }
+        if !query.statuses.is_empty() {
+            conditions.push(format!("status IN ({})", placeholders(query.statuses.len())));
+            values.extend(query.statuses.iter().cloned().map(Value::Text));
+        }
+        if let Some(from) = due_from {
+            conditions.push("due_at >= ?".to_string());
+            values.push(Value::Integer(from));
+        }
+        if let Some(to) = due_to {
+            conditions.push("due_at < ?".to_string());
+            values.push(Value::Integer(to));
+        }
+        if let Some(has_due_date) = query.has_due_date {
+            conditions.push(format!("due_date IS {}NULL", if has_due_date { "NOT " } else { "" }));
+        }
+        if let Some(completed) = query.completed {
+            conditions.push("completed = ?".to_string());
+            values.push(Value::Integer(completed as i64));
+        }
+        if let Some(blocked) = query.blocked {
+            conditions.push(format!(
+                "{}EXISTS (SELECT 1 FROM task_dependencies d JOIN tasks b ON b.id = d.blocked_by \
+                 WHERE d.task_id = tasks.id AND b.completed = 0 AND b.deleted = 0)",
+                if blocked { "" } else { "NOT " }
+            ));
+        }
+        if let Some(text) = query.search_text() {
+            conditions.push("(title LIKE ? ESCAPE '\\' OR description LIKE ? ESCAPE '\\')".to_string());
+            let pattern = format!("%{}%", escape_like(text));
+            values.push(Value::Text(pattern.clone()));
+            values.push(Value::Text(pattern));
+        }
+
+        let conn = self.connect()?;
+        let where_clause = conditions.join(" AND ");
+
+        let total: i64 = conn
+            .query_row(
+                &format!("SELECT COUNT(*) FROM tasks WHERE {}", where_clause),
+                params_from_iter(values.iter()),
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("Failed to count tasks: {}", e))?;
+
+        let direction = if query.descending { "DESC" } else { "ASC" };
+        let order_by = match query.sort {
+            TaskSort::Order => format!("\"order\" {}", direction),
+            TaskSort::DueDate => format!("due_at IS NULL, due_at {}", direction),
+            TaskSort::Priority => format!(
+                "CASE priority WHEN 'low' THEN 1 WHEN 'medium' THEN 2 WHEN 'high' THEN 3 ELSE 0 END {}",
+                direction
+            ),
+            TaskSort::CreatedAt => format!("created_at {}", direction),
+            TaskSort::UpdatedAt => format!("updated_at {}", direction),
+            TaskSort::Title => format!("title COLLATE NOCASE {}", direction),
+        };
+        values.push(Value::Integer(query.limit.map(i64::from).unwrap_or(-1)));
+        values.push(Value::Integer(query.offset as i64));
+
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT {} FROM tasks WHERE {} ORDER BY {}, \"order\" ASC, created_at DESC LIMIT ? OFFSET ?",
+                TASK_COLUMNS, where_clause, order_by
+            ))
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let tasks = stmt.query_map(params_from_iter(values.iter()), row_to_task)
+            .map_err(|e| format!("Failed to query tasks: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect tasks: {}", e))?;
+
+        Ok(TaskPage { tasks, total })
+    }
+
+    // 全文搜索：不少于 3 个字符的搜索词走 FTS5 索引并按 bm25 排序，更短的词（如两个汉字）用 LIKE 在索引内容中匹配
+    fn search_tasks(&self, text: &str, limit: usize) -> Result<Vec<SearchResult>, String> {
+        use rusqlite::types::Value;
+
+        let terms = search_terms(text);
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+        let (long_terms, short_terms): (Vec<&String>, Vec<&String>) =
+            terms.iter().partition(|t| t.chars().count() >= 3);
+
+        // 相关度：标题、描述、子任务的权重依次降低；bm25 越小越相关，取反后与短词的命中权重相加
+        let mut scores = Vec::new();
+        let mut score_values = Vec::new();
+        let mut conditions = Vec::new();
+        let mut values = Vec::new();
+        if !long_terms.is_empty() {
+            let phrases: Vec<String> = long_terms.iter().map(|t| format!("\"{}\"", t.replace('"', "\"\""))).collect();
+            scores.push("-bm25(tasks_fts, 10.0, 4.0, 2.0)".to_string());
+            conditions.push("tasks_fts MATCH ?".to_string());
+            values.push(Value::Text(phrases.join(" ")));
+        }
+        for term in short_terms {
+            let pattern = Value::Text(format!("%{}%", escape_like(term)));
+            scores.push("(title LIKE ? ESCAPE '\\') * 10.0 + (description LIKE ? ESCAPE '\\') * 4.0 + (subtasks LIKE ? ESCAPE '\\') * 2.0".to_string());
+            score_values.extend(std::iter::repeat_n(pattern.clone(), 3));
+            conditions.push("(title LIKE ? ESCAPE '\\' OR description LIKE ? ESCAPE '\\' OR subtasks LIKE ? ESCAPE '\\')".to_string());
+            values.extend(std::iter::repeat_n(pattern, 3));
+        }
+        // 参数顺序与 SQL 中出现的顺序一致：相关度、筛选条件、LIMIT
+        let values: Vec<Value> = score_values
+            .into_iter()
+            .chain(values)
+            .chain([Value::Integer(limit.min(i64::MAX as usize) as i64)])
+            .collect();
+
+        let conn = self.connect()?;
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT {}, hits.score FROM tasks
+                 JOIN (SELECT rowid AS fts_rowid, {} AS score FROM tasks_fts WHERE {}) hits ON tasks.rowid = hits.fts_rowid
+                 WHERE deleted = 0
+                 ORDER BY hits.score DESC, updated_at DESC
+                 LIMIT ?",
+                TASK_COLUMNS,
+                scores.join(" + "),
+                conditions.join(" AND ")
+            ))
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let hits = stmt
+            .query_map(params_from_iter(values.iter()), |row| Ok((row_to_task(row)?, row.get::<_, f64>(TASK_COLUMN_COUNT)?)))
+            .map_err(|e| format!("Failed to search tasks: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect tasks: {}", e))?;
+
+        Ok(hits
+            .into_iter()
+            .map(|(task, score)| search_result(task, &terms, score))
+            .collect())
+    }
+
+    // 保存任务（父任务必须存在且不能形成环）
+    fn save_task(&self, task: &Task) -> Result<(), String> {
+        let mut conn = self.connect()?;
+        let tx = conn.savepoint()
+            .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+        if let Some(parent_id) = &task.parent_id {
+            check_parent(&tx, &task.id, parent_id)?;
+        }
+        upsert_task(&tx, task)?;
+        record_change(&tx, "task", &task.id, "upsert")?;
+        tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+        Ok(())
+    }
+
+    // 删除任务及其所有后代（单个事务内，同时删除相关的提醒记录，子任务、计时等由触发器删除）
+    fn delete_task(&self, id: &str) -> Result<(), String> {
+        let mut conn = self.connect()?;
+        let tx = conn.savepoint()
+            .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+
+        for id in delete_subtree(&tx, id)? {
+            record_change(&tx, "task", &id, "delete")?;
+        }
+
+        tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+        Ok(())
+    }
+
+    // 批量写入与删除（单个事务内，任一任务的父任务不存在或形成环时全部回滚）；删除时一并删除后代
+    // record 为 false 时不写入变更记录（用于应用 REST 服务下发的变更，避免回传）
+    fn apply_task_changes(&self, upserts: &[Task], deletes: &[String], record: bool) -> Result<(), String> {
+        let mut conn = self.connect()?;
+        let tx = conn.savepoint()
+            .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+
+        for task in upserts {
+            upsert_task(&tx, task)?;
+            if record {
+                record_change(&tx, "task", &task.id, "upsert")?;
+            }
+        }
+        // 全部写入后再检查层级，父任务可以在同一批中写入
+        for task in upserts {
+            if let Some(parent_id) = &task.parent_id {
+                check_parent(&tx, &task.id, parent_id)?;
+            }
+        }
+
+        for id in deletes {
+            for id in delete_subtree(&tx, id)? {
+                if record {
+                    record_change(&tx, "task", &id, "delete")?;
+                }
+            }
+        }
+
+        tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+        Ok(())
+    }
+
+    // 任务历史
+    fn task_events(&self, task_id: &str) -> Result<Vec<TaskEvent>, String> {
+        let conn = self.connect()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT id, task_id, field, old_value, new_value, created_at FROM task_events
+             WHERE task_id = ?1 ORDER BY id ASC",
+        ).map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let events = stmt.query_map(params![task_id], |row| {
+            Ok(TaskEvent {
+                id: row.get(0)?,
+                task_id: row.get(1)?,
+                field: row.get(2)?,
+                old_value: row.get(3)?,
+                new_value: row.get(4)?,
+                created_at: row.get(5)?,
+            })
+        })
+        .map_err(|e| format!("Failed to query task events: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect task events: {}", e))?;
+
+        Ok(events)
+    }
+
+    // ========== 撤销与重做 ==========
+
+    fn log_operation(&self, label: &str, before: &[TaskSnapshot], after: &[TaskSnapshot]) -> Result<(), String> {
+        let before = serde_json::to_string(before).map_err(|e| format!("Failed to serialize snapshot: {}", e))?;
+        let after = serde_json::to_string(after).map_err(|e| format!("Failed to serialize snapshot: {}", e))?;
+
+        let mut conn = self.connect()?;
+        let tx = conn.savepoint()
+            .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+        tx.execute("DELETE FROM undo_log WHERE undone = 1", [])
+            .map_err(|e| format!("Failed to clear redo log: {}", e))?;
+        tx.execute(
+            "INSERT INTO undo_log (label, before_state, after_state, undone, created_at) VALUES (?1, ?2, ?3, 0, ?4)",
+            params![label, before, after, chrono::Utc::now().to_rfc3339()],
+        ).map_err(|e| format!("Failed to record operation: {}", e))?;
+        tx.execute(
+            "DELETE FROM undo_log WHERE id NOT IN (SELECT id FROM undo_log ORDER BY id DESC LIMIT ?1)",
+            params![UNDO_LIMIT as i64],
+        ).map_err(|e| format!("Failed to trim undo log: {}", e))?;
+        tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+        Ok(())
+    }
+
+    fn undo(&self, steps: usize) -> Result<Vec<UndoEntry>, String> {
+        self.replay(steps, true)
+    }
+
+    fn redo(&self, steps: usize) -> Result<Vec<UndoEntry>, String> {
+        self.replay(steps, false)
+    }
+
+    fn undo_state(&self) -> Result<UndoState, String> {
+        let conn = self.connect()?;
+        let count = |undone: bool| {
+            conn.query_row("SELECT COUNT(*) FROM undo_log WHERE undone = ?1", params![undone as i32], |row| row.get(0))
+                .map_err(|e| format!("Failed to count undo log: {}", e))
+        };
+        Ok(UndoState {
+            undo_count: count(false)?,
+            redo_count: count(true)?,
+            next_undo: select_operations(&conn, false, 1)?.first().map(UndoOperation::entry),
+            next_redo: select_operations(&conn, true, 1)?.first().map(UndoOperation::entry),
+        })
+    }
+
+    // ========== 任务层级 ==========
+
+    // 任务的所有后代（递归查询）
+    fn descendants(&self, id: &str) -> Result<Vec<Task>, String> {
+        let conn = self.connect()?;
+        select_tasks(
+            &conn,
+            &format!(
+                "WITH RECURSIVE tree(id) AS (
+                    SELECT ?1
+                    UNION
+                    SELECT t.id FROM tasks t JOIN tree ON t.parent_id = tree.id
+                )
+                SELECT {} FROM tasks WHERE id IN (SELECT id FROM tree) AND id != ?1
+                ORDER BY \"order\" ASC, created_at DESC",
+                TASK_COLUMNS
+            ),
+            id,
+        )
+    }
+
+    // 任务的所有祖先（递归查询，再按父子关系排成从父任务到根的顺序）
+    fn ancestors(&self, id: &str) -> Result<Vec<Task>, String> {
+        let conn = self.connect()?;
+        let tasks = select_tasks(
+            &conn,
+            &format!(
+                "WITH RECURSIVE chain(id) AS (
+                    SELECT ?1
+                    UNION
+                    SELECT t.parent_id FROM tasks t JOIN chain ON t.id = chain.id WHERE t.parent_id IS NOT NULL
+                )
+                SELECT {} FROM tasks WHERE id IN (SELECT id FROM chain)",
+                TASK_COLUMNS
+            ),
+            id,
+        )?;
+
+        let mut by_id: HashMap<String, Task> = tasks.into_iter().map(|t| (t.id.clone(), t)).collect();
+        let mut next = by_id.remove(id).and_then(|t| t.parent_id);
+        let mut ancestors = Vec::new();
+        while let Some(task) = next.and_then(|parent_id| by_id.remove(&parent_id)) {
+            next = task.parent_id.clone();
+            ancestors.push(task);
+        }
+        Ok(ancestors)
+    }
+
+    // ========== 任务依赖 ==========
+
+    // 依赖关系
+    fn task_dependencies(&self, task_id: Option<&str>) -> Result<Vec<TaskDependency>, String> {
+        let conn = self.connect()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT task_id, blocked_by, created_at FROM task_dependencies
+             WHERE ?1 IS NULL OR task_id = ?1 OR blocked_by = ?1
+             ORDER BY created_at ASC",
+        ).map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let dependencies = stmt.query_map(params![task_id], |row| {
+            Ok(TaskDependency {
+                task_id: row.get(0)?,
+                blocked_by: row.get(1)?,
+                created_at: row.get(2)?,
+            })
+        })
+        .map_err(|e| format!("Failed to query task dependencies: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect task dependencies: {}", e))?;
+
+        Ok(dependencies)
+    }
+
+    // 添加依赖（在事务内检查任务是否存在与是否形成环）
+    fn add_task_dependency(&self, task_id: &str, blocked_by: &str) -> Result<TaskDependency, String> {
+        if task_id == blocked_by {
+            return Err("A task cannot block itself".to_string());
+        }
+
+        let mut conn = self.connect()?;
+        let tx = conn.savepoint()
+            .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+
+        for id in [task_id, blocked_by] {
+            let exists = tx
+                .prepare("SELECT 1 FROM tasks WHERE id = ?1")
+                .and_then(|mut stmt| stmt.exists(params![id]))
+                .map_err(|e| format!("Failed to query task: {}", e))?;
+            if !exists {
+                return Err(format!("Task not found: {}", id));
+            }
+        }
+
+        // blocked_by 直接或间接被 task_id 阻塞时会形成环
+        let cycle = tx
+            .prepare(
+                "WITH RECURSIVE chain(id) AS (
+                    SELECT ?1
+                    UNION
+                    SELECT d.blocked_by FROM task_dependencies d JOIN chain ON d.task_id = chain.id
+                )
+                SELECT 1 FROM chain WHERE id = ?2",
+            )
+            .and_then(|mut stmt| stmt.exists(params![blocked_by, task_id]))
+            .map_err(|e| format!("Failed to check task dependencies: {}", e))?;
+        if cycle {
+            return Err("Adding this dependency would create a cycle".to_string());
+        }
+
+        tx.execute(
+            "INSERT OR IGNORE INTO task_dependencies (task_id, blocked_by, created_at) VALUES (?1, ?2, ?3)",
+            params![task_id, blocked_by, chrono::Utc::now().to_rfc3339()],
+        ).map_err(|e| format!("Failed to save task dependency: {}", e))?;
+
+        let dependency = tx
+            .query_row(
+                "SELECT created_at FROM task_dependencies WHERE task_id = ?1 AND blocked_by = ?2",
+                params![task_id, blocked_by],
+                |row| row.get(0),
+            )
+            .map(|created_at| TaskDependency {
+                task_id: task_id.to_string(),
+                blocked_by: blocked_by.to_string(),
+                created_at,
+            })
+            .map_err(|e| format!("Failed to read task dependency: {}", e))?;
+
+        tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+        Ok(dependency)
+    }
+
+    // 删除依赖
+    fn remove_task_dependency(&self, task_id: &str, blocked_by: &str) -> Result<bool, String> {
+        let conn = self.connect()?;
+
+        let deleted = conn.execute(
+            "DELETE FROM task_dependencies WHERE task_id = ?1 AND blocked_by = ?2",
+            params![task_id, blocked_by],
+        ).map_err(|e| format!("Failed to delete task dependency: {}", e))?;
+
+        Ok(deleted > 0)
+    }
+
+    // ========== 计时 ==========
+
+    // 计时记录
+    fn time_entries(&self, task_id: Option<&str>) -> Result<Vec<TimeEntry>, String> {
+        let conn = self.connect()?;
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM time_entries WHERE ?1 IS NULL OR task_id = ?1 ORDER BY started_at DESC, created_at DESC",
+            TIME_ENTRY_COLUMNS
+        )).map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let entries = stmt.query_map(params![task_id], row_to_time_entry)
+            .map_err(|e| format!("Failed to query time entries: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect time entries: {}", e))?;
+
+        Ok(entries)
+    }
+
+    // 正在计时的记录
+    fn running_timer(&self) -> Result<Option<TimeEntry>, String> {
+        let conn = self.connect()?;
+        running_timer(&conn)
+    }
+
+    // 开始计时（在事务内停止正在进行的其他计时）
+    fn start_timer(&self, task_id: &str, now: i64) -> Result<TimeEntry, String> {
+        let mut conn = self.connect()?;
+        let tx = conn.savepoint()
+            .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+
+        let exists = tx
+            .prepare("SELECT 1 FROM tasks WHERE id = ?1")
+            .and_then(|mut stmt| stmt.exists(params![task_id]))
+            .map_err(|e| format!("Failed to query task: {}", e))?;
+        if !exists {
+            return Err(format!("Task not found: {}", task_id));
+        }
+
+        if let Some(running) = running_timer(&tx)? {
+            if running.task_id == task_id {
+                return Ok(running);
+            }
+            finish_timer(&tx, &running, now)?;
+        }
+
+        let entry = timer_entry(task_id, now);
+        insert_time_entry(&tx, &entry)?;
+
+        tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+        Ok(entry)
+    }
+
+    // 停止计时（读取与结束正在计时的记录在同一事务内）
+    fn stop_timer(&self, now: i64) -> Result<Option<TimeEntry>, String> {
+        let mut conn = self.connect()?;
+        let tx = conn.savepoint()
+            .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+
+        let stopped = match running_timer(&tx)? {
+            Some(running) => Some(finish_timer(&tx, &running, now)?),
+            None => None,
+        };
+
+        tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+        Ok(stopped)
+    }
+
+    // 保存已结束的计时记录
+    fn add_time_entry(&self, entry: &TimeEntry) -> Result<(), String> {
+        if entry.is_running() {
+            return Err("Use start_timer to start a running timer".to_string());
+        }
+
+        let conn = self.connect()?;
+        let exists = conn
+            .prepare("SELECT 1 FROM tasks WHERE id = ?1")
+            .and_then(|mut stmt| stmt.exists(params![entry.task_id]))
+            .map_err(|e| format!("Failed to query task: {}", e))?;
+        if !exists {
+            return Err(format!("Task not found: {}", entry.task_id));
+        }
+
+        insert_time_entry(&conn, entry)
+    }
+
+    // 汇总时长（按清单汇总时忽略已不存在的任务）
+    fn time_totals(&self, group: TimeGroup, now: i64) -> Result<Vec<TimeTotal>, String> {
+        let conn = self.connect()?;
+
+        let sql = match group {
+            TimeGroup::Task => {
+                "SELECT task_id, SUM(MAX(0, COALESCE(ended_at, ?1) - started_at)), COUNT(*)
+                 FROM time_entries GROUP BY task_id"
+            }
+            TimeGroup::List => {
+                "SELECT t.list_id, SUM(MAX(0, COALESCE(e.ended_at, ?1) - e.started_at)), COUNT(*)
+                 FROM time_entries e JOIN tasks t ON t.id = e.task_id GROUP BY t.list_id"
+            }
+        };
+        let mut stmt = conn.prepare(sql)
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let mut totals = stmt.query_map(params![now], |row| {
+            Ok(TimeTotal {
+                id: row.get(0)?,
+                seconds: row.get(1)?,
+                entries: row.get(2)?,
+            })
+        })
+        .map_err(|e| format!("Failed to query time totals: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect time totals: {}", e))?;
+
+        sort_totals(&mut totals);
+        Ok(totals)
+    }
+
+    // 番茄记录
+    fn pomodoros(&self, task_id: Option<&str>) -> Result<Vec<PomodoroRecord>, String> {
+        let conn = self.connect()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT id, task_id, started_at, ended_at FROM pomodoros
+             WHERE ?1 IS NULL OR task_id = ?1
+             ORDER BY started_at DESC",
+        ).map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let records = stmt.query_map(params![task_id], |row| {
+            Ok(PomodoroRecord {
+                id: row.get(0)?,
+                task_id: row.get(1)?,
+                started_at: row.get(2)?,
+                ended_at: row.get(3)?,
+            })
+        })
+        .map_err(|e| format!("Failed to query pomodoros: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect pomodoros: {}", e))?;
+
+        Ok(records)
+    }
+
+    // 记录完成的番茄
+    fn log_pomodoro(&self, record: &PomodoroRecord) -> Result<(), String> {
+        let conn = self.connect()?;
+        let exists = conn
+            .prepare("SELECT 1 FROM tasks WHERE id = ?1")
+            .and_then(|mut stmt| stmt.exists(params![record.task_id]))
+            .map_err(|e| format!("Failed to query task: {}", e))?;
+        if !exists {
+            return Err(format!("Task not found: {}", record.task_id));
+        }
+
+        conn.execute(
+            "INSERT OR REPLACE INTO pomodoros (id, task_id, started_at, ended_at) VALUES (?1, ?2, ?3, ?4)",
+            params![record.id, record.task_id, record.started_at, record.ended_at],
+        ).map_err(|e| format!("Failed to save pomodoro: {}", e))?;
+        Ok(())
+    }
+
+    // ========== 子任务 ==========
+
+    // 获取任务的子任务
+    fn subtasks(&self, task_id: &str) -> Result<Vec<SubTask>, String> {
+        let conn = self.connect()?;
+        let exists = conn
+            .prepare("SELECT 1 FROM tasks WHERE id = ?1")
+            .and_then(|mut stmt| stmt.exists(params![task_id]))
+            .map_err(|e| format!("Failed to query task: {}", e))?;
+        if !exists {
+            return Err(format!("Task not found: {}", task_id));
+        }
+        load_subtasks(&conn, task_id)
+    }
+
+    // 子任务所属任务的 ID
+    fn subtask_parent(&self, subtask_id: &str) -> Result<Option<String>, String> {
+        let conn = self.connect()?;
+        let mut stmt = conn.prepare("SELECT task_id FROM subtasks WHERE id = ?1")
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let mut rows = stmt.query_map(params![subtask_id], |row| row.get(0))
+            .map_err(|e| format!("Failed to query subtask: {}", e))?;
+
+        rows.next()
+            .transpose()
+            .map_err(|e| format!("Failed to read subtask: {}", e))
+    }
+
+    // 子任务完成进度
+    fn subtask_progress(&self) -> Result<Vec<TaskProgress>, String> {
+        let conn = self.connect()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT s.task_id, COUNT(*), SUM(s.completed) FROM subtasks s
+             JOIN tasks t ON t.id = s.task_id
+             WHERE t.deleted = 0
+             GROUP BY s.task_id
+             ORDER BY s.task_id",
+        ).map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let progress = stmt.query_map([], |row| Ok(TaskProgress::new(row.get(0)?, row.get(1)?, row.get(2)?)))
+            .map_err(|e| format!("Failed to query subtask progress: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect subtask progress: {}", e))?;
+
+        Ok(progress)
+    }
+
+    // ========== 清单 ==========
+
+    // 获取所有清单
+    fn lists(&self) -> Result<Vec<TaskList>, String> {
+        let conn = self.connect()?;
+
+        let mut stmt = conn.prepare("SELECT * FROM lists ORDER BY \"order\" ASC")
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let lists = stmt.query_map([], |row| {
+            Ok(TaskList {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                icon: row.get(2)?,
+                color: row.get(3)?,
+                order: row.get(4)?,
+                created_at: row.get(5)?,
+            })
+        })
+        .map_err(|e| format!("Failed to query lists: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect lists: {}", e))?;
+
+        Ok(lists)
+    }
+
+    // 保存清单
+    fn save_list(&self, list: &TaskList) -> Result<(), String> {
+        let conn = self.connect()?;
+
+        conn.execute(
+            "INSERT OR REPLACE INTO lists (id, name, icon, color, \"order\", created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![list.id, list.name, list.icon, list.color, list.order, list.created_at],
+        ).map_err(|e| format!("Failed to save list: {}", e))?;
+
+        record_change(&conn, "list", &list.id, "upsert")?;
+
+        Ok(())
+    }
+
+    // 删除清单（返回是否存在）
+    fn delete_list(&self, id: &str) -> Result<bool, String> {
+        let conn = self.connect()?;
+
+        let deleted = conn.execute("DELETE FROM lists WHERE id = ?1", params![id])
+            .map_err(|e| format!("Failed to delete list: {}", e))?;
+        if deleted > 0 {
+            record_change(&conn, "list", id, "delete")?;
+        }
+
+        Ok(deleted > 0)
+    }
+
+    // 批量应用清单变更
+    fn apply_list_changes(&self, upserts: &[TaskList], deletes: &[String], record: bool) -> Result<(), String> {
+        let mut conn = self.connect()?;
+        let tx = conn.savepoint()
+            .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+
+        for list in upserts {
+            tx.execute(
+                "INSERT OR REPLACE INTO lists (id, name, icon, color, \"order\", created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![list.id, list.name, list.icon, list.color, list.order, list.created_at],
+            ).map_err(|e| format!("Failed to save list: {}", e))?;
+            if record {
+                record_change(&tx, "list", &list.id, "upsert")?;
+            }
+        }
+        for id in deletes {
+            let deleted = tx.execute("DELETE FROM lists WHERE id = ?1", params![id])
+                .map_err(|e| format!("Failed to delete list: {}", e))?;
+            if record && deleted > 0 {
+                record_change(&tx, "list", id, "delete")?;
+            }
+        }
+
+        tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+        Ok(())
+    }
+
+    // 统计任务与清单数量
+    fn count_rows(&self) -> Result<(i64, i64), String> {
+        let conn = self.connect()?;
+
+        let task_count: i64 = conn.query_row("SELECT COUNT(*) FROM tasks", [], |row| row.get(0))
+            .map_err(|e| format!("Failed to count tasks: {}", e))?;
+        let list_count: i64 = conn.query_row("SELECT COUNT(*) FROM lists", [], |row| row.get(0))
+            .map_err(|e| format!("Failed to count lists: {}", e))?;
+
+        Ok((task_count, list_count))
+    }
+
+    // ========== 提醒记录 ==========
+
+    // 检查提醒是否已发送
+    fn is_reminder_sent(&self, task_id: &str, reminder_time: i64) -> Result<bool, String> {
+        let conn = self.connect()?;
+
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM sent_reminders WHERE task_id = ?1 AND reminder_time = ?2",
+            params![task_id, reminder_time],
+            |row| row.get(0),
+        ).map_err(|e| format!("Failed to query sent reminder: {}", e))?;
+
+        Ok(count > 0)
+    }
+
+    // 记录已发送的提醒
+    fn save_sent_reminder(&self, id: &str, task_id: &str, reminder_time: i64, reminder_data: &str) -> Result<(), String> {
+        let conn = self.connect()?;
+
+        let sent_at = chrono::Utc::now().timestamp();
+
+        conn.execute(
+            "INSERT OR REPLACE INTO sent_reminders (id, task_id, reminder_time, sent_at, reminder_data) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![id, task_id, reminder_time, sent_at, reminder_data],
+        ).map_err(|e| format!("Failed to save sent reminder: {}", e))?;
+
+        Ok(())
+    }
+
+    // 清理 30 天前的提醒记录
+    fn cleanup_old_reminders(&self) -> Result<(), String> {
+        let conn = self.connect()?;
+
+        let thirty_days_ago = chrono::Utc::now().timestamp() - (30 * 24 * 60 * 60);
+
+        conn.execute("DELETE FROM sent_reminders WHERE sent_at < ?1", params![thirty_days_ago])
+            .map_err(|e| format!("Failed to cleanup old reminders: {}", e))?;
+
+        Ok(())
+    }
+
+    // 删除任务的提醒记录
+    fn delete_task_reminders(&self, task_id: &str) -> Result<(), String> {
+        let conn = self.connect()?;
+
+        conn.execute("DELETE FROM sent_reminders WHERE task_id = ?1", params![task_id])
+            .map_err(|e| format!("Failed to delete task reminders: {}", e))?;
+
+        Ok(())
+    }
+
+    // ========== 变更记录与同步状态 ==========
+
+    // 获取 seq 大于指定值的变更
+    fn changes_since(&self, after_seq: i64) -> Result<Vec<ChangeRecord>, String> {
+        let conn = self.connect()?;
+
+        let mut stmt = conn.prepare("SELECT seq, entity, entity_id, op, changed_at FROM changes WHERE seq > ?1 ORDER BY seq ASC")
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let changes = stmt.query_map(params![after_seq], |row| {
+            Ok(ChangeRecord {
+                seq: row.get(0)?,
+                entity: row.get(1)?,
+                entity_id: row.get(2)?,
+                op: row.get(3)?,
+                changed_at: row.get(4)?,
+            })
+        })
+        .map_err(|e| format!("Failed to query changes: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect changes: {}", e))?;
+
+        Ok(changes)
+    }
+
+    // 获取当前最大的变更序号
+    fn max_change_seq(&self) -> Result<i64, String> {
+        let conn = self.connect()?;
+
+        conn.query_row("SELECT COALESCE(MAX(seq), 0) FROM changes", [], |row| row.get(0))
+            .map_err(|e| format!("Failed to query changes: {}", e))
+    }
+
+    // 读取同步状态
+    fn sync_state(&self, key: &str) -> Result<Option<String>, String> {
+        let conn = self.connect()?;
+
+        let mut stmt = conn.prepare("SELECT value FROM sync_state WHERE key = ?1")
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let mut rows = stmt.query_map(params![key], |row| row.get::<_, String>(0))
+            .map_err(|e| format!("Failed to query sync state: {}", e))?;
+
+        rows.next()
+            .transpose()
+            .map_err(|e| format!("Failed to read sync state: {}", e))
+    }
+
+    // 写入同步状态
+    fn set_sync_state(&self, key: &str, value: &str) -> Result<(), String> {
+        let conn = self.connect()?;
+
+        conn.execute(
+            "INSERT OR REPLACE INTO sync_state (key, value) VALUES (?1, ?2)",
+            params![key, value],
+        ).map_err(|e| format!("Failed to save sync state: {}", e))?;
+
+        Ok(())
+    }
+
+    // ========== 任务冲突 ==========
+
+    // 记录冲突（同一任务的未解决冲突只保留最新一条）
+    fn save_task_conflicts(&self, conflicts: &[TaskConflict]) -> Result<(), String> {
+        if conflicts.is_empty() {
+            return Ok(());
+        }
+
+        let mut conn = self.connect()?;
+        let tx = conn.savepoint()
+            .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+
+        for conflict in conflicts {
+            let local_data = serde_json::to_string(&conflict.local)
+                .map_err(|e| format!("Failed to serialize task: {}", e))?;
+            let remote_data = serde_json::to_string(&conflict.remote)
+                .map_err(|e| format!("Failed to serialize task: {}", e))?;
+
+            tx.execute(
+                "DELETE FROM task_conflicts WHERE task_id = ?1 AND resolved_at IS NULL",
+                params![conflict.task_id],
+            ).map_err(|e| format!("Failed to save conflict: {}", e))?;
+
+            tx.execute(
+                "INSERT INTO task_conflicts (id, task_id, source, local_data, remote_data, winner, detected_at, resolved_at, resolution) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                params![
+                    conflict.id,
+                    conflict.task_id,
+                    conflict.source,
+                    local_data,
+                    remote_data,
+                    conflict.winner,
+                    conflict.detected_at,
+                    conflict.resolved_at,
+                    conflict.resolution,
+                ],
+            ).map_err(|e| format!("Failed to save conflict: {}", e))?;
+        }
+
+        tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+        Ok(())
+    }
+
+    // 获取冲突列表（默认只返回未解决的）
+    fn task_conflicts(&self, include_resolved: bool) -> Result<Vec<TaskConflict>, String> {
+        let conn = self.connect()?;
+
+        let sql = if include_resolved {
+            "SELECT id, task_id, source, local_data, remote_data, winner, detected_at, resolved_at, resolution FROM task_conflicts ORDER BY detected_at DESC"
+        } else {
+            "SELECT id, task_id, source, local_data, remote_data, winner, detected_at, resolved_at, resolution FROM task_conflicts WHERE resolved_at IS NULL ORDER BY detected_at DESC"
+        };
+        let mut stmt = conn.prepare(sql)
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let conflicts = stmt.query_map([], row_to_conflict)
+            .map_err(|e| format!("Failed to query conflicts: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect conflicts: {}", e))?;
+
+        Ok(conflicts)
+    }
+
+    // 获取单个冲突
+    fn task_conflict(&self, id: &str) -> Result<Option<TaskConflict>, String> {
+        let conn = self.connect()?;
+
+        let mut stmt = conn.prepare("SELECT id, task_id, source, local_data, remote_data, winner, detected_at, resolved_at, resolution FROM task_conflicts WHERE id = ?1")
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let mut rows = stmt.query_map(params![id], row_to_conflict)
+            .map_err(|e| format!("Failed to query conflict: {}", e))?;
+
+        rows.next()
+            .transpose()
+            .map_err(|e| format!("Failed to read conflict: {}", e))
+    }
+
+    // 标记冲突已解决
+    fn resolve_task_conflict(&self, id: &str, resolution: &str) -> Result<(), String> {
+        let conn = self.connect()?;
+
+        conn.execute(
+            "UPDATE task_conflicts SET resolved_at = ?1, resolution = ?2 WHERE id = ?3",
+            params![chrono::Utc::now().to_rfc3339(), resolution, id],
+        ).map_err(|e| format!("Failed to resolve conflict: {}", e))?;
+
+        Ok(())
+    }
+}
+
+// 写入任务与子任务（不记录变更）；使用 ON CONFLICT 更新而非 REPLACE，保证全文索引触发器生效
+fn upsert_task(conn: &Connection, task: &Task) -> Result<(), String> {
+    let previous = load_subtasks(conn, &task.id)?;
+    let stored = select_tasks(conn, &format!("SELECT {} FROM tasks WHERE id = ?1", TASK_COLUMNS), &task.id)?
+        .into_iter()
+        .next();
+    let mut task = task.clone();
+    let subtasks = normalize_task_subtasks(&mut task, &previous);
+    task.normalize_completed_at(stored.as_ref().and_then(|t| t.completed_at.clone()));
+
+    conn.execute(
+        "INSERT INTO tasks (id, title, description, completed, priority, status, list_id, tags, sub_tasks, reminders, due_date, created_at, updated_at, \"order\", category_id, deleted, deleted_at, due_at, parent_id, estimate_minutes, story_points, completed_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22)
+         ON CONFLICT(id) DO UPDATE SET
+             title = excluded.title, description = excluded.description, completed = excluded.completed,
+             priority = excluded.priority, status = excluded.status, list_id = excluded.list_id, tags = excluded.tags,
+             sub_tasks = excluded.sub_tasks, reminders = excluded.reminders, due_date = excluded.due_date,
+             created_at = excluded.created_at, updated_at = excluded.updated_at, \"order\" = excluded.\"order\",
+             category_id = excluded.category_id, deleted = excluded.deleted, deleted_at = excluded.deleted_at,
+             due_at = excluded.due_at, parent_id = excluded.parent_id,
+             estimate_minutes = excluded.estimate_minutes, story_points = excluded.story_points,
+             completed_at = excluded.completed_at",
+        params![
+            task.id,
+            task.title,
+            task.description,
+            task.completed as i32,
+            task.priority,
+            task.status,
+            task.list_id,
+            task.tags,
+            task.sub_tasks,
+            task.reminders,
+            task.due_date,
+            task.created_at,
+            task.updated_at,
+            task.order,
+            task.category_id,
+            task.deleted as i32,
+            task.deleted_at,
+            task.due_date.as_deref().and_then(due_timestamp),
+            task.parent_id,
+            task.estimate_minutes,
+            task.story_points,
+            task.completed_at,
+        ],
+    ).map_err(|e| format!("Failed to save task: {}", e))?;
+
+    write_subtasks(conn, &task.id, &subtasks)?;
+
+    // 记录字段变更
+    let now = chrono::Utc::now().to_rfc3339();
+    for event in task_events(stored.as_ref(), &task, &now) {
+        conn.execute(
+            "INSERT INTO task_events (task_id, field, old_value, new_value, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![event.task_id, event.field, event.old_value, event.new_value, event.created_at],
+        ).map_err(|e| format!("Failed to record task event: {}", e))?;
+    }
+    Ok(())
+}
+
+// 读取任务的子任务（按 order 排序）
+fn load_subtasks(conn: &Connection, task_id: &str) -> Result<Vec<SubTask>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT id, title, completed, completed_at, due_date, \"order\" FROM subtasks WHERE task_id = ?1 ORDER BY \"order\" ASC",
+    ).map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let subtasks = stmt.query_map(params![task_id], |row| {
+        Ok(SubTask {
+            id: row.get(0)?,
+            title: row.get(1)?,
+            completed: row.get::<_, i32>(2)? != 0,
+            completed_at: row.get(3)?,
+            due_date: row.get(4)?,
+            order: row.get(5)?,
+        })
+    })
+    .map_err(|e| format!("Failed to query subtasks: {}", e))?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| format!("Failed to collect subtasks: {}", e))?;
+
+    Ok(subtasks)
+}
+
+// 用给定列表替换任务的子任务
+fn write_subtasks(conn: &Connection, task_id: &str, subtasks: &[SubTask]) -> Result<(), String> {
+    conn.execute("DELETE FROM subtasks WHERE task_id = ?1", params![task_id])
+        .map_err(|e| format!("Failed to save subtasks: {}", e))?;
+
+    for sub in subtasks {
+        conn.execute(
+            "INSERT OR REPLACE INTO subtasks (id, task_id, title, completed, completed_at, due_date, \"order\") VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![sub.id, task_id, sub.title, sub.completed as i32, sub.completed_at, sub.due_date, sub.order],
+        ).map_err(|e| format!("Failed to save subtasks: {}", e))?;
+    }
+
+    Ok(())
+}
+
+// 从 tasks.sub_tasks 导入子任务（已完成的子任务以任务更新时间作为完成时间），并写回整理后的 JSON
+fn migrate_subtasks(conn: &Connection) -> Result<(), String> {
+    let mut stmt = conn.prepare("SELECT id, sub_tasks, updated_at FROM tasks WHERE sub_tasks NOT IN ('', '[]')")
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+    let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)))
+        .map_err(|e| format!("Failed to query tasks: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect tasks: {}", e))?;
+
+    for (task_id, json, updated_at) in rows {
+        let mut subtasks = parse_subtasks(&json);
+        for (index, sub) in subtasks.iter_mut().enumerate() {
+            sub.order = index as i32;
+            if sub.completed && sub.completed_at.is_none() {
+                sub.completed_at = Some(updated_at.clone());
+            }
+        }
+        write_subtasks(conn, &task_id, &subtasks)?;
+        conn.execute("UPDATE tasks SET sub_tasks = ?1 WHERE id = ?2", params![subtasks_json(&subtasks), task_id])
+            .map_err(|e| format!("Failed to update task: {}", e))?;
+    }
+    Ok(())
+}
+
+// 读取撤销日志：undone 为 false 时按从新到旧返回可撤销的操作，为 true 时按从旧到新返回可重做的操作
+fn select_operations(conn: &Connection, undone: bool, limit: usize) -> Result<Vec<UndoOperation>, String> {
+    let order = if undone { "ASC" } else { "DESC" };
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id, label, before_state, after_state, undone, created_at FROM undo_log
+         WHERE undone = ?1 ORDER BY id {} LIMIT ?2",
+        order
+    )).map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let rows = stmt.query_map(params![undone as i32, limit as i64], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, String>(3)?,
+            row.get::<_, i32>(4)? != 0,
+            row.get::<_, String>(5)?,
+        ))
+    })
+    .map_err(|e| format!("Failed to query undo log: {}", e))?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| format!("Failed to collect undo log: {}", e))?;
+
+    rows.into_iter()
+        .map(|(id, label, before, after, undone, created_at)| {
+            let parse = |data: &str| {
+                serde_json::from_str::<Vec<TaskSnapshot>>(data)
+                    .map_err(|e| format!("Failed to parse undo log {}: {}", id, e))
+            };
+            Ok(UndoOperation { id, label, before: parse(&before)?, after: parse(&after)?, undone, created_at })
+        })
+        .collect()
+}
+
+// 记录实体变更（同一实体只保留最新一条，seq 重新分配以保证单调递增）
+fn record_change(conn: &Connection, entity: &str, entity_id: &str, op: &str) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM changes WHERE entity = ?1 AND entity_id = ?2",
+        params![entity, entity_id],
+    ).map_err(|e| format!("Failed to record change: {}", e))?;
+
+    conn.execute(
+        "INSERT INTO changes (entity, entity_id, op, changed_at) VALUES (?1, ?2, ?3, ?4)",
+        params![entity, entity_id, op, chrono::Utc::now().to_rfc3339()],
+    ).map_err(|e| format!("Failed to record change: {}", e))?;
+
+    Ok(())
+}
+
+fn row_to_task(row: &rusqlite::Row) -> rusqlite::Result<Task> {
+    Ok(Task {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        description: row.get(2)?,
+        completed: row.get::<_, i32>(3)? != 0,
+        priority: row.get(4)?,
+        status: row.get(5)?,
+        list_id: row.get(6)?,
+        tags: row.get(7)?,
+        sub_tasks: row.get(8)?,
+        reminders: row.get(9)?,
+        due_date: row.get(10)?,
+        created_at: row.get(11)?,
+        updated_at: row.get(12)?,
+        order: row.get(13)?,
+        category_id: row.get(14)?,
+        deleted: row.get::<_, i32>(15)? != 0,
+        deleted_at: row.get(16)?,
+        parent_id: row.get(17)?,
+        estimate_minutes: row.get(18)?,
+        story_points: row.get(19)?,
+        completed_at: row.get(20)?,
+        blocked: row.get(21)?,
+    })
+}
+
+fn row_to_time_entry(row: &rusqlite::Row) -> rusqlite::Result<TimeEntry> {
+    Ok(TimeEntry {
+        id: row.get(0)?,
+        task_id: row.get(1)?,
+        started_at: row.get(2)?,
+        ended_at: row.get(3)?,
+        note: row.get(4)?,
+        manual: row.get::<_, i32>(5)? != 0,
+        created_at: row.get(6)?,
+    })
+}
+
+fn running_timer(conn: &Connection) -> Result<Option<TimeEntry>, String> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM time_entries WHERE ended_at IS NULL", TIME_ENTRY_COLUMNS))
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let mut rows = stmt.query_map([], row_to_time_entry)
+        .map_err(|e| format!("Failed to query running timer: {}", e))?;
+
+    rows.next()
+        .transpose()
+        .map_err(|e| format!("Failed to read running timer: {}", e))
+}
+
+fn insert_time_entry(conn: &Connection, entry: &TimeEntry) -> Result<(), String> {
+    conn.execute(
+        &format!("INSERT OR REPLACE INTO time_entries ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)", TIME_ENTRY_COLUMNS),
+        params![
+            entry.id,
+            entry.task_id,
+            entry.started_at,
+            entry.ended_at,
+            entry.note,
+            entry.manual as i32,
+            entry.created_at,
+        ],
+    ).map_err(|e| format!("Failed to save time entry: {}", e))?;
+    Ok(())
+}
+
+// 在 now 结束计时（不早于开始时间），返回结束后的记录
+fn finish_timer(conn: &Connection, running: &TimeEntry, now: i64) -> Result<TimeEntry, String> {
+    let ended_at = now.max(running.started_at);
+    conn.execute("UPDATE time_entries SET ended_at = ?1 WHERE id = ?2", params![ended_at, running.id])
+        .map_err(|e| format!("Failed to stop timer: {}", e))?;
+    Ok(TimeEntry { ended_at: Some(ended_at), ..running.clone() })
+}
+
+// 执行以单个 ID 为参数的任务查询
+fn select_tasks(conn: &Connection, sql: &str, id: &str) -> Result<Vec<Task>, String> {
+    let mut stmt = conn.prepare(sql)
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let tasks = stmt.query_map(params![id], row_to_task)
+        .map_err(|e| format!("Failed to query tasks: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect tasks: {}", e))?;
+
+    Ok(tasks)
+}
+
+// 写回撤销删除时保存的关联记录（已存在的记录保持不变，另一端任务不存在的依赖不写回）
+fn restore_records(conn: &Connection, records: &TaskRecords) -> Result<(), String> {
+    for entry in &records.time_entries {
+        conn.execute(
+            "INSERT OR IGNORE INTO time_entries (id, task_id, started_at, ended_at, note, manual, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![entry.id, entry.task_id, entry.started_at, entry.ended_at, entry.note, entry.manual, entry.created_at],
+        ).map_err(|e| format!("Failed to restore time entry: {}", e))?;
+    }
+    for record in &records.pomodoros {
+        conn.execute(
+            "INSERT OR IGNORE INTO pomodoros (id, task_id, started_at, ended_at) VALUES (?1, ?2, ?3, ?4)",
+            params![record.id, record.task_id, record.started_at, record.ended_at],
+        ).map_err(|e| format!("Failed to restore pomodoro: {}", e))?;
+    }
+    for dependency in &records.dependencies {
+        conn.execute(
+            "INSERT OR IGNORE INTO task_dependencies (task_id, blocked_by, created_at)
+             SELECT ?1, ?2, ?3 WHERE EXISTS (SELECT 1 FROM tasks WHERE id = ?1) AND EXISTS (SELECT 1 FROM tasks WHERE id = ?2)",
+            params![dependency.task_id, dependency.blocked_by, dependency.created_at],
+        ).map_err(|e| format!("Failed to restore task dependency: {}", e))?;
+    }
+    Ok(())
+}
+
+// 检查父任务存在且不是任务自身或其后代
+fn check_parent(conn: &Connection, task_id: &str, parent_id: &str) -> Result<(), String> {
+    let exists = conn
+        .prepare("SELECT 1 FROM tasks WHERE id = ?1")
+        .and_then(|mut stmt| stmt.exists(params![parent_id]))
+        .map_err(|e| format!("Failed to query task: {}", e))?;
+    if !exists {
+        return Err(format!("Parent task not found: {}", parent_id));
+    }
+    if creates_cycle(conn, task_id, parent_id)? {
+        return Err("Cannot move a task under itself or its own descendant".to_string());
+    }
+    Ok(())
+}
+
+// 删除任务及其所有后代与相关的提醒记录（子任务、计时等由触发器删除），返回被删除的任务 ID
+fn delete_subtree(conn: &Connection, id: &str) -> Result<Vec<String>, String> {
+    let ids: Vec<String> = conn
+        .prepare(
+            "WITH RECURSIVE tree(id) AS (
+                SELECT ?1
+                UNION
+                SELECT t.id FROM tasks t JOIN tree ON t.parent_id = tree.id
+            )
+            SELECT id FROM tree",
+        )
+        .and_then(|mut stmt| stmt.query_map(params![id], |row| row.get(0))?.collect())
+        .map_err(|e| format!("Failed to query task tree: {}", e))?;
+
+    for id in &ids {
+        remove_task(conn, id)?;
+    }
+    Ok(ids)
+}
+
+// 删除单个任务及其提醒记录，并记录 deleted 事件（任务不存在时不做任何事）
+fn remove_task(conn: &Connection, id: &str) -> Result<(), String> {
+    let title: Option<String> = conn
+        .query_row("SELECT title FROM tasks WHERE id = ?1", params![id], |row| row.get(0))
+        .optional()
+        .map_err(|e| format!("Failed to query task: {}", e))?;
+    let Some(title) = title else {
+        return Ok(());
+    };
+
+    conn.execute("DELETE FROM tasks WHERE id = ?1", params![id])
+        .map_err(|e| format!("Failed to delete task: {}", e))?;
+    conn.execute("DELETE FROM sent_reminders WHERE task_id = ?1", params![id])
+        .map_err(|e| format!("Failed to delete task reminders: {}", e))?;
+    let event = deleted_event(id, &title, &chrono::Utc::now().to_rfc3339());
+    conn.execute(
+        "INSERT INTO task_events (task_id, field, old_value, new_value, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![event.task_id, event.field, event.old_value, event.new_value, event.created_at],
+    ).map_err(|e| format!("Failed to record task event: {}", e))?;
+    Ok(())
+}
+
+// 把任务挂到 parent_id 下是否会形成环（parent_id 为任务自身或其后代）
+fn creates_cycle(conn: &Connection, task_id: &str, parent_id: &str) -> Result<bool, String> {
+    conn.prepare(
+        "WITH RECURSIVE chain(id) AS (
+            SELECT ?1
+            UNION
+            SELECT t.parent_id FROM tasks t JOIN chain ON t.id = chain.id WHERE t.parent_id IS NOT NULL
+        )
+        SELECT 1 FROM chain WHERE id = ?2",
+    )
+    .and_then(|mut stmt| stmt.exists(params![parent_id, task_id]))
+    .map_err(|e| format!("Failed to check task hierarchy: {}", e))
+}
+
+// 表中缺少该列时添加（返回是否新增）
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<bool, String> {
+    let exists = conn
+        .prepare(&format!("SELECT 1 FROM pragma_table_info('{}') WHERE name = ?1", table))
+        .and_then(|mut stmt| stmt.exists(params![column]))
+        .map_err(|e| format!("Failed to inspect {} table: {}", table, e))?;
+    if exists {
+        return Ok(false);
+    }
+
+    conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition), [])
+        .map_err(|e| format!("Failed to add {}.{} column: {}", table, column, e))?;
+    Ok(true)
+}
+
+// 为已有任务计算截止时间戳
+fn backfill_due_at(conn: &Connection) -> Result<(), String> {
+    let mut stmt = conn.prepare("SELECT id, due_date FROM tasks WHERE due_date IS NOT NULL")
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+    let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| format!("Failed to query tasks: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect tasks: {}", e))?;
+
+    for (id, due_date) in rows {
+        conn.execute("UPDATE tasks SET due_at = ?1 WHERE id = ?2", params![due_timestamp(&due_date), id])
+            .map_err(|e| format!("Failed to update task: {}", e))?;
+    }
+    Ok(())
+}
+
+// 全文索引的列值（row 为 new 或 tasks）：标题、描述与子任务标题（每行一个）
+fn fts_values(row: &str) -> String {
+    format!(
+        "{row}.rowid, {row}.title, coalesce({row}.description, ''), \
+         (SELECT coalesce(group_concat(json_extract(value, '$.title'), char(10)), '') \
+          FROM json_each(CASE WHEN json_valid({row}.sub_tasks) THEN {row}.sub_tasks ELSE '[]' END))",
+        row = row
+    )
+}
+
+// n 个 SQL 占位符
+fn placeholders(n: usize) -> String {
+    vec!["?"; n].join(", ")
+}
+
+// 转义 LIKE 通配符（配合 ESCAPE '\'）
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+fn row_to_conflict(row: &rusqlite::Row) -> rusqlite::Result<TaskConflict> {
+    let parse = |idx: usize, data: String| {
+        serde_json::from_str::<Task>(&data).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(idx, rusqlite::types::Type::Text, Box::new(e))
+        })
+    };
+
+    Ok(TaskConflict {
+        id: row.get(0)?,
+        task_id: row.get(1)?,
+        source: row.get(2)?,
+        local: parse(3, row.get(3)?)?,
+        remote: parse(4, row.get(4)?)?,
+        winner: row.get(5)?,
+        detected_at: row.get(6)?,
+        resolved_at: row.get(7)?,
+        resolution: row.get(8)?,
+    })
+}
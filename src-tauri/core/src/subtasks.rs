@@ -0,0 +1,199 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::model::{SubTask, Task};
+
+// 任务的子任务完成进度
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskProgress {
+    pub task_id: String,
+    pub total: i64,
+    pub completed: i64,
+    // 完成百分比（四舍五入到整数）
+    pub percent: u8,
+}
+
+impl TaskProgress {
+    pub fn new(task_id: String, total: i64, completed: i64) -> TaskProgress {
+        let percent = if total > 0 { (completed * 100 + total / 2) / total } else { 0 };
+        TaskProgress {
+            task_id,
+            total,
+            completed,
+            percent: percent.clamp(0, 100) as u8,
+        }
+    }
+}
+
+// 解析子任务 JSON（缺少 id 的条目补上新 id，无法解析的条目忽略）
+pub fn parse_subtasks(json: &str) -> Vec<SubTask> {
+    serde_json::from_str::<Vec<serde_json::Value>>(json)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|mut value| {
+            if let Some(obj) = value.as_object_mut() {
+                obj.entry("id").or_insert_with(|| uuid::Uuid::new_v4().to_string().into());
+            }
+            serde_json::from_value(value).ok()
+        })
+        .collect()
+}
+
+// 子任务序列化为 JSON（写回 Task::sub_tasks）
+pub fn subtasks_json(subtasks: &[SubTask]) -> String {
+    serde_json::to_string(subtasks).unwrap_or_else(|_| "[]".to_string())
+}
+
+// 整理子任务：去掉重复 id，order 按数组顺序重新编号；
+// 完成时间沿用传入值或旧记录，首次完成时取当前时间，未完成时清空
+pub fn normalize_subtasks(previous: &[SubTask], incoming: Vec<SubTask>) -> Vec<SubTask> {
+    let now = chrono::Utc::now().to_rfc3339();
+    let mut seen = HashSet::new();
+
+    incoming
+        .into_iter()
+        .filter(|sub| seen.insert(sub.id.clone()))
+        .enumerate()
+        .map(|(index, mut sub)| {
+            sub.order = index as i32;
+            sub.completed_at = if sub.completed {
+                sub.completed_at
+                    .or_else(|| {
+                        previous
+                            .iter()
+                            .find(|p| p.id == sub.id && p.completed)
+                            .and_then(|p| p.completed_at.clone())
+                    })
+                    .or_else(|| Some(now.clone()))
+            } else {
+                None
+            };
+            sub
+        })
+        .collect()
+}
+
+// 整理任务的子任务 JSON（previous 为保存前的子任务），返回整理后的子任务
+pub fn normalize_task_subtasks(task: &mut Task, previous: &[SubTask]) -> Vec<SubTask> {
+    let subtasks = normalize_subtasks(previous, parse_subtasks(&task.sub_tasks));
+    task.sub_tasks = subtasks_json(&subtasks);
+    subtasks
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::TaskProgress;
+    use crate::testing::{save, with_repositories};
+
+    #[test]
+    fn add_toggle_and_reorder() {
+        with_repositories(|repo| {
+            save(repo, "a", json!({}));
+            assert!(repo.add_subtask("a", "  ", None).unwrap_err().contains("required"));
+
+            let first = repo.add_subtask("a", " first ", None).unwrap();
+            let second = repo.add_subtask("a", "second", Some("2024-05-01T00:00:00Z".into())).unwrap();
+            assert_eq!((first.title.as_str(), first.order), ("first", 0));
+            assert_eq!(second.order, 1);
+            assert_eq!(repo.subtask_parent(&second.id).unwrap().as_deref(), Some("a"));
+
+            // 完成时记录完成时间，取消完成时清空
+            let done = repo.toggle_subtask(&first.id).unwrap();
+            assert!(done.completed && done.completed_at.is_some());
+            let undone = repo.toggle_subtask(&first.id).unwrap();
+            assert!(!undone.completed && undone.completed_at.is_none());
+
+            let third = repo.add_subtask("a", "third", None).unwrap();
+            // 未列出的子任务保持原顺序排在后面
+            let ordered = repo.reorder_subtasks("a", std::slice::from_ref(&third.id)).unwrap();
+            let ids: Vec<&str> = ordered.iter().map(|s| s.id.as_str()).collect();
+            assert_eq!(ids, [third.id.as_str(), first.id.as_str(), second.id.as_str()]);
+            assert_eq!(ordered.iter().map(|s| s.order).collect::<Vec<_>>(), [0, 1, 2]);
+            assert_eq!(repo.subtasks("a").unwrap().len(), 3);
+            assert!(repo.reorder_subtasks("a", &["missing".to_string()]).is_err());
+        });
+    }
+
+    #[test]
+    fn promote_moves_subtask_to_a_task() {
+        with_repositories(|repo| {
+            save(repo, "a", json!({ "listId": "work", "categoryId": "c1" }));
+            let sub = repo.add_subtask("a", "promoted", Some("2024-05-01T00:00:00Z".into())).unwrap();
+            repo.add_subtask("a", "kept", None).unwrap();
+            repo.toggle_subtask(&sub.id).unwrap();
+
+            let task = repo.promote_subtask(&sub.id).unwrap();
+            let task = repo.task(&task.id).unwrap().unwrap();
+            assert_eq!(task.title, "promoted");
+            assert_eq!(task.list_id, "work");
+            assert_eq!(task.category_id.as_deref(), Some("c1"));
+            assert_eq!(task.due_date.as_deref(), Some("2024-05-01T00:00:00Z"));
+            assert!(task.completed);
+            assert_eq!(task.status, "done");
+
+            let remaining = repo.subtasks("a").unwrap();
+            assert_eq!(remaining.len(), 1);
+            assert_eq!((remaining[0].title.as_str(), remaining[0].order), ("kept", 0));
+            assert!(repo.subtask_parent(&sub.id).unwrap().is_none());
+            assert!(repo.promote_subtask(&sub.id).is_err());
+        });
+    }
+
+    #[test]
+    fn progress() {
+        with_repositories(|repo| {
+            save(repo, "a", json!({}));
+            save(repo, "empty", json!({}));
+            save(repo, "trashed", json!({ "deleted": true }));
+            repo.add_subtask("trashed", "hidden", None).unwrap();
+            let subs: Vec<_> = (0..3).map(|i| repo.add_subtask("a", &format!("s{}", i), None).unwrap()).collect();
+            repo.toggle_subtask(&subs[0].id).unwrap();
+            repo.toggle_subtask(&subs[1].id).unwrap();
+
+            let progress = repo.subtask_progress().unwrap();
+            assert_eq!(progress.len(), 1);
+            assert_eq!((progress[0].task_id.as_str(), progress[0].total, progress[0].completed), ("a", 3, 2));
+            assert_eq!(progress[0].percent, 67);
+        });
+        assert_eq!(TaskProgress::new("x".into(), 0, 0).percent, 0);
+        assert_eq!(TaskProgress::new("x".into(), 3, 1).percent, 33);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn migrates_legacy_json_subtasks() {
+        use crate::repository::TaskRepository;
+        use crate::store::Store;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("todo.db");
+        let store = Store::open(&path).unwrap();
+        save(&store, "a", json!({ "updatedAt": "2024-01-02T00:00:00Z" }));
+        drop(store);
+
+        // 模拟旧版本数据库：没有 subtasks 表，子任务只保存在 sub_tasks JSON 中
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "DROP TABLE subtasks;
+             UPDATE tasks SET sub_tasks = '[{\"title\":\"legacy\",\"completed\":true},{\"id\":\"s2\",\"title\":\"open\",\"order\":7}]',
+                 updated_at = '2024-01-02T00:00:00Z'
+             WHERE id = 'a';",
+        )
+        .unwrap();
+        drop(conn);
+
+        let store = Store::open(&path).unwrap();
+        let subtasks = store.subtasks("a").unwrap();
+        assert_eq!(subtasks.len(), 2);
+        assert_eq!((subtasks[0].title.as_str(), subtasks[0].order), ("legacy", 0));
+        assert!(!subtasks[0].id.is_empty());
+        // 已完成但没有完成时间的子任务取任务的更新时间
+        assert_eq!(subtasks[0].completed_at.as_deref(), Some("2024-01-02T00:00:00Z"));
+        assert_eq!((subtasks[1].id.as_str(), subtasks[1].order), ("s2", 1));
+        assert_eq!(store.subtask_parent("s2").unwrap().as_deref(), Some("a"));
+        assert_eq!(store.subtask_progress().unwrap()[0].completed, 1);
+    }
+}
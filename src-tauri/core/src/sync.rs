@@ -0,0 +1,53 @@
+// REST 同步协议的请求与响应结构（桌面端与自托管同步服务共用）
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::{Task, TaskList};
+
+// 单条同步变更（上传与下载共用）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncChange {
+    // 实体类型："task" 或 "list"
+    pub entity: String,
+    pub id: String,
+    // "upsert" 或 "delete"
+    pub op: String,
+    // 任务 upsert 时的完整数据
+    #[serde(default)]
+    pub data: Option<Task>,
+    // 清单 upsert 时的完整数据
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub list: Option<TaskList>,
+    // 变更时间（任务 upsert 为 updated_at，清单 upsert 与 delete 为变更时间），用于冲突判断
+    pub updated_at: String,
+}
+
+// POST /api/sync/upload 请求体
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadRequest {
+    pub device_id: String,
+    pub changes: Vec<SyncChange>,
+}
+
+// POST /api/sync/upload 响应
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadResponse {
+    // 服务端已接受的实体 ID
+    #[serde(default)]
+    pub accepted: Vec<String>,
+    // 服务端版本更新而被拒绝的变更（返回服务端当前版本）
+    #[serde(default)]
+    pub conflicts: Vec<SyncChange>,
+}
+
+// GET /api/sync/download?since=<cursor> 响应
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadResponse {
+    pub changes: Vec<SyncChange>,
+    // 下次下载使用的游标
+    pub cursor: String,
+}
@@ -0,0 +1,30 @@
+// 测试辅助：同一组断言分别在内存存储与临时 SQLite 数据库上运行
+
+use crate::memory::MemoryStore;
+use crate::model::{task_from_partial, Task};
+use crate::repository::TaskRepository;
+
+// 依次用 MemoryStore 与临时目录中的 Store 执行 check
+pub(crate) fn with_repositories(check: impl Fn(&dyn TaskRepository)) {
+    check(&MemoryStore::new());
+
+    #[cfg(feature = "sqlite")]
+    {
+        let dir = tempfile::tempdir().unwrap();
+        let store = crate::store::Store::open(dir.path().join("todo.db")).unwrap();
+        check(&store);
+    }
+}
+
+// 由 JSON 字段构造任务（标题默认为 ID）
+pub(crate) fn task(id: &str, extra: serde_json::Value) -> Task {
+    let mut value = serde_json::json!({ "id": id, "title": id });
+    value.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+    task_from_partial(value).unwrap()
+}
+
+// 保存任务并返回保存后的版本
+pub(crate) fn save(repo: &dyn TaskRepository, id: &str, extra: serde_json::Value) -> Task {
+    repo.save_task(&task(id, extra)).unwrap();
+    repo.task(id).unwrap().unwrap()
+}
@@ -0,0 +1,147 @@
+use serde::{Deserialize, Serialize};
+
+use crate::model::TimeEntry;
+
+// 计时汇总的分组方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TimeGroup {
+    #[default]
+    Task,
+    List,
+}
+
+// 计时汇总（id 为任务 ID 或清单 ID，按时长降序）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeTotal {
+    pub id: String,
+    // 总时长（秒），包含正在计时的记录
+    pub seconds: i64,
+    pub entries: i64,
+}
+
+// 开始计时的新记录
+pub fn timer_entry(task_id: &str, now: i64) -> TimeEntry {
+    TimeEntry {
+        id: uuid::Uuid::new_v4().to_string(),
+        task_id: task_id.to_string(),
+        started_at: now,
+        ended_at: None,
+        note: None,
+        manual: false,
+        created_at: chrono::Utc::now().to_rfc3339(),
+    }
+}
+
+// 手动补录的记录（结束时间必须晚于开始时间）
+pub fn manual_entry(task_id: &str, started_at: i64, ended_at: i64, note: Option<String>) -> Result<TimeEntry, String> {
+    if ended_at <= started_at {
+        return Err("End time must be after start time".to_string());
+    }
+    Ok(TimeEntry {
+        ended_at: Some(ended_at),
+        note: note.map(|n| n.trim().to_string()).filter(|n| !n.is_empty()),
+        manual: true,
+        ..timer_entry(task_id, started_at)
+    })
+}
+
+// 按时长降序、ID 升序排列汇总结果
+pub fn sort_totals(totals: &mut [TimeTotal]) {
+    totals.sort_by(|a, b| b.seconds.cmp(&a.seconds).then_with(|| a.id.cmp(&b.id)));
+}
+
+// 时长格式化为 H:MM:SS（用于托盘菜单）
+pub fn format_duration(seconds: i64) -> String {
+    let seconds = seconds.max(0);
+    format!("{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing::{save, with_repositories};
+
+    #[test]
+    fn only_one_timer_runs_at_a_time() {
+        with_repositories(|repo| {
+            save(repo, "a", json!({}));
+            save(repo, "b", json!({}));
+
+            let first = repo.start_timer("a", 1_000).unwrap();
+            // 同一任务再次开始返回正在运行的记录
+            assert_eq!(repo.start_timer("a", 1_100).unwrap().id, first.id);
+
+            let second = repo.start_timer("b", 1_600).unwrap();
+            let running = repo.running_timer().unwrap().unwrap();
+            assert_eq!(running.id, second.id);
+            assert_eq!(repo.time_entries(None).unwrap().iter().filter(|e| e.is_running()).count(), 1);
+            let stopped = repo.time_entries(Some("a")).unwrap();
+            assert_eq!(stopped.len(), 1);
+            assert_eq!(stopped[0].ended_at, Some(1_600));
+
+            assert!(repo.start_timer("missing", 1_700).is_err());
+            assert_eq!(repo.running_timer().unwrap().unwrap().id, second.id);
+        });
+    }
+
+    #[test]
+    fn stop_timer_clamps_to_start_and_is_idempotent() {
+        with_repositories(|repo| {
+            save(repo, "a", json!({}));
+            assert!(repo.stop_timer(1_000).unwrap().is_none());
+
+            repo.start_timer("a", 2_000).unwrap();
+            // 时钟回拨时结束时间不早于开始时间
+            let stopped = repo.stop_timer(1_500).unwrap().unwrap();
+            assert_eq!(stopped.ended_at, Some(2_000));
+            assert!(repo.running_timer().unwrap().is_none());
+            assert!(repo.stop_timer(3_000).unwrap().is_none());
+        });
+    }
+
+    #[test]
+    fn manual_entries_cannot_run() {
+        with_repositories(|repo| {
+            save(repo, "a", json!({}));
+            assert!(repo.add_manual_entry("a", 2_000, 2_000, None).is_err());
+            let entry = repo.add_manual_entry("a", 1_000, 1_900, Some("  call  ".to_string())).unwrap();
+            assert!(entry.manual);
+            assert_eq!(entry.note.as_deref(), Some("call"));
+
+            let running = TimeEntry { ended_at: None, ..entry };
+            assert!(repo.add_time_entry(&running).is_err());
+            assert!(repo.running_timer().unwrap().is_none());
+        });
+    }
+
+    #[test]
+    fn totals_include_running_timer() {
+        with_repositories(|repo| {
+            save(repo, "a", json!({ "listId": "work" }));
+            save(repo, "b", json!({ "listId": "work" }));
+            save(repo, "c", json!({ "listId": "home" }));
+            repo.add_manual_entry("a", 0, 600, None).unwrap();
+            repo.add_manual_entry("c", 0, 900, None).unwrap();
+            repo.start_timer("b", 1_000).unwrap();
+
+            let by_task = repo.time_totals(TimeGroup::Task, 1_500).unwrap();
+            let summary: Vec<(&str, i64, i64)> = by_task.iter().map(|t| (t.id.as_str(), t.seconds, t.entries)).collect();
+            assert_eq!(summary, vec![("c", 900, 1), ("a", 600, 1), ("b", 500, 1)]);
+
+            let by_list = repo.time_totals(TimeGroup::List, 1_500).unwrap();
+            let summary: Vec<(&str, i64)> = by_list.iter().map(|t| (t.id.as_str(), t.seconds)).collect();
+            assert_eq!(summary, vec![("work", 1_100), ("home", 900)]);
+        });
+    }
+
+    #[test]
+    fn formats_durations() {
+        assert_eq!(format_duration(0), "0:00:00");
+        assert_eq!(format_duration(3_725), "1:02:05");
+        assert_eq!(format_duration(-5), "0:00:00");
+    }
+}
@@ -0,0 +1,463 @@
+use serde::{Deserialize, Serialize};
+
+use crate::model::{PomodoroRecord, Task, TaskBatch, TaskDependency, TimeEntry};
+use crate::repository::TaskRepository;
+
+// 撤销日志最多保留的操作数（超出时删除最早的操作）
+pub const UNDO_LIMIT: usize = 100;
+
+// 任务在操作前或操作后的状态（task 为空表示任务不存在）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskSnapshot {
+    pub id: String,
+    pub task: Option<Task>,
+    // 删除任务时由触发器一并删除的记录（只保存在删除操作的 before 中，撤销删除时写回）
+    #[serde(default, skip_serializing_if = "TaskRecords::is_empty")]
+    pub records: TaskRecords,
+}
+
+// 任务的计时、番茄与依赖记录（历史在删除后保留，不需要保存）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TaskRecords {
+    pub time_entries: Vec<TimeEntry>,
+    pub pomodoros: Vec<PomodoroRecord>,
+    pub dependencies: Vec<TaskDependency>,
+}
+
+impl TaskRecords {
+    pub fn is_empty(&self) -> bool {
+        self.time_entries.is_empty() && self.pomodoros.is_empty() && self.dependencies.is_empty()
+    }
+}
+
+// 一次可撤销的操作（保存、删除、移动、重排等）：before / after 为受影响任务在操作前后的状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoOperation {
+    pub id: i64,
+    pub label: String,
+    pub before: Vec<TaskSnapshot>,
+    pub after: Vec<TaskSnapshot>,
+    // 已撤销（可重做）
+    pub undone: bool,
+    pub created_at: String,
+}
+
+// 操作摘要（返回给前端）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoEntry {
+    pub id: i64,
+    pub label: String,
+    pub task_ids: Vec<String>,
+    pub created_at: String,
+}
+
+// 撤销 / 重做栈状态
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UndoState {
+    pub undo_count: i64,
+    pub redo_count: i64,
+    // 下一次撤销、重做的操作
+    pub next_undo: Option<UndoEntry>,
+    pub next_redo: Option<UndoEntry>,
+}
+
+impl UndoOperation {
+    pub fn entry(&self) -> UndoEntry {
+        UndoEntry {
+            id: self.id,
+            label: self.label.clone(),
+            task_ids: self.before.iter().map(|s| s.id.clone()).collect(),
+            created_at: self.created_at.clone(),
+        }
+    }
+}
+
+// 撤销或重做时写回的任务：更新时间改为当前时间，使同步把这次恢复当作新的修改
+pub fn restored_task(task: &Task, now: &str) -> Task {
+    Task { updated_at: now.to_string(), blocked: false, ..task.clone() }
+}
+
+// 读取任务当前状态（blocked 为计算字段，不参与比较与恢复）
+pub fn snapshot(repo: &dyn TaskRepository, ids: &[String]) -> Result<Vec<TaskSnapshot>, String> {
+    ids.iter()
+        .map(|id| {
+            let task = repo.task(id)?.map(|t| Task { blocked: false, ..t });
+            Ok(TaskSnapshot { id: id.clone(), task, records: TaskRecords::default() })
+        })
+        .collect()
+}
+
+// 读取任务的关联记录；正在计时的记录视为在此刻停止（撤销删除时不会恢复出第二个计时器）
+fn task_records(repo: &dyn TaskRepository, id: &str, now: i64) -> Result<TaskRecords, String> {
+    let mut time_entries = repo.time_entries(Some(id))?;
+    for entry in &mut time_entries {
+        entry.ended_at.get_or_insert(now);
+    }
+    Ok(TaskRecords {
+        time_entries,
+        pomodoros: repo.pomodoros(Some(id))?,
+        dependencies: repo.task_dependencies(Some(id))?,
+    })
+}
+
+// 任务及其后代的 ID（with_ancestors 为 true 时包含祖先），用于在操作前确定受影响的任务
+pub fn affected_ids(repo: &dyn TaskRepository, id: &str, with_ancestors: bool) -> Result<Vec<String>, String> {
+    let mut ids = vec![id.to_string()];
+    ids.extend(repo.descendants(id)?.into_iter().map(|t| t.id));
+    if with_ancestors {
+        ids.extend(repo.ancestors(id)?.into_iter().map(|t| t.id));
+    }
+    Ok(ids)
+}
+
+// 批量修改可能影响的任务：更新与重排的任务，以及删除的任务及其后代
+pub fn batch_ids(repo: &dyn TaskRepository, batch: &TaskBatch) -> Result<Vec<String>, String> {
+    let mut ids: Vec<String> = batch.updates.iter().map(|u| u.id.clone()).chain(batch.order.iter().cloned()).collect();
+    for id in &batch.deletes {
+        ids.extend(affected_ids(repo, id, false)?);
+    }
+    ids.sort();
+    ids.dedup();
+    Ok(ids)
+}
+
+// 执行操作并写入撤销日志：ids 为操作可能修改的任务，只记录前后状态不同的任务（没有变化时不记录）；
+// 被删除的任务同时保存其关联记录。快照、操作与日志在同一个事务内完成，操作失败时不写入任何内容
+pub fn undoable<T>(
+    repo: &dyn TaskRepository,
+    label: &str,
+    ids: &[String],
+    op: impl FnOnce(&dyn TaskRepository) -> Result<T, String>,
+) -> Result<T, String> {
+    let mut op = Some(op);
+    let mut result = None;
+    repo.transaction(&mut |repo| {
+        let op = op.take().ok_or("Operation already executed")?;
+        result = Some(logged(repo, label, ids, op)?);
+        Ok(())
+    })?;
+    result.ok_or_else(|| "Operation was not executed".to_string())
+}
+
+fn logged<T>(
+    repo: &dyn TaskRepository,
+    label: &str,
+    ids: &[String],
+    op: impl FnOnce(&dyn TaskRepository) -> Result<T, String>,
+) -> Result<T, String> {
+    let mut before = snapshot(repo, ids)?;
+    let now = chrono::Utc::now().timestamp();
+    for snapshot in before.iter_mut().filter(|s| s.task.is_some()) {
+        snapshot.records = task_records(repo, &snapshot.id, now)?;
+    }
+    let result = op(repo)?;
+    let after = snapshot(repo, ids)?;
+
+    let (before, after): (Vec<TaskSnapshot>, Vec<TaskSnapshot>) = before
+        .into_iter()
+        .zip(after)
+        .filter(|(b, a)| !same_state(&b.task, &a.task, true))
+        .map(|(mut b, a)| {
+            if a.task.is_some() {
+                b.records = TaskRecords::default();
+            }
+            (b, a)
+        })
+        .unzip();
+    if !before.is_empty() {
+        repo.log_operation(label, &before, &after)?;
+    }
+    Ok(result)
+}
+
+// 撤销或重做前检查：受影响任务的当前状态须与操作后（撤销）或操作前（重做）的状态一致，
+// 否则任务在之后又被其他途径修改（子任务、同步、本地 API、CLI 等），写回会覆盖这些修改；
+// 冲突时返回列出所有冲突任务的错误，操作保留在日志中
+pub fn check_replay(
+    operation: &UndoOperation,
+    undo: bool,
+    current: impl Fn(&str) -> Result<Option<Task>, String>,
+) -> Result<(), String> {
+    let expected = if undo { &operation.after } else { &operation.before };
+    let mut conflicts = Vec::new();
+    for snapshot in expected {
+        let task = current(&snapshot.id)?;
+        if !same_state(&task, &snapshot.task, false) {
+            conflicts.push(snapshot.id.as_str());
+        }
+    }
+    if conflicts.is_empty() {
+        return Ok(());
+    }
+    Err(format!(
+        "Cannot {} \"{}\": conflicts with later changes to task {}",
+        if undo { "undo" } else { "redo" },
+        operation.label,
+        conflicts.join(", ")
+    ))
+}
+
+// 比较任务状态（blocked 为计算字段不参与比较；with_time 为 false 时忽略撤销与重做写回时改写的更新时间）
+fn same_state(a: &Option<Task>, b: &Option<Task>, with_time: bool) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => {
+            let normalize = |t: &Task| {
+                let updated_at = if with_time { t.updated_at.clone() } else { String::new() };
+                serde_json::to_value(Task { blocked: false, updated_at, ..t.clone() }).ok()
+            };
+            normalize(a) == normalize(b)
+        }
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::model::{PomodoroRecord, TimeEntry};
+    use crate::testing::{save, with_repositories};
+
+    fn delete(repo: &dyn TaskRepository, id: &str) {
+        let ids = affected_ids(repo, id, false).unwrap();
+        undoable(repo, "delete", &ids, |repo| repo.delete_task_tree(id)).unwrap();
+    }
+
+    #[test]
+    fn undo_delete_restores_related_records() {
+        with_repositories(|repo| {
+            save(repo, "parent", json!({}));
+            save(repo, "child", json!({ "parentId": "parent" }));
+            save(repo, "blocker", json!({}));
+            repo.add_task_dependency("child", "blocker").unwrap();
+            repo.add_manual_entry("parent", 1_000, 1_600, None).unwrap();
+            repo.start_timer("child", 2_000).unwrap();
+            repo.log_pomodoro(&PomodoroRecord {
+                id: "p1".to_string(),
+                task_id: "child".to_string(),
+                started_at: 3_000,
+                ended_at: 4_500,
+            })
+            .unwrap();
+            let title_change = Task { title: "renamed".to_string(), ..repo.task("parent").unwrap().unwrap() };
+            repo.save_task(&title_change).unwrap();
+            let events = repo.task_events("parent").unwrap().len();
+
+            delete(repo, "parent");
+            assert!(repo.task("child").unwrap().is_none());
+            assert!(repo.time_entries(None).unwrap().is_empty());
+            assert!(repo.task_dependencies(None).unwrap().is_empty());
+
+            repo.undo(1).unwrap();
+            assert_eq!(repo.task("child").unwrap().unwrap().parent_id.as_deref(), Some("parent"));
+            let entries: Vec<TimeEntry> = repo.time_entries(None).unwrap();
+            assert_eq!(entries.len(), 2);
+            // 删除时正在计时的记录恢复为已停止，不会同时存在两个计时器
+            assert!(entries.iter().all(|e| e.ended_at.is_some()));
+            assert!(repo.running_timer().unwrap().is_none());
+            assert_eq!(repo.pomodoros(Some("child")).unwrap().len(), 1);
+            assert_eq!(repo.task_dependencies(Some("child")).unwrap().len(), 1);
+            assert!(repo.task("child").unwrap().unwrap().blocked);
+            assert!(repo.task_events("parent").unwrap().len() >= events);
+
+            // 重做再次删除，之后再次撤销仍能恢复
+            repo.redo(1).unwrap();
+            assert!(repo.pomodoros(None).unwrap().is_empty());
+            repo.undo(1).unwrap();
+            assert_eq!(repo.pomodoros(None).unwrap().len(), 1);
+            assert_eq!(repo.time_entries(Some("parent")).unwrap().len(), 1);
+        });
+    }
+
+    #[test]
+    fn undo_refuses_when_task_changed_afterwards() {
+        with_repositories(|repo| {
+            save(repo, "a", json!({ "title": "first" }));
+            let ids = vec!["a".to_string()];
+            undoable(repo, "save", &ids, |repo| {
+                let task = Task { title: "second".to_string(), ..repo.task("a").unwrap().unwrap() };
+                repo.save_task(&task)
+            })
+            .unwrap();
+
+            // 不经过撤销日志的修改（同步、子任务、CLI 等）
+            let task = Task { description: Some("edited elsewhere".to_string()), ..repo.task("a").unwrap().unwrap() };
+            repo.save_task(&task).unwrap();
+
+            let err = repo.undo(1).unwrap_err();
+            assert!(err.contains("conflicts with later changes to task a"));
+            let task = repo.task("a").unwrap().unwrap();
+            assert_eq!(task.title, "second");
+            assert_eq!(task.description.as_deref(), Some("edited elsewhere"));
+            // 冲突的操作保留在日志中
+            assert_eq!(repo.undo_state().unwrap().undo_count, 1);
+        });
+    }
+
+    #[test]
+    fn redo_refuses_when_task_changed_after_undo() {
+        with_repositories(|repo| {
+            let ids = vec!["a".to_string()];
+            undoable(repo, "create", &ids, |repo| repo.save_task(&crate::testing::task("a", json!({})))).unwrap();
+            repo.undo(1).unwrap();
+            assert!(repo.task("a").unwrap().is_none());
+
+            save(repo, "a", json!({ "title": "recreated" }));
+            assert!(repo.redo(1).is_err());
+            assert_eq!(repo.task("a").unwrap().unwrap().title, "recreated");
+            assert_eq!(repo.undo_state().unwrap().redo_count, 1);
+        });
+    }
+
+    fn rename(repo: &dyn TaskRepository, id: &str, title: &str) {
+        undoable(repo, title, &[id.to_string()], |repo| {
+            let task = Task { title: title.to_string(), ..repo.task(id)?.unwrap() };
+            repo.save_task(&task)
+        })
+        .unwrap();
+    }
+
+    fn title(repo: &dyn TaskRepository, id: &str) -> String {
+        repo.task(id).unwrap().unwrap().title
+    }
+
+    #[test]
+    fn undo_and_redo_walk_the_stack_in_order() {
+        with_repositories(|repo| {
+            save(repo, "a", json!({ "title": "v0" }));
+            for title in ["v1", "v2", "v3"] {
+                rename(repo, "a", title);
+            }
+            // 保存相同内容不产生撤销记录
+            undoable(repo, "noop", &["a".to_string()], |_| Ok(())).unwrap();
+            assert_eq!(repo.undo_state().unwrap().undo_count, 3);
+
+            let undone = repo.undo(2).unwrap();
+            assert_eq!(undone.iter().map(|e| e.label.as_str()).collect::<Vec<_>>(), vec!["v3", "v2"]);
+            assert_eq!(title(repo, "a"), "v1");
+            let state = repo.undo_state().unwrap();
+            assert_eq!((state.undo_count, state.redo_count), (1, 2));
+            assert_eq!(state.next_undo.unwrap().label, "v1");
+            assert_eq!(state.next_redo.unwrap().label, "v2");
+
+            let redone = repo.redo(5).unwrap();
+            assert_eq!(redone.iter().map(|e| e.label.as_str()).collect::<Vec<_>>(), vec!["v2", "v3"]);
+            assert_eq!(title(repo, "a"), "v3");
+
+            // 撤销全部之后继续撤销没有效果
+            assert_eq!(repo.undo(10).unwrap().len(), 3);
+            assert_eq!(title(repo, "a"), "v0");
+            assert!(repo.undo(1).unwrap().is_empty());
+            assert!(repo.undo_state().unwrap().next_undo.is_none());
+        });
+    }
+
+    #[test]
+    fn new_operation_clears_redo_stack() {
+        with_repositories(|repo| {
+            save(repo, "a", json!({ "title": "v0" }));
+            rename(repo, "a", "v1");
+            rename(repo, "a", "v2");
+            repo.undo(1).unwrap();
+            assert_eq!(repo.undo_state().unwrap().redo_count, 1);
+
+            rename(repo, "a", "other");
+            let state = repo.undo_state().unwrap();
+            assert_eq!((state.undo_count, state.redo_count), (2, 0));
+            assert!(repo.redo(1).unwrap().is_empty());
+            assert_eq!(title(repo, "a"), "other");
+
+            repo.undo(2).unwrap();
+            assert_eq!(title(repo, "a"), "v0");
+        });
+    }
+
+    #[test]
+    fn keeps_only_the_latest_operations() {
+        with_repositories(|repo| {
+            save(repo, "a", json!({ "title": "v0" }));
+            for i in 1..=UNDO_LIMIT + 5 {
+                rename(repo, "a", &format!("v{}", i));
+            }
+            assert_eq!(repo.undo_state().unwrap().undo_count, UNDO_LIMIT as i64);
+
+            assert_eq!(repo.undo(UNDO_LIMIT + 5).unwrap().len(), UNDO_LIMIT);
+            // 最早的 5 个操作已被删除，只能撤销到 v5
+            assert_eq!(title(repo, "a"), "v5");
+        });
+    }
+
+    #[test]
+    fn undo_create_and_redo_recreates() {
+        with_repositories(|repo| {
+            let ids = vec!["a".to_string()];
+            undoable(repo, "create", &ids, |repo| repo.save_task(&crate::testing::task("a", json!({ "title": "new" })))).unwrap();
+
+            repo.undo(1).unwrap();
+            assert!(repo.task("a").unwrap().is_none());
+            repo.redo(1).unwrap();
+            assert_eq!(title(repo, "a"), "new");
+            // 重做同样写入变更记录，供 REST 同步推送
+            let changes = repo.changes_since(0).unwrap();
+            assert!(changes.iter().any(|c| c.entity_id == "a" && c.op == "upsert"));
+        });
+    }
+
+    #[test]
+    fn failed_operation_writes_nothing() {
+        with_repositories(|repo| {
+            save(repo, "a", json!({ "title": "old" }));
+            let ids = vec!["a".to_string(), "b".to_string()];
+            let result: Result<(), String> = undoable(repo, "edit", &ids, |repo| {
+                repo.save_task(&crate::testing::task("a", json!({ "title": "changed" })))?;
+                repo.save_task(&crate::testing::task("b", json!({ "title": "created" })))?;
+                Err("failed".to_string())
+            });
+
+            assert_eq!(result.unwrap_err(), "failed");
+            // 操作中已完成的写入随事务回滚，也没有写入撤销日志
+            assert_eq!(title(repo, "a"), "old");
+            assert!(repo.task("b").unwrap().is_none());
+            assert_eq!(repo.undo_state().unwrap().undo_count, 0);
+        });
+    }
+
+    #[test]
+    fn partial_conflict_keeps_the_whole_operation() {
+        with_repositories(|repo| {
+            save(repo, "a", json!({ "title": "a" }));
+            save(repo, "b", json!({ "title": "b" }));
+            let ids = vec!["a".to_string(), "b".to_string()];
+            undoable(repo, "batch", &ids, |repo| {
+                repo.save_task(&Task { completed: true, ..repo.task("a")?.unwrap() })?;
+                repo.save_task(&Task { completed: true, ..repo.task("b")?.unwrap() })
+            })
+            .unwrap();
+            // 只有 b 在之后被修改
+            repo.save_task(&Task { title: "b edited".to_string(), ..repo.task("b").unwrap().unwrap() }).unwrap();
+
+            let err = repo.undo(1).unwrap_err();
+            assert!(err.contains("task b"));
+            assert!(!err.contains("task a"));
+            // 整个操作都不写回，a 也保持完成状态，且操作仍可在之后撤销
+            assert!(repo.task("a").unwrap().unwrap().completed);
+            assert_eq!(title(repo, "b"), "b edited");
+            let state = repo.undo_state().unwrap();
+            assert_eq!(state.undo_count, 1);
+            assert_eq!(state.redo_count, 0);
+
+            // 恢复 b 后可以撤销整个操作
+            repo.save_task(&Task { title: "b".to_string(), ..repo.task("b").unwrap().unwrap() }).unwrap();
+            repo.undo(1).unwrap();
+            assert!(!repo.task("a").unwrap().unwrap().completed);
+            assert!(!repo.task("b").unwrap().unwrap().completed);
+        });
+    }
+}
@@ -0,0 +1,163 @@
+use std::path::PathBuf;
+use tokio::fs;
+
+use my_todo_core::backup::is_partial_upload;
+
+use super::{BackupEntry, BackupTarget, WebDavSettings};
+
+/// 本地目录备份目标（也可指向挂载的 NAS 共享目录）
+pub(crate) struct LocalTarget {
+    dir: PathBuf,
+}
+
+impl LocalTarget {
+    pub fn new(settings: &WebDavSettings) -> Result<Self, String> {
+        let path = settings
+            .local_path
+            .as_deref()
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .ok_or_else(|| "Local backup directory not configured".to_string())?;
+        Ok(Self {
+            dir: PathBuf::from(path),
+        })
+    }
+}
+
+impl BackupTarget for LocalTarget {
+    async fn test(&self) -> Result<(), String> {
+        self.prepare().await?;
+        // 写入并删除探测文件，确认目录可写
+        let probe = self.dir.join(".my-todo-probe");
+        fs::write(&probe, b"probe").await.map_err(|e| format!("Backup directory is not writable: {}", e))?;
+        fs::remove_file(&probe).await.map_err(|e| format!("Failed to remove probe file: {}", e))?;
+        Ok(())
+    }
+
+    async fn prepare(&self) -> Result<(), String> {
+        fs::create_dir_all(&self.dir).await.map_err(|e| format!("Failed to create backup directory: {}", e))
+    }
+
+    async fn upload(&self, filename: &str, data: Vec<u8>, _mime: &str) -> Result<(), String> {
+        // 先写以 . 开头的临时文件再重命名，避免中断时留下不完整的备份（list 会忽略隐藏文件）
+        let tmp = self.dir.join(format!(".{}.tmp", filename));
+        fs::write(&tmp, &data).await.map_err(|e| format!("Failed to write backup: {}", e))?;
+        fs::rename(&tmp, self.dir.join(filename)).await.map_err(|e| format!("Failed to write backup: {}", e))?;
+        Ok(())
+    }
+
+    async fn list(&self) -> Result<Vec<BackupEntry>, String> {
+        if !fs::try_exists(&self.dir).await.unwrap_or(false) {
+            return Ok(Vec::new());
+        }
+        let mut entries = Vec::new();
+        let mut dir = fs::read_dir(&self.dir).await.map_err(|e| format!("Failed to list backups: {}", e))?;
+        while let Some(entry) = dir.next_entry().await.map_err(|e| format!("Failed to list backups: {}", e))? {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            // 跳过隐藏文件（上传中的临时文件与探测文件）
+            if is_partial_upload(&name) {
+                continue;
+            }
+            let metadata = entry.metadata().await.map_err(|e| format!("Failed to read metadata: {}", e))?;
+            if !metadata.is_file() {
+                continue;
+            }
+            let modified = metadata
+                .modified()
+                .ok()
+                .map(|t| chrono::DateTime::<chrono::Utc>::from(t).to_rfc3339());
+            entries.push(BackupEntry {
+                name,
+                size: Some(metadata.len()),
+                modified,
+            });
+        }
+        Ok(entries)
+    }
+
+    async fn download(&self, filename: &str) -> Result<Vec<u8>, String> {
+        fs::read(self.dir.join(filename)).await.map_err(|e| format!("Failed to read backup: {}", e))
+    }
+
+    async fn delete(&self, filename: &str) -> Result<(), String> {
+        match fs::remove_file(self.dir.join(filename)).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(format!("Failed to delete backup: {}", e)),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::{list_backup_entries, prune_backups};
+    use my_todo_core::backup::BACKUP_PREFIX;
+
+    fn target(dir: &tempfile::TempDir) -> LocalTarget {
+        LocalTarget { dir: dir.path().join("backups") }
+    }
+
+    fn backup_name(n: u32) -> String {
+        format!("{}2026-10-{:02}.zip", BACKUP_PREFIX, n)
+    }
+
+    #[tokio::test]
+    async fn upload_list_download_delete() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = target(&dir);
+        assert!(target.list().await.unwrap().is_empty());
+
+        target.prepare().await.unwrap();
+        target.test().await.unwrap();
+        target.upload(&backup_name(1), b"backup".to_vec(), "application/zip").await.unwrap();
+
+        let entries = target.list().await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].name, backup_name(1));
+        assert_eq!(entries[0].size, Some(6));
+        assert!(entries[0].modified.is_some());
+        assert_eq!(target.download(&backup_name(1)).await.unwrap(), b"backup");
+
+        target.delete(&backup_name(1)).await.unwrap();
+        assert!(target.list().await.unwrap().is_empty());
+        // 删除不存在的文件不报错
+        target.delete(&backup_name(1)).await.unwrap();
+        assert!(target.download(&backup_name(1)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn list_ignores_temporary_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = target(&dir);
+        target.prepare().await.unwrap();
+        target.upload(&backup_name(1), b"ok".to_vec(), "application/zip").await.unwrap();
+        // 模拟中断的上传留下的临时文件
+        std::fs::write(target.dir.join(format!(".{}.tmp", backup_name(2))), b"partial").unwrap();
+        std::fs::write(target.dir.join(format!("{}.tmp", backup_name(3))), b"partial").unwrap();
+        std::fs::create_dir(target.dir.join(backup_name(4))).unwrap();
+
+        let names: Vec<String> = list_backup_entries(&target).await.unwrap().into_iter().map(|e| e.name).collect();
+        assert_eq!(names, vec![backup_name(1)]);
+    }
+
+    #[tokio::test]
+    async fn prune_keeps_newest_backups() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = target(&dir);
+        target.prepare().await.unwrap();
+        for n in 1..=5 {
+            target.upload(&backup_name(n), vec![n as u8], "application/zip").await.unwrap();
+        }
+        target.upload("notes.txt", b"keep".to_vec(), "text/plain").await.unwrap();
+
+        prune_backups(&target, 2).await.unwrap();
+        let names: Vec<String> = list_backup_entries(&target).await.unwrap().into_iter().map(|e| e.name).collect();
+        assert_eq!(names, vec![backup_name(5), backup_name(4)]);
+        // 其他文件不受影响
+        assert_eq!(target.download("notes.txt").await.unwrap(), b"keep");
+
+        // max 为 0 表示不限制
+        prune_backups(&target, 0).await.unwrap();
+        assert_eq!(list_backup_entries(&target).await.unwrap().len(), 2);
+    }
+}
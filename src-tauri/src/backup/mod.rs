@@ -0,0 +1,459 @@
+mod local;
+mod s3;
+mod webdav;
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+
+use my_todo_core::backup::{
+    self as archive, validate_backup_name, BACKUP_PREFIX, DB_ENTRY, NOTIFICATION_SETTINGS_ENTRY,
+    WEBDAV_SETTINGS_ENTRY,
+};
+pub use my_todo_core::backup::BackupEntry;
+use my_todo_core::crypto;
+use crate::database;
+use local::LocalTarget;
+use s3::S3Target;
+pub(crate) use webdav::WebDavTarget;
+
+/// 备份目标类型
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BackupTargetKind {
+    /// WebDAV 服务（坚果云 / Nextcloud 等）
+    #[default]
+    Webdav,
+    /// 本地目录或挂载的 NAS 目录
+    Local,
+    /// S3 兼容对象存储（AWS S3 / MinIO 等）
+    S3,
+}
+
+/// S3 兼容对象存储设置（对象键前缀使用 base_path）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct S3Settings {
+    /// 自定义服务地址（例如：http://localhost:9000），为空时使用 AWS 默认地址
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    /// 区域（默认 us-east-1）
+    #[serde(default)]
+    pub region: String,
+    /// 存储桶名称
+    pub bucket: String,
+    pub access_key: String,
+    pub secret_key: String,
+    /// 使用路径风格访问（MinIO 等自建服务通常需要开启）
+    #[serde(default)]
+    pub path_style: bool,
+}
+
+/// 备份设置（历史原因沿用 WebDavSettings 名称，同时包含其他备份目标的配置）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebDavSettings {
+    /// 是否启用备份
+    pub enabled: bool,
+    /// 备份目标类型
+    #[serde(default)]
+    pub target: BackupTargetKind,
+    /// 本地备份目录（target 为 local 时使用）
+    #[serde(default)]
+    pub local_path: Option<String>,
+    /// S3 设置（target 为 s3 时使用）
+    #[serde(default)]
+    pub s3: Option<S3Settings>,
+    /// WebDAV 服务器地址（例如：https://dav.jianguoyun.com/dav）
+    pub url: String,
+    /// 用户名
+    pub username: String,
+    /// 密码或访问令牌
+    pub password: String,
+    /// 存储路径（例如：/my-todo-backups）
+    pub base_path: String,
+    /// 是否自动备份
+    pub auto_backup: bool,
+    /// 最大备份数量（超过后保留最新，其余删除）
+    #[serde(default)]
+    pub max_backups: Option<u32>,
+    /// 精简备份：仅备份数据库文件，不打包其他内容
+    pub simple_mode: bool,
+    /// 加密口令：设置后备份在上传前使用该口令加密（为空则不加密）
+    #[serde(default)]
+    pub encryption_passphrase: Option<String>,
+    /// 双向同步：定期与 WebDAV 上的同步文件按记录合并（多设备使用）
+    #[serde(default)]
+    pub sync_enabled: bool,
+}
+
+impl WebDavSettings {
+    /// 获取有效的加密口令（空字符串视为未设置）
+    pub(crate) fn passphrase(&self) -> Option<&str> {
+        self.encryption_passphrase
+            .as_deref()
+            .filter(|p| !p.is_empty())
+    }
+}
+
+/// 备份目标：负责备份文件的存取，备份内容的生成与恢复由上层统一处理
+pub(crate) trait BackupTarget {
+    /// 检查目标是否可用（连接、凭据与写入权限）
+    async fn test(&self) -> Result<(), String>;
+    /// 确保备份目录存在
+    async fn prepare(&self) -> Result<(), String>;
+    /// 上传备份文件
+    async fn upload(&self, filename: &str, data: Vec<u8>, mime: &str) -> Result<(), String>;
+    /// 列出目录中的文件
+    async fn list(&self) -> Result<Vec<BackupEntry>, String>;
+    /// 下载备份文件
+    async fn download(&self, filename: &str) -> Result<Vec<u8>, String>;
+    /// 删除备份文件
+    async fn delete(&self, filename: &str) -> Result<(), String>;
+}
+
+/// 获取应用数据目录路径
+fn get_app_data_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create app data dir: {}", e))?;
+    Ok(dir)
+}
+
+/// 获取当前存储使用的 SQLite 数据库文件路径（使用内存存储时没有数据库文件，返回错误）
+fn get_sqlite_db_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    database::store(app_handle)?
+        .database_path()
+        .ok_or_else(|| "Backup and restore are unavailable while using in-memory storage".to_string())
+}
+
+/// 替换数据库文件：先写入同目录下的临时文件并落盘，再重命名覆盖，写入中断时原数据库保持完整
+fn replace_database(db_path: &Path, data: &[u8]) -> Result<(), String> {
+    let tmp_path = db_path.with_extension("db.restoring");
+    let written = fs::File::create(&tmp_path).and_then(|mut file| {
+        file.write_all(data)?;
+        file.sync_all()
+    });
+    if let Err(e) = written.and_then(|_| fs::rename(&tmp_path, db_path)) {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(format!("Failed to write db: {}", e));
+    }
+    // 目录落盘（保证重命名持久化）；不支持打开目录的平台忽略
+    if let Some(dir) = db_path.parent() {
+        if let Ok(dir) = fs::File::open(dir) {
+            let _ = dir.sync_all();
+        }
+    }
+    Ok(())
+}
+
+/// 获取通知设置文件路径（与 lib.rs 中的通知设置保持一致，位于本地数据目录）
+fn get_notification_settings_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = app_handle
+        .path()
+        .app_local_data_dir()
+        .map_err(|e| format!("Failed to get app local data dir: {}", e))?;
+    Ok(dir.join("notification_settings.json"))
+}
+
+/// 保存 WebDAV 设置到本地
+#[tauri::command]
+pub async fn save_webdav_settings(
+    app_handle: tauri::AppHandle,
+    settings: WebDavSettings,
+) -> Result<(), String> {
+    let dir = get_app_data_dir(&app_handle)?;
+    let path = dir.join("webdav_settings.json");
+    let json = serde_json::to_string_pretty(&settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    fs::write(path, json).map_err(|e| format!("Failed to write settings: {}", e))?;
+    Ok(())
+}
+
+/// 加载 WebDAV 设置
+#[tauri::command]
+pub async fn load_webdav_settings(
+    app_handle: tauri::AppHandle,
+) -> Result<Option<WebDavSettings>, String> {
+    let dir = get_app_data_dir(&app_handle)?;
+    let path = dir.join("webdav_settings.json");
+    if !path.exists() {
+        return Ok(None);
+    }
+    let data = fs::read_to_string(path).map_err(|e| format!("Failed to read settings: {}", e))?;
+    let settings: WebDavSettings =
+        serde_json::from_str(&data).map_err(|e| format!("Failed to parse settings: {}", e))?;
+    Ok(Some(settings))
+}
+
+/// 测试备份目标是否可用
+#[tauri::command]
+pub async fn test_webdav_connection(settings: WebDavSettings) -> Result<(), String> {
+    match settings.target {
+        BackupTargetKind::Webdav => WebDavTarget::new(&settings)?.test().await,
+        BackupTargetKind::Local => LocalTarget::new(&settings)?.test().await,
+        BackupTargetKind::S3 => S3Target::new(&settings)?.test().await,
+    }
+}
+
+/// 立即备份到已配置的目标（WebDAV、本地目录或 S3）
+#[tauri::command]
+pub async fn backup_to_webdav(app_handle: tauri::AppHandle) -> Result<String, String> {
+    let settings = load_enabled_settings(&app_handle).await?;
+    match settings.target {
+        BackupTargetKind::Webdav => run_backup(&WebDavTarget::new(&settings)?, &app_handle, &settings).await,
+        BackupTargetKind::Local => run_backup(&LocalTarget::new(&settings)?, &app_handle, &settings).await,
+        BackupTargetKind::S3 => run_backup(&S3Target::new(&settings)?, &app_handle, &settings).await,
+    }
+}
+
+/// 列出备份目标中的备份文件（按时间从新到旧）
+#[tauri::command]
+pub async fn list_backups(app_handle: tauri::AppHandle) -> Result<Vec<BackupEntry>, String> {
+    let settings = load_enabled_settings(&app_handle).await?;
+    match settings.target {
+        BackupTargetKind::Webdav => list_backup_entries(&WebDavTarget::new(&settings)?).await,
+        BackupTargetKind::Local => list_backup_entries(&LocalTarget::new(&settings)?).await,
+        BackupTargetKind::S3 => list_backup_entries(&S3Target::new(&settings)?).await,
+    }
+}
+
+/// 从备份目标恢复（下载指定备份并覆盖本地数据；components 仅对完整备份生效，为空时恢复全部内容）
+#[tauri::command]
+pub async fn restore_from_webdav(
+    app_handle: tauri::AppHandle,
+    filename: String,
+    components: Option<Vec<String>>,
+) -> Result<(), String> {
+    let settings = load_enabled_settings(&app_handle).await?;
+    match settings.target {
+        BackupTargetKind::Webdav => {
+            run_restore(&WebDavTarget::new(&settings)?, &app_handle, &settings, &filename, components).await
+        }
+        BackupTargetKind::Local => {
+            run_restore(&LocalTarget::new(&settings)?, &app_handle, &settings, &filename, components).await
+        }
+        BackupTargetKind::S3 => {
+            run_restore(&S3Target::new(&settings)?, &app_handle, &settings, &filename, components).await
+        }
+    }
+}
+
+/// 加载设置并确认备份已启用
+async fn load_enabled_settings(app_handle: &tauri::AppHandle) -> Result<WebDavSettings, String> {
+    let settings = load_webdav_settings(app_handle.clone())
+        .await?
+        .ok_or_else(|| "Backup settings not configured".to_string())?;
+    if !settings.enabled {
+        return Err("Backup is disabled".to_string());
+    }
+    Ok(settings)
+}
+
+/// 生成备份并上传到目标，随后按保留数量清理旧备份
+async fn run_backup<T: BackupTarget>(
+    target: &T,
+    app_handle: &tauri::AppHandle,
+    settings: &WebDavSettings,
+) -> Result<String, String> {
+    let db_path = get_sqlite_db_path(app_handle)?;
+    if !db_path.exists() {
+        return Err("Local database not found".to_string());
+    }
+
+    let ts = chrono::Local::now().format("%Y%m%d-%H%M%S").to_string();
+
+    // 构造待上传的内容与文件名
+    let (mut bytes, mut filename, mut mime) = if settings.simple_mode {
+        let buf = fs::read(&db_path).map_err(|e| format!("Failed to read db: {}", e))?;
+        (buf, format!("{}{}.db", BACKUP_PREFIX, ts), "application/octet-stream")
+    } else {
+        // 打包为 ZIP（数据库、设置与清单）
+        let zip_buf = build_full_archive(app_handle, settings, &db_path)?;
+        (zip_buf, format!("{}{}.zip", BACKUP_PREFIX, ts), "application/zip")
+    };
+
+    // 配置了口令时，上传前加密（文件名追加 .enc 后缀）
+    if let Some(passphrase) = settings.passphrase() {
+        bytes = crypto::encrypt_backup(&bytes, passphrase)?;
+        filename.push_str(".enc");
+        mime = "application/octet-stream";
+    }
+
+    target.prepare().await?;
+    target.upload(&filename, bytes, mime).await?;
+
+    if let Some(max) = settings.max_backups {
+        // 清理失败不影响本次备份结果
+        if let Err(e) = prune_backups(target, max).await {
+            eprintln!("[Backup] Failed to prune old backups: {}", e);
+        }
+    }
+
+    Ok(filename)
+}
+
+/// 从目标下载备份并恢复到本地
+async fn run_restore<T: BackupTarget>(
+    target: &T,
+    app_handle: &tauri::AppHandle,
+    settings: &WebDavSettings,
+    filename: &str,
+    components: Option<Vec<String>>,
+) -> Result<(), String> {
+    validate_backup_name(filename)?;
+    let db_path = get_sqlite_db_path(app_handle)?;
+    let mut bytes = target.download(filename).await?;
+
+    // 加密备份需先解密
+    if crypto::is_encrypted(&bytes) {
+        let passphrase = settings
+            .passphrase()
+            .ok_or_else(|| "Backup is encrypted, please set the encryption passphrase".to_string())?;
+        bytes = crypto::decrypt_backup(&bytes, passphrase)?;
+    }
+
+    // 记录恢复前的本地任务，用于检测被覆盖的本地修改
+    let before = snapshot_tasks(app_handle);
+
+    // 根据扩展名判断是否为 zip
+    if filename.trim_end_matches(".enc").ends_with(".zip") {
+        restore_full_archive(app_handle, &db_path, bytes, components.as_deref())?;
+    } else {
+        replace_database(&db_path, &bytes)?;
+    }
+
+    record_restore_conflicts(app_handle, before)
+}
+
+/// 列出本应用生成的备份，按文件名（即时间戳）倒序排列
+async fn list_backup_entries<T: BackupTarget>(target: &T) -> Result<Vec<BackupEntry>, String> {
+    Ok(archive::backup_entries(target.list().await?))
+}
+
+/// 仅保留最新的 `max` 个备份
+async fn prune_backups<T: BackupTarget>(target: &T, max: u32) -> Result<(), String> {
+    let entries = list_backup_entries(target).await?;
+    for entry in archive::expired_backups(&entries, max) {
+        eprintln!("[Backup] Removing old backup: {}", entry.name);
+        target.delete(&entry.name).await?;
+    }
+    Ok(())
+}
+
+/// 构建完整备份 ZIP：数据库、通知设置、脱敏后的 WebDAV 设置以及清单文件
+fn build_full_archive(
+    app_handle: &tauri::AppHandle,
+    settings: &WebDavSettings,
+    db_path: &Path,
+) -> Result<Vec<u8>, String> {
+    let mut entries: Vec<(&str, Vec<u8>)> = Vec::new();
+
+    let db_bytes = fs::read(db_path).map_err(|e| format!("Failed to read db: {}", e))?;
+    entries.push((DB_ENTRY, db_bytes));
+
+    let notification_path = get_notification_settings_path(app_handle)?;
+    if notification_path.exists() {
+        let data = fs::read(&notification_path)
+            .map_err(|e| format!("Failed to read notification settings: {}", e))?;
+        entries.push((NOTIFICATION_SETTINGS_ENTRY, data));
+    }
+
+    // 凭据与加密口令不写入备份
+    let mut sanitized = settings.clone();
+    sanitized.password = String::new();
+    sanitized.encryption_passphrase = None;
+    if let Some(s3) = sanitized.s3.as_mut() {
+        s3.secret_key = String::new();
+    }
+    let webdav_json = serde_json::to_vec_pretty(&sanitized)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    entries.push((WEBDAV_SETTINGS_ENTRY, webdav_json));
+
+    let (task_count, list_count) = count_rows(app_handle)?;
+    archive::build_archive(entries, env!("CARGO_PKG_VERSION"), task_count, list_count)
+}
+
+/// 校验并恢复完整备份 ZIP 中选定的内容
+fn restore_full_archive(
+    app_handle: &tauri::AppHandle,
+    db_path: &Path,
+    bytes: Vec<u8>,
+    components: Option<&[String]>,
+) -> Result<(), String> {
+    // 先整体校验再写入，避免部分恢复
+    let files = archive::read_archive(bytes, components)?;
+
+    for (entry, data) in files {
+        match entry {
+            DB_ENTRY => replace_database(db_path, &data)?,
+            NOTIFICATION_SETTINGS_ENTRY => {
+                let path = get_notification_settings_path(app_handle)?;
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
+                }
+                fs::write(&path, &data).map_err(|e| format!("Failed to write notification settings: {}", e))?;
+            }
+            WEBDAV_SETTINGS_ENTRY => {
+                // 备份中的设置不含凭据，保留本地的密码与加密口令
+                let mut restored: WebDavSettings = serde_json::from_slice(&data)
+                    .map_err(|e| format!("Failed to parse webdav settings: {}", e))?;
+                let path = get_app_data_dir(app_handle)?.join("webdav_settings.json");
+                if let Ok(current) = fs::read_to_string(&path) {
+                    if let Ok(current) = serde_json::from_str::<WebDavSettings>(&current) {
+                        restored.password = current.password;
+                        restored.encryption_passphrase = current.encryption_passphrase;
+                        if let (Some(restored_s3), Some(current_s3)) = (restored.s3.as_mut(), current.s3) {
+                            restored_s3.secret_key = current_s3.secret_key;
+                        }
+                    }
+                }
+                let json = serde_json::to_string_pretty(&restored)
+                    .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+                fs::write(path, json).map_err(|e| format!("Failed to write settings: {}", e))?;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// 读取当前本地任务（数据库不可用时视为空）
+fn snapshot_tasks(app_handle: &tauri::AppHandle) -> Vec<database::Task> {
+    database::store(app_handle)
+        .and_then(|store| store.tasks())
+        .unwrap_or_default()
+}
+
+/// 恢复后补齐旧备份缺少的表，并为比备份更新的本地任务记录冲突（保留被覆盖的本地版本）
+fn record_restore_conflicts(app_handle: &tauri::AppHandle, before: Vec<database::Task>) -> Result<(), String> {
+    let store = database::store(app_handle)?;
+    store.init()?;
+
+    let restored: std::collections::HashMap<String, database::Task> = store
+        .tasks()?
+        .into_iter()
+        .map(|t| (t.id.clone(), t))
+        .collect();
+    let conflicts: Vec<database::TaskConflict> = before
+        .iter()
+        .filter_map(|local| {
+            let remote = restored.get(&local.id)?;
+            let newer = chrono::DateTime::parse_from_rfc3339(&local.updated_at).ok()?
+                > chrono::DateTime::parse_from_rfc3339(&remote.updated_at).ok()?;
+            (newer && !crate::conflicts::diff_tasks(local, remote).is_empty())
+                .then(|| crate::conflicts::new_conflict("restore", local, remote, "remote"))
+        })
+        .collect();
+    crate::conflicts::record_conflicts(app_handle, &conflicts)
+}
+
+/// 统计任务与清单数量（写入清单文件）
+fn count_rows(app_handle: &tauri::AppHandle) -> Result<(i64, i64), String> {
+    database::store(app_handle)?.count_rows()
+}
@@ -0,0 +1,337 @@
+use my_todo_core::backup::xml_elements;
+
+use super::{BackupEntry, BackupTarget, WebDavSettings};
+
+/// WebDAV 备份目标
+pub(crate) struct WebDavTarget {
+    client: reqwest::Client,
+    /// 服务器地址（不含存储路径）
+    root_url: String,
+    /// 存储路径的各级目录
+    segments: Vec<String>,
+    base_url: String,
+    username: String,
+    password: String,
+}
+
+impl WebDavTarget {
+    pub fn new(settings: &WebDavSettings) -> Result<Self, String> {
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(60))
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+        Ok(Self {
+            client,
+            root_url: normalize_base(&settings.url, ""),
+            segments: settings
+                .base_path
+                .split('/')
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+                .collect(),
+            base_url: normalize_base(&settings.url, &settings.base_path),
+            username: settings.username.clone(),
+            password: settings.password.clone(),
+        })
+    }
+
+    fn remote(&self, filename: &str) -> String {
+        format!("{}{}", self.base_url, filename)
+    }
+
+    /// 下载文件并返回其 ETag，文件不存在时返回 None
+    pub async fn download_versioned(&self, filename: &str) -> Result<Option<(Vec<u8>, Option<String>)>, String> {
+        let resp = self
+            .client
+            .get(self.remote(filename))
+            .basic_auth(&self.username, Some(&self.password))
+            .send()
+            .await
+            .map_err(|e| format!("Failed to download {}: {}", filename, e))?;
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !resp.status().is_success() {
+            return Err(format!("Download failed: {}", resp.status()));
+        }
+        let etag = resp
+            .headers()
+            .get(reqwest::header::ETAG)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let bytes = resp
+            .bytes()
+            .await
+            .map_err(|e| format!("Failed to read response: {}", e))?;
+        Ok(Some((bytes.to_vec(), etag)))
+    }
+
+    /// 条件上传：仅当远端文件未被他人修改时写入（`etag` 为 None 表示文件应尚不存在）
+    ///
+    /// 返回 false 表示远端已变化（412），调用方应重新下载合并后再试。
+    pub async fn upload_if_unchanged(
+        &self,
+        filename: &str,
+        data: Vec<u8>,
+        etag: Option<&str>,
+    ) -> Result<bool, String> {
+        let mut request = self
+            .client
+            .put(self.remote(filename))
+            .basic_auth(&self.username, Some(&self.password))
+            .header(reqwest::header::CONTENT_TYPE, "application/octet-stream");
+        request = match etag {
+            Some(etag) => request.header(reqwest::header::IF_MATCH, etag),
+            None => request.header(reqwest::header::IF_NONE_MATCH, "*"),
+        };
+        let resp = request
+            .body(data)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to upload {}: {}", filename, e))?;
+
+        if resp.status() == reqwest::StatusCode::PRECONDITION_FAILED {
+            return Ok(false);
+        }
+        if !resp.status().is_success() {
+            return Err(format!("Upload failed: {}", resp.status()));
+        }
+        Ok(true)
+    }
+}
+
+impl BackupTarget for WebDavTarget {
+    async fn test(&self) -> Result<(), String> {
+        // 1. 校验服务器地址与凭据
+        match propfind_status(&self.client, &self.root_url, &self.username, &self.password).await? {
+            DavStatus::Exists => {}
+            DavStatus::Missing => return Err("WebDAV server path not found, please check the URL".to_string()),
+            DavStatus::Unauthorized => {
+                return Err("Authentication failed, please check username and password".to_string())
+            }
+            DavStatus::Other(status) => return Err(format!("WebDAV status: {}", status)),
+        }
+
+        // 2. 确保存储目录存在
+        self.prepare().await?;
+
+        // 3. 写入并删除探测文件，确认有写入权限
+        let probe = ".my-todo-probe";
+        self.upload(probe, b"probe".to_vec(), "text/plain")
+            .await
+            .map_err(|e| format!("No write permission: {}", e))?;
+        self.delete(probe)
+            .await
+            .map_err(|e| format!("No delete permission: {}", e))
+    }
+
+    async fn prepare(&self) -> Result<(), String> {
+        // 确保远端目录存在（逐级 MKCOL 创建缺失的目录）
+        ensure_remote_dir(
+            &self.client,
+            &self.root_url,
+            &self.segments,
+            &self.username,
+            &self.password,
+        )
+        .await
+    }
+
+    async fn upload(&self, filename: &str, data: Vec<u8>, mime: &str) -> Result<(), String> {
+        let resp = self
+            .client
+            .put(self.remote(filename))
+            .basic_auth(&self.username, Some(&self.password))
+            .header(reqwest::header::CONTENT_TYPE, mime)
+            .body(data)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to upload backup: {}", e))?;
+
+        if !resp.status().is_success() {
+            return Err(format!("Upload failed: {}", resp.status()));
+        }
+        Ok(())
+    }
+
+    async fn list(&self) -> Result<Vec<BackupEntry>, String> {
+        let body = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:"><d:prop><d:getcontentlength/><d:getlastmodified/></d:prop></d:propfind>"#;
+
+        let resp = self
+            .client
+            .request(reqwest::Method::from_bytes(b"PROPFIND").unwrap(), &self.base_url)
+            .basic_auth(&self.username, Some(&self.password))
+            .header("Depth", "1")
+            .header(reqwest::header::CONTENT_TYPE, "application/xml")
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to list backups: {}", e))?;
+
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(Vec::new());
+        }
+        if !resp.status().is_success() {
+            return Err(format!("List failed: {}", resp.status()));
+        }
+        let xml = resp
+            .text()
+            .await
+            .map_err(|e| format!("Failed to read response: {}", e))?;
+
+        Ok(parse_propfind_entries(&xml))
+    }
+
+    async fn download(&self, filename: &str) -> Result<Vec<u8>, String> {
+        let resp = self
+            .client
+            .get(self.remote(filename))
+            .basic_auth(&self.username, Some(&self.password))
+            .send()
+            .await
+            .map_err(|e| format!("Failed to download backup: {}", e))?;
+        if !resp.status().is_success() {
+            return Err(format!("Download failed: {}", resp.status()));
+        }
+        let bytes = resp
+            .bytes()
+            .await
+            .map_err(|e| format!("Failed to read response: {}", e))?;
+        Ok(bytes.to_vec())
+    }
+
+    async fn delete(&self, filename: &str) -> Result<(), String> {
+        let resp = self
+            .client
+            .delete(self.remote(filename))
+            .basic_auth(&self.username, Some(&self.password))
+            .send()
+            .await
+            .map_err(|e| format!("Failed to delete backup: {}", e))?;
+
+        if resp.status().is_success() || resp.status() == reqwest::StatusCode::NOT_FOUND {
+            Ok(())
+        } else {
+            Err(format!("Delete failed: {}", resp.status()))
+        }
+    }
+}
+
+/// 解析 PROPFIND 多状态响应中的文件（跳过目录）
+fn parse_propfind_entries(xml: &str) -> Vec<BackupEntry> {
+    xml_elements(xml, "response")
+        .into_iter()
+        .filter_map(|response| {
+            let href = xml_elements(response, "href").into_iter().next()?.trim();
+            if href.ends_with('/') {
+                return None;
+            }
+            let name = href.rsplit('/').next()?.to_string();
+            if name.is_empty() {
+                return None;
+            }
+            let size = xml_elements(response, "getcontentlength")
+                .into_iter()
+                .next()
+                .and_then(|v| v.trim().parse().ok());
+            let modified = xml_elements(response, "getlastmodified")
+                .into_iter()
+                .next()
+                .and_then(|v| chrono::DateTime::parse_from_rfc2822(v.trim()).ok())
+                .map(|d| d.to_rfc3339());
+            Some(BackupEntry { name, size, modified })
+        })
+        .collect()
+}
+
+/// 规范化基础路径，确保以斜杠结尾
+fn normalize_base(base: &str, path: &str) -> String {
+    let mut url = String::new();
+    url.push_str(base.trim_end_matches('/'));
+    url.push('/');
+    url.push_str(path.trim_start_matches('/'));
+    if !url.ends_with('/') {
+        url.push('/');
+    }
+    url
+}
+
+/// PROPFIND 探测结果
+enum DavStatus {
+    Exists,
+    Missing,
+    Unauthorized,
+    Other(reqwest::StatusCode),
+}
+
+/// 使用 PROPFIND Depth:0 探测资源是否存在（部分服务器不允许对目录 GET）
+async fn propfind_status(
+    client: &reqwest::Client,
+    url: &str,
+    username: &str,
+    password: &str,
+) -> Result<DavStatus, String> {
+    let resp = client
+        .request(reqwest::Method::from_bytes(b"PROPFIND").unwrap(), url)
+        .basic_auth(username, Some(password))
+        .header("Depth", "0")
+        .send()
+        .await
+        .map_err(|e| format!("Failed to check remote dir: {}", e))?;
+
+    let status = resp.status();
+    Ok(match status.as_u16() {
+        // 207 Multi-Status 为标准响应，部分服务器返回 200
+        200 | 207 => DavStatus::Exists,
+        // 目录地址缺少或多余斜杠时服务器会重定向到规范地址，说明资源存在
+        301 | 302 | 307 | 308 => DavStatus::Exists,
+        404 => DavStatus::Missing,
+        401 | 403 => DavStatus::Unauthorized,
+        _ => DavStatus::Other(status),
+    })
+}
+
+/// 确保远端路径可用：从上到下逐级探测，缺失的目录依次 MKCOL 创建
+async fn ensure_remote_dir(
+    client: &reqwest::Client,
+    root_url: &str,
+    segments: &[String],
+    username: &str,
+    password: &str,
+) -> Result<(), String> {
+    let mut url = root_url.to_string();
+    for segment in segments {
+        url.push_str(segment);
+        url.push('/');
+
+        match propfind_status(client, &url, username, password).await? {
+            DavStatus::Exists => continue,
+            DavStatus::Missing => {}
+            DavStatus::Unauthorized => {
+                return Err("Authentication failed, please check username and password".to_string())
+            }
+            DavStatus::Other(status) => {
+                eprintln!("[WebDAV] Unexpected PROPFIND status {} for {}, trying MKCOL", status, url);
+            }
+        }
+
+        eprintln!("[WebDAV] Creating remote dir: {}", url);
+        let resp = client
+            .request(reqwest::Method::from_bytes(b"MKCOL").unwrap(), &url)
+            .basic_auth(username, Some(password))
+            .send()
+            .await
+            .map_err(|e| format!("Failed to create remote dir: {}", e))?;
+
+        // 某些服务器对已存在目录返回 405
+        if resp.status().is_success() || resp.status() == reqwest::StatusCode::METHOD_NOT_ALLOWED {
+            continue;
+        }
+
+        eprintln!("[WebDAV] MKCOL failed: {}", resp.status());
+        return Err(format!("Create dir {} failed: {}", url, resp.status()));
+    }
+    Ok(())
+}
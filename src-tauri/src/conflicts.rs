@@ -0,0 +1,154 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::Emitter;
+
+pub(crate) use my_todo_core::merge::{new_conflict, TASK_FIELDS};
+use crate::database::{self, Task, TaskConflict};
+
+// 冲突的单个字段差异
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldDiff {
+    pub field: String,
+    pub local: serde_json::Value,
+    pub remote: serde_json::Value,
+}
+
+// 冲突解决方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Resolution {
+    KeepLocal,
+    KeepRemote,
+    // 按字段选择（未指定的字段保留本地）
+    Merge,
+}
+
+// 已删除一方的版本：删除没有任务数据，以另一方的任务标记为已删除表示（保留删除前的内容）
+pub(crate) fn tombstone(task: &Task, deleted_at: &str) -> Task {
+    Task {
+        deleted: true,
+        deleted_at: Some(deleted_at.to_string()),
+        updated_at: deleted_at.to_string(),
+        ..task.clone()
+    }
+}
+
+// 保存冲突记录，并通知前端
+pub(crate) fn record_conflicts(app_handle: &tauri::AppHandle, conflicts: &[TaskConflict]) -> Result<(), String> {
+    if conflicts.is_empty() {
+        return Ok(());
+    }
+
+    database::store(app_handle)?.save_task_conflicts(conflicts)?;
+    eprintln!("[Conflicts] Recorded {} conflicts", conflicts.len());
+    let _ = app_handle.emit("conflicts-changed", conflicts.len());
+    Ok(())
+}
+
+// 比较两个版本，返回值不同的字段
+pub(crate) fn diff_tasks(local: &Task, remote: &Task) -> Vec<FieldDiff> {
+    let local_map = to_map(local);
+    let remote_map = to_map(remote);
+    TASK_FIELDS
+        .iter()
+        .filter_map(|field| {
+            let l = local_map.get(*field).cloned().unwrap_or(serde_json::Value::Null);
+            let r = remote_map.get(*field).cloned().unwrap_or(serde_json::Value::Null);
+            (l != r).then(|| FieldDiff {
+                field: field.to_string(),
+                local: l,
+                remote: r,
+            })
+        })
+        .collect()
+}
+
+// 获取冲突列表
+#[tauri::command]
+pub async fn list_task_conflicts(
+    app_handle: tauri::AppHandle,
+    include_resolved: Option<bool>,
+) -> Result<Vec<TaskConflict>, String> {
+    database::store(&app_handle)?.task_conflicts(include_resolved.unwrap_or(false))
+}
+
+// 获取冲突的逐字段差异
+#[tauri::command]
+pub async fn get_task_conflict_diff(app_handle: tauri::AppHandle, id: String) -> Result<Vec<FieldDiff>, String> {
+    let conflict = load_conflict(&app_handle, &id)?;
+    Ok(diff_tasks(&conflict.local, &conflict.remote))
+}
+
+// 解决冲突：保留本地、保留远端或按字段合并，结果作为新的本地修改参与下次同步
+#[tauri::command]
+pub async fn resolve_task_conflict(
+    app_handle: tauri::AppHandle,
+    id: String,
+    resolution: Resolution,
+    fields: Option<HashMap<String, String>>,
+) -> Result<Task, String> {
+    let conflict = load_conflict(&app_handle, &id)?;
+    if conflict.resolved_at.is_some() {
+        return Err("Conflict already resolved".to_string());
+    }
+
+    let mut task = match resolution {
+        Resolution::KeepLocal => conflict.local.clone(),
+        Resolution::KeepRemote => conflict.remote.clone(),
+        Resolution::Merge => {
+            let fields = fields.unwrap_or_default();
+            let remote_map = to_map(&conflict.remote);
+            let mut merged = to_map(&conflict.local);
+            for (field, side) in &fields {
+                if !TASK_FIELDS.contains(&field.as_str()) {
+                    return Err(format!("Unknown task field: {}", field));
+                }
+                match side.as_str() {
+                    "local" => {}
+                    "remote" => {
+                        match remote_map.get(field) {
+                            Some(value) => merged.insert(field.clone(), value.clone()),
+                            None => merged.remove(field),
+                        };
+                    }
+                    _ => return Err(format!("Invalid side for {}: {}", field, side)),
+                }
+            }
+            serde_json::from_value(serde_json::Value::Object(merged))
+                .map_err(|e| format!("Failed to merge task: {}", e))?
+        }
+    };
+    task.id = conflict.task_id.clone();
+    task.updated_at = chrono::Utc::now().to_rfc3339();
+
+    let resolution = match resolution {
+        Resolution::KeepLocal => "keepLocal",
+        Resolution::KeepRemote => "keepRemote",
+        Resolution::Merge => "merge",
+    };
+
+    save_resolution(&app_handle, &id, &task, resolution)?;
+
+    let _ = app_handle.emit("tasks-changed", ());
+    let _ = app_handle.emit("conflicts-changed", ());
+    Ok(task)
+}
+
+fn save_resolution(app_handle: &tauri::AppHandle, id: &str, task: &Task, resolution: &str) -> Result<(), String> {
+    let store = database::store(app_handle)?;
+    store.save_task(task)?;
+    store.resolve_task_conflict(id, resolution)
+}
+
+fn load_conflict(app_handle: &tauri::AppHandle, id: &str) -> Result<TaskConflict, String> {
+    database::store(app_handle)?.task_conflict(id)?
+        .ok_or_else(|| format!("Conflict not found: {}", id))
+}
+
+fn to_map(task: &Task) -> serde_json::Map<String, serde_json::Value> {
+    match serde_json::to_value(task) {
+        Ok(serde_json::Value::Object(map)) => map,
+        _ => serde_json::Map::new(),
+    }
+}
@@ -3,8 +3,18 @@
 
 mod database;
 mod backup;
-
-use database::{Task, TaskList};
+mod conflicts;
+mod local_api;
+mod pomodoro;
+mod rest_sync;
+mod sync;
+
+use database::{
+    EstimateReport, SearchResult, Statistics, StatisticsQuery, SubTask, Task, TaskBatch, TaskDependency, TaskEvent,
+    TaskList, TaskNode, TaskPage, TaskProgress, TaskQuery, TimeEntry, TimeGroup, TimeTotal, UndoEntry, UndoState,
+};
+use my_todo_core::undo::{affected_ids, batch_ids, undoable};
+use my_todo_core::ReminderTask;
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 use tauri::Manager;
@@ -16,71 +26,326 @@ pub struct NotificationSettings {
     pub wechat_webhook: Option<String>,
 }
 
-// 提醒任务结构
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ReminderTask {
-    pub id: String,
-    pub task_id: String,
-    pub task_title: String,
-    pub reminder_time: i64,
-    pub repeat: String,
-    pub sent: bool,
-}
-
 // 获取所有任务
 #[tauri::command]
 async fn get_tasks(app_handle: tauri::AppHandle) -> Result<Vec<Task>, String> {
-    #[cfg(feature = "sqlite")]
-    {
-        return database::get_sqlite_tasks(&app_handle);
-    }
+    database::store(&app_handle)?.tasks()
+}
 
-    #[cfg(not(feature = "sqlite"))]
-    {
-        Err("No database available".to_string())
-    }
+// 按条件查询任务（筛选、排序与分页在数据库中完成）
+#[tauri::command]
+async fn query_tasks(app_handle: tauri::AppHandle, query: TaskQuery) -> Result<TaskPage, String> {
+    database::store(&app_handle)?.query_tasks(&query)
 }
 
-// 保存任务
+// 全文搜索任务（标题、描述与子任务标题），返回按相关度排序的结果与高亮摘要
 #[tauri::command]
-async fn save_task(app_handle: tauri::AppHandle, task: Task) -> Result<(), String> {
-    #[cfg(feature = "sqlite")]
-    {
-        return database::save_sqlite_task(&app_handle, &task);
-    }
+async fn search_tasks(app_handle: tauri::AppHandle, query: String, limit: Option<u32>) -> Result<Vec<SearchResult>, String> {
+    database::store(&app_handle)?.search_tasks(&query, limit.unwrap_or(50) as usize)
+}
 
-    #[cfg(not(feature = "sqlite"))]
-    {
-        Err("No database available".to_string())
-    }
+// 保存任务（可撤销）
+#[tauri::command]
+async fn save_task(app_handle: tauri::AppHandle, task: Task) -> Result<(), String> {
+    let store = database::store(&app_handle)?;
+    undoable(&**store, "save", &[task.id.clone()], |repo| repo.save_task(&task))
 }
 
-// 删除任务
+// 删除任务（可撤销）
 #[tauri::command]
 async fn delete_task(app_handle: tauri::AppHandle, id: String) -> Result<(), String> {
-    #[cfg(feature = "sqlite")]
-    {
-        return database::delete_sqlite_task(&app_handle, &id);
-    }
+    // 子任务树一并删除
+    let store = database::store(&app_handle)?;
+    let ids = affected_ids(&**store, &id, false)?;
+    undoable(&**store, "delete", &ids, |repo| repo.delete_task_tree(&id))?;
+    Ok(())
+}
+
+// 按给定顺序重排任务（拖拽排序，可撤销）
+#[tauri::command]
+async fn reorder_tasks(app_handle: tauri::AppHandle, ids: Vec<String>) -> Result<Vec<Task>, String> {
+    let store = database::store(&app_handle)?;
+    undoable(&**store, "reorder", &ids, |repo| repo.reorder_tasks(&ids))
+}
+
+// 批量修改任务（更新或创建、重排与删除在单个事务内完成，可撤销），完成后推送一次 tasks-changed
+#[tauri::command]
+async fn batch_update_tasks(app_handle: tauri::AppHandle, batch: TaskBatch) -> Result<Vec<Task>, String> {
+    use tauri::Emitter;
+
+    let store = database::store(&app_handle)?;
+    let ids = batch_ids(&**store, &batch)?;
+    let tasks = undoable(&**store, "batch", &ids, |repo| repo.batch_update_tasks(&batch))?;
+    let _ = app_handle.emit("tasks-changed", ());
+    Ok(tasks)
+}
+
+// 撤销最近的 steps 个操作（默认 1 个）
+#[tauri::command]
+async fn undo(app_handle: tauri::AppHandle, steps: Option<usize>) -> Result<Vec<UndoEntry>, String> {
+    let entries = database::store(&app_handle)?.undo(steps.unwrap_or(1))?;
+    notify_undo(&app_handle, "Undid", &entries);
+    Ok(entries)
+}
+
+// 重做最近撤销的 steps 个操作（默认 1 个）
+#[tauri::command]
+async fn redo(app_handle: tauri::AppHandle, steps: Option<usize>) -> Result<Vec<UndoEntry>, String> {
+    let entries = database::store(&app_handle)?.redo(steps.unwrap_or(1))?;
+    notify_undo(&app_handle, "Redid", &entries);
+    Ok(entries)
+}
+
+// 获取撤销 / 重做栈状态（用于菜单与按钮是否可用）
+#[tauri::command]
+async fn get_undo_state(app_handle: tauri::AppHandle) -> Result<UndoState, String> {
+    database::store(&app_handle)?.undo_state()
+}
+
+// 撤销或重做后通知前端重新加载任务
+fn notify_undo(app_handle: &tauri::AppHandle, action: &str, entries: &[UndoEntry]) {
+    use tauri::Emitter;
 
-    #[cfg(not(feature = "sqlite"))]
-    {
-        Err("No database available".to_string())
+    if entries.is_empty() {
+        return;
     }
+    let labels: Vec<&str> = entries.iter().map(|e| e.label.as_str()).collect();
+    eprintln!("[Undo] {} {} operation(s): {}", action, entries.len(), labels.join(", "));
+    let _ = app_handle.emit("tasks-changed", ());
+}
+
+// 获取任务历史（按时间顺序，用于详情对话框的时间线）
+#[tauri::command]
+async fn get_task_events(app_handle: tauri::AppHandle, task_id: String) -> Result<Vec<TaskEvent>, String> {
+    database::store(&app_handle)?.task_events(&task_id)
+}
+
+// 获取任务树（root_id 为空时返回所有未删除任务组成的森林）
+#[tauri::command]
+async fn get_task_tree(app_handle: tauri::AppHandle, root_id: Option<String>) -> Result<Vec<TaskNode>, String> {
+    database::store(&app_handle)?.task_tree(root_id.as_deref())
+}
+
+// 移动任务及其子树（移到其他任务下或作为顶层任务移到指定清单，可撤销）
+#[tauri::command]
+async fn move_task(
+    app_handle: tauri::AppHandle,
+    id: String,
+    parent_id: Option<String>,
+    list_id: Option<String>,
+) -> Result<Vec<Task>, String> {
+    let store = database::store(&app_handle)?;
+    let ids = affected_ids(&**store, &id, false)?;
+    undoable(&**store, "move", &ids, |repo| repo.move_task(&id, parent_id.as_deref(), list_id.as_deref()))
+}
+
+// 设置完成状态（完成时连同后代，取消时连同祖先，可撤销）
+#[tauri::command]
+async fn set_task_completed(app_handle: tauri::AppHandle, id: String, completed: bool) -> Result<Vec<Task>, String> {
+    let store = database::store(&app_handle)?;
+    let ids = affected_ids(&**store, &id, !completed)?;
+    undoable(&**store, "complete", &ids, |repo| repo.set_task_completed(&id, completed))
+}
+
+// 移到回收站（连同后代，可撤销）
+#[tauri::command]
+async fn trash_task(app_handle: tauri::AppHandle, id: String) -> Result<Vec<Task>, String> {
+    let store = database::store(&app_handle)?;
+    let ids = affected_ids(&**store, &id, false)?;
+    undoable(&**store, "trash", &ids, |repo| repo.trash_task(&id))
+}
+
+// 从回收站恢复（连同后代与被删除的祖先，可撤销）
+#[tauri::command]
+async fn restore_task(app_handle: tauri::AppHandle, id: String) -> Result<Vec<Task>, String> {
+    let store = database::store(&app_handle)?;
+    let ids = affected_ids(&**store, &id, true)?;
+    undoable(&**store, "restore", &ids, |repo| repo.restore_task(&id))
+}
+
+// 获取依赖关系（指定 task_id 时只返回与该任务相关的）
+#[tauri::command]
+async fn get_task_dependencies(
+    app_handle: tauri::AppHandle,
+    task_id: Option<String>,
+) -> Result<Vec<TaskDependency>, String> {
+    database::store(&app_handle)?.task_dependencies(task_id.as_deref())
+}
+
+// 添加依赖：task_id 被 blocked_by 阻塞（会形成环时返回错误）
+#[tauri::command]
+async fn add_task_dependency(
+    app_handle: tauri::AppHandle,
+    task_id: String,
+    blocked_by: String,
+) -> Result<TaskDependency, String> {
+    database::store(&app_handle)?.add_task_dependency(&task_id, &blocked_by)
+}
+
+// 移除依赖
+#[tauri::command]
+async fn remove_task_dependency(app_handle: tauri::AppHandle, task_id: String, blocked_by: String) -> Result<bool, String> {
+    database::store(&app_handle)?.remove_task_dependency(&task_id, &blocked_by)
+}
+
+// 获取任务的子任务
+#[tauri::command]
+async fn get_subtasks(app_handle: tauri::AppHandle, task_id: String) -> Result<Vec<SubTask>, String> {
+    database::store(&app_handle)?.subtasks(&task_id)
+}
+
+// 添加子任务
+#[tauri::command]
+async fn add_subtask(
+    app_handle: tauri::AppHandle,
+    task_id: String,
+    title: String,
+    due_date: Option<String>,
+) -> Result<SubTask, String> {
+    database::store(&app_handle)?.add_subtask(&task_id, &title, due_date)
+}
+
+// 切换子任务完成状态
+#[tauri::command]
+async fn toggle_subtask(app_handle: tauri::AppHandle, id: String) -> Result<SubTask, String> {
+    database::store(&app_handle)?.toggle_subtask(&id)
+}
+
+// 按给定顺序重排子任务（可撤销）
+#[tauri::command]
+async fn reorder_subtasks(app_handle: tauri::AppHandle, task_id: String, ids: Vec<String>) -> Result<Vec<SubTask>, String> {
+    let store = database::store(&app_handle)?;
+    undoable(&**store, "reorder", &[task_id.clone()], |repo| repo.reorder_subtasks(&task_id, &ids))
+}
+
+// 将子任务提升为独立任务
+#[tauri::command]
+async fn promote_subtask(app_handle: tauri::AppHandle, id: String) -> Result<Task, String> {
+    database::store(&app_handle)?.promote_subtask(&id)
+}
+
+// 获取各任务的子任务完成进度
+#[tauri::command]
+async fn get_task_progress(app_handle: tauri::AppHandle) -> Result<Vec<TaskProgress>, String> {
+    database::store(&app_handle)?.subtask_progress()
+}
+
+// 获取计时记录（task_id 为空时返回全部）
+#[tauri::command]
+async fn get_time_entries(app_handle: tauri::AppHandle, task_id: Option<String>) -> Result<Vec<TimeEntry>, String> {
+    database::store(&app_handle)?.time_entries(task_id.as_deref())
+}
+
+// 获取正在计时的记录
+#[tauri::command]
+async fn get_running_timer(app_handle: tauri::AppHandle) -> Result<Option<TimeEntry>, String> {
+    database::store(&app_handle)?.running_timer()
+}
+
+// 开始计时（会先停止正在进行的其他计时）
+#[tauri::command]
+async fn start_timer(app_handle: tauri::AppHandle, task_id: String) -> Result<TimeEntry, String> {
+    let entry = database::store(&app_handle)?.start_timer(&task_id, chrono::Utc::now().timestamp())?;
+    eprintln!("[Timer] Started timer for task {}", task_id);
+    refresh_tray_timer(&app_handle);
+    Ok(entry)
+}
+
+// 停止计时
+#[tauri::command]
+async fn stop_timer(app_handle: tauri::AppHandle) -> Result<Option<TimeEntry>, String> {
+    let entry = database::store(&app_handle)?.stop_timer(chrono::Utc::now().timestamp())?;
+    refresh_tray_timer(&app_handle);
+    Ok(entry)
+}
+
+// 手动补录计时（时间为 Unix 时间戳，单位秒）
+#[tauri::command]
+async fn add_manual_entry(
+    app_handle: tauri::AppHandle,
+    task_id: String,
+    started_at: i64,
+    ended_at: i64,
+    note: Option<String>,
+) -> Result<TimeEntry, String> {
+    database::store(&app_handle)?.add_manual_entry(&task_id, started_at, ended_at, note)
+}
+
+// 按任务或清单汇总计时
+#[tauri::command]
+async fn get_time_totals(app_handle: tauri::AppHandle, group: Option<TimeGroup>) -> Result<Vec<TimeTotal>, String> {
+    database::store(&app_handle)?.time_totals(group.unwrap_or_default(), chrono::Utc::now().timestamp())
+}
+
+// 预估与实际用时对比报表（按清单、标签与周统计已完成任务）
+#[tauri::command]
+async fn get_estimate_report(app_handle: tauri::AppHandle) -> Result<EstimateReport, String> {
+    database::store(&app_handle)?.estimate_report(chrono::Utc::now().timestamp())
+}
+
+// 效率统计（默认最近 30 天）
+#[tauri::command]
+async fn get_statistics(app_handle: tauri::AppHandle, query: Option<StatisticsQuery>) -> Result<Statistics, String> {
+    database::store(&app_handle)?.statistics(&query.unwrap_or_default(), chrono::Utc::now().timestamp())
+}
+
+// 托盘菜单中的计时状态项
+#[cfg(target_os = "windows")]
+struct TrayTimerMenu {
+    status: tauri::menu::MenuItem<tauri::Wry>,
+    stop: tauri::menu::MenuItem<tauri::Wry>,
+}
+
+// 刷新托盘菜单中的计时状态（显示正在计时的任务与已用时长）
+#[cfg(target_os = "windows")]
+fn refresh_tray_timer(app_handle: &tauri::AppHandle) {
+    let Some(menu) = app_handle.try_state::<TrayTimerMenu>() else {
+        return;
+    };
+    let running = database::store(app_handle).and_then(|store| {
+        Ok(match store.running_timer()? {
+            Some(entry) => {
+                let title = store.task(&entry.task_id)?.map(|t| t.title).unwrap_or_default();
+                Some((title, entry.duration(chrono::Utc::now().timestamp())))
+            }
+            None => None,
+        })
+    });
+
+    let text = match &running {
+        Ok(Some((title, seconds))) => {
+            format!("计时中：{} {}", title, my_todo_core::time_tracking::format_duration(*seconds))
+        }
+        Ok(None) => "未在计时".to_string(),
+        Err(e) => {
+            eprintln!("[Tray] Failed to load running timer: {}", e);
+            "未在计时".to_string()
+        }
+    };
+    let _ = menu.status.set_text(text);
+    let _ = menu.stop.set_enabled(matches!(running, Ok(Some(_))));
+}
+
+// 刷新托盘计时（没有托盘的平台占位）
+#[cfg(not(target_os = "windows"))]
+fn refresh_tray_timer(_app_handle: &tauri::AppHandle) {}
+
+// 每秒刷新托盘菜单中的计时
+#[cfg(target_os = "windows")]
+fn start_tray_timer_updater(app_handle: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(1));
+        loop {
+            interval.tick().await;
+            refresh_tray_timer(&app_handle);
+        }
+    });
 }
 
 // 获取所有清单
 #[tauri::command]
 async fn get_lists(app_handle: tauri::AppHandle) -> Result<Vec<TaskList>, String> {
-    #[cfg(feature = "sqlite")]
-    {
-        return database::get_sqlite_lists(&app_handle);
-    }
-
-    #[cfg(not(feature = "sqlite"))]
-    {
-        Err("No database available".to_string())
-    }
+    database::store(&app_handle)?.lists()
 }
 
 // ========== 辅助函数 ==========
@@ -106,6 +371,13 @@ async fn send_notification(
     Ok(())
 }
 
+// 启动失败时发送系统通知（此时窗口还没有加载，无法在界面中提示）
+fn show_startup_error(message: &str) {
+    if let Err(e) = notify_rust::Notification::new().summary("My Todo 启动失败").body(message).show() {
+        eprintln!("[Startup] Failed to show error notification: {}", e);
+    }
+}
+
 // 检查通知权限状态
 // Windows 桌面应用默认拥有通知权限，用户可在系统设置中手动关闭
 #[tauri::command]
@@ -165,75 +437,15 @@ async fn send_wechat_notification(
 async fn get_due_reminders(
     app_handle: tauri::AppHandle,
 ) -> Result<Vec<ReminderTask>, String> {
-    let tasks = get_tasks(app_handle.clone()).await?;
     let now = chrono::Local::now().timestamp();
     eprintln!("[Reminder] Checking reminders, current time: {}", now);
 
-    let mut reminders = Vec::new();
-    let mut checked_count = 0;
-
-    for task in tasks {
-        // 跳过已完成的任务
-        if task.completed {
-            continue;
-        }
-
-        checked_count += 1;
-
-        if let Ok(reminders_list) = serde_json::from_str::<Vec<serde_json::Value>>(&task.reminders) {
-            for reminder in reminders_list {
-                if let Some(date_str) = reminder.get("date").and_then(|d| d.as_str()) {
-                    if let Ok(timestamp) = chrono::DateTime::parse_from_rfc3339(date_str) {
-                        let reminder_time = timestamp.timestamp();
-
-                        // 检查是否已发送过此提醒
-                        let already_sent = is_reminder_sent(&app_handle, &task.id, reminder_time).await?;
-
-                        eprintln!("[Reminder] Task: {}, reminder_time: {}, now: {}, already_sent: {}",
-                            task.title, reminder_time, now, already_sent);
+    let reminders = my_todo_core::reminders::due_reminders(&**database::store(&app_handle)?, now)?;
 
-                        // 只处理已到期但未发送的提醒
-                        if reminder_time <= now && !already_sent {
-                            let repeat = reminder.get("repeat").and_then(|r| r.as_str()).unwrap_or("none").to_string();
-
-                            eprintln!("[Reminder] Adding due reminder: {} at {}", task.title, reminder_time);
-
-                            reminders.push(ReminderTask {
-                                id: uuid::Uuid::new_v4().to_string(),
-                                task_id: task.id.clone(),
-                                task_title: task.title.clone(),
-                                reminder_time,
-                                repeat: repeat.clone(),
-                                sent: false,
-                            });
-                        }
-                    }
-                }
-            }
-        }
-    }
-
-    eprintln!("[Reminder] Checked {} tasks, found {} due reminders", checked_count, reminders.len());
+    eprintln!("[Reminder] Found {} due reminders", reminders.len());
     Ok(reminders)
 }
 
-// 检查提醒是否已发送
-async fn is_reminder_sent(
-    app_handle: &tauri::AppHandle,
-    task_id: &str,
-    reminder_time: i64,
-) -> Result<bool, String> {
-    #[cfg(feature = "sqlite")]
-    {
-        return database::is_sqlite_reminder_sent(app_handle, task_id, reminder_time);
-    }
-
-    #[cfg(not(feature = "sqlite"))]
-    {
-        Ok(false)
-    }
-}
-
 // 处理单个提醒发送
 async fn process_reminder(
     app_handle: tauri::AppHandle,
@@ -291,15 +503,7 @@ async fn save_sent_reminder_internal(
     reminder_time: i64,
     reminder_data: &str,
 ) -> Result<(), String> {
-    #[cfg(feature = "sqlite")]
-    {
-        return database::save_sqlite_sent_reminder(app_handle, id, task_id, reminder_time, reminder_data);
-    }
-
-    #[cfg(not(feature = "sqlite"))]
-    {
-        Ok(())
-    }
+    database::store(app_handle)?.save_sent_reminder(id, task_id, reminder_time, reminder_data)
 }
 
 // 启动提醒检查任务（改进版）
@@ -406,15 +610,7 @@ async fn check_and_send_reminders(app_handle: tauri::AppHandle) {
 
 // 清理旧的提醒记录
 async fn cleanup_old_reminders_internal(app_handle: &tauri::AppHandle) -> Result<(), String> {
-    #[cfg(feature = "sqlite")]
-    {
-        return database::cleanup_sqlite_old_reminders(app_handle);
-    }
-
-    #[cfg(not(feature = "sqlite"))]
-    {
-        Ok(())
-    }
+    database::store(app_handle)?.cleanup_old_reminders()
 }
 
 // 保存通知设置
@@ -502,8 +698,38 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             greet,
             get_tasks,
+            query_tasks,
+            search_tasks,
             save_task,
             delete_task,
+            get_task_events,
+            reorder_tasks,
+            batch_update_tasks,
+            undo,
+            redo,
+            get_undo_state,
+            get_task_tree,
+            move_task,
+            set_task_completed,
+            trash_task,
+            restore_task,
+            get_task_dependencies,
+            add_task_dependency,
+            remove_task_dependency,
+            get_subtasks,
+            add_subtask,
+            toggle_subtask,
+            reorder_subtasks,
+            promote_subtask,
+            get_task_progress,
+            get_time_entries,
+            get_running_timer,
+            start_timer,
+            stop_timer,
+            add_manual_entry,
+            get_time_totals,
+            get_estimate_report,
+            get_statistics,
             get_lists,
             send_notification,
             send_wechat_notification,
@@ -520,22 +746,56 @@ pub fn run() {
             backup::load_webdav_settings,
             backup::test_webdav_connection,
             backup::backup_to_webdav,
+            backup::list_backups,
             backup::restore_from_webdav,
+            // 同步相关命令
+            sync::sync_webdav,
+            rest_sync::save_api_sync_settings,
+            rest_sync::load_api_sync_settings,
+            rest_sync::rest_sync_push,
+            rest_sync::rest_sync_pull,
+            rest_sync::rest_sync_now,
+            // 本地 HTTP API
+            local_api::get_local_api_info,
+            local_api::save_local_api_settings,
+            local_api::regenerate_local_api_token,
+            // 番茄钟
+            pomodoro::save_pomodoro_settings,
+            pomodoro::load_pomodoro_settings,
+            pomodoro::start_pomodoro,
+            pomodoro::pause_pomodoro,
+            pomodoro::resume_pomodoro,
+            pomodoro::skip_pomodoro_phase,
+            pomodoro::stop_pomodoro,
+            pomodoro::get_pomodoro_status,
+            pomodoro::get_pomodoros,
+            // 冲突处理命令
+            conflicts::list_task_conflicts,
+            conflicts::get_task_conflict_diff,
+            conflicts::resolve_task_conflict,
         ])
         .setup(|app| {
-            // 初始化数据库（仅 SQLite）
-            let app_handle = app.handle().clone();
-            tauri::async_runtime::spawn(async move {
-                #[cfg(feature = "sqlite")]
-                if let Err(e) = database::init_sqlite_database(&app_handle) {
-                    eprintln!("Failed to initialize database: {}", e);
-                }
-            });
+            // 打开任务存储（SQLite 或内存），之后的命令与后台任务通过 database::store 访问；
+            // 数据库无法打开时中止启动并显示错误，而不是静默使用内存存储
+            let repository = database::open_store(app.handle()).inspect_err(|e| show_startup_error(e))?;
+            app.manage(repository);
 
             // 启动提醒检查任务
             let app_handle = app.handle().clone();
             start_reminder_checker(app_handle);
 
+            // 启动 WebDAV 定期同步任务（未开启同步时跳过）
+            sync::start_sync_scheduler(app.handle().clone());
+
+            // 启动 REST 同步任务（未配置或未启用时跳过）
+            rest_sync::start_rest_sync_scheduler(app.handle().clone());
+
+            // 启动番茄钟后台任务（没有进行中的番茄钟时空转）
+            pomodoro::start_pomodoro_ticker(app.handle().clone());
+
+            // 启动本地 HTTP API（未启用时跳过）
+            local_api::start_local_api(app.handle().clone());
+
             // 拦截窗口关闭事件，隐藏窗口而不是退出（仅桌面平台）
             #[cfg(not(target_os = "android"))]
             {
@@ -556,12 +816,22 @@ pub fn run() {
             {
                 use tauri::menu::{MenuBuilder, MenuItemBuilder};
                 use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+                use tauri::Emitter;
 
+                // 计时状态（只读）与停止计时，由 start_tray_timer_updater 每秒刷新
+                let timer_item = MenuItemBuilder::with_id("timer", "未在计时").enabled(false).build(app)?;
+                let stop_timer_item = MenuItemBuilder::with_id("stop_timer", "停止计时").enabled(false).build(app)?;
                 let show_item = MenuItemBuilder::with_id("show", "显示窗口").build(app)?;
                 let quit_item = MenuItemBuilder::with_id("quit", "退出").build(app)?;
                 let menu = MenuBuilder::new(app)
+                    .items(&[&timer_item, &stop_timer_item])
+                    .separator()
                     .items(&[&show_item, &quit_item])
                     .build()?;
+                app.manage(TrayTimerMenu {
+                    status: timer_item,
+                    stop: stop_timer_item,
+                });
 
                 // 从编译时嵌入的图标数据加载并解码为 RGBA
                 let icon_data = include_bytes!("../icons/icon.png");
@@ -584,6 +854,17 @@ pub fn run() {
                                     let _ = window.set_focus();
                                 }
                             }
+                            "stop_timer" => {
+                                eprintln!("[Tray] Stop timer clicked");
+                                if let Err(e) = database::store(app)
+                                    .and_then(|store| store.stop_timer(chrono::Utc::now().timestamp()))
+                                {
+                                    eprintln!("[Tray] Failed to stop timer: {}", e);
+                                }
+                                refresh_tray_timer(app);
+                                // 通知前端刷新计时状态
+                                let _ = app.emit("timer-changed", ());
+                            }
                             "quit" => {
                                 eprintln!("[Tray] Quit clicked");
                                 app.exit(0);
@@ -608,6 +889,8 @@ pub fn run() {
                         }
                     })
                     .build(app)?;
+
+                start_tray_timer_updater(app.handle().clone());
             }
 
             Ok(())
@@ -0,0 +1,472 @@
+use axum::extract::{Path, Query, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{Emitter, Manager};
+
+use crate::database::{self, SearchResult, Task, TaskBatch, TaskList};
+use my_todo_core::crypto::constant_time_eq;
+use my_todo_core::undo::{affected_ids, batch_ids, undoable};
+
+// 默认监听端口（仅绑定 127.0.0.1）
+const DEFAULT_PORT: u16 = 27123;
+const SETTINGS_FILE: &str = "local_api_settings.json";
+const TOKEN_FILE: &str = "local_api_token";
+
+// 正在运行的本地 API 服务（用于停止或重启）
+static SERVER: Mutex<Option<RunningServer>> = Mutex::new(None);
+
+struct RunningServer {
+    port: u16,
+    shutdown: tokio::sync::oneshot::Sender<()>,
+}
+
+// 本地 HTTP API 设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalApiSettings {
+    pub enabled: bool,
+    #[serde(default = "default_port")]
+    pub port: u16,
+}
+
+impl Default for LocalApiSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: DEFAULT_PORT,
+        }
+    }
+}
+
+fn default_port() -> u16 {
+    DEFAULT_PORT
+}
+
+// 本地 API 状态（供设置界面展示）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalApiInfo {
+    pub settings: LocalApiSettings,
+    pub running: bool,
+    pub url: String,
+    pub token: String,
+}
+
+#[derive(Clone)]
+struct ApiState {
+    app_handle: tauri::AppHandle,
+    token: String,
+}
+
+// 接口错误（响应体为 `{ "message": ... }`）
+struct ApiError(StatusCode, String);
+
+impl ApiError {
+    fn not_found(what: &str, id: &str) -> Self {
+        Self(StatusCode::NOT_FOUND, format!("{} not found: {}", what, id))
+    }
+
+    fn bad_request(message: String) -> Self {
+        Self(StatusCode::BAD_REQUEST, message)
+    }
+}
+
+impl From<String> for ApiError {
+    fn from(message: String) -> Self {
+        Self(StatusCode::INTERNAL_SERVER_ERROR, message)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(serde_json::json!({ "message": self.1 }))).into_response()
+    }
+}
+
+type ApiResult<T> = Result<Json<T>, ApiError>;
+
+// ========== 设置与令牌 ==========
+
+fn get_app_data_file(app_handle: &tauri::AppHandle, name: &str) -> Result<PathBuf, String> {
+    let dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create app data dir: {}", e))?;
+    Ok(dir.join(name))
+}
+
+fn load_settings(app_handle: &tauri::AppHandle) -> Result<LocalApiSettings, String> {
+    let path = get_app_data_file(app_handle, SETTINGS_FILE)?;
+    if !path.exists() {
+        return Ok(LocalApiSettings::default());
+    }
+    let json = fs::read_to_string(path).map_err(|e| format!("Failed to read settings: {}", e))?;
+    serde_json::from_str(&json).map_err(|e| format!("Failed to parse settings: {}", e))
+}
+
+// 读取访问令牌（首次使用时生成并保存到应用数据目录）
+fn load_or_create_token(app_handle: &tauri::AppHandle) -> Result<String, String> {
+    let path = get_app_data_file(app_handle, TOKEN_FILE)?;
+    if let Ok(token) = fs::read_to_string(&path) {
+        let token = token.trim();
+        if !token.is_empty() {
+            return Ok(token.to_string());
+        }
+    }
+    write_new_token(app_handle)
+}
+
+fn write_new_token(app_handle: &tauri::AppHandle) -> Result<String, String> {
+    let path = get_app_data_file(app_handle, TOKEN_FILE)?;
+    let token = format!(
+        "{}{}",
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    );
+    // 令牌文件仅当前用户可读：删除旧文件后以 0600 权限新建，写入前不会出现其他用户可读的窗口
+    let _ = fs::remove_file(&path);
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+        .open(&path)
+        .and_then(|mut file| file.write_all(token.as_bytes()))
+        .map_err(|e| format!("Failed to write API token: {}", e))?;
+
+    Ok(token)
+}
+
+// 获取本地 API 设置、运行状态与令牌
+#[tauri::command]
+pub async fn get_local_api_info(app_handle: tauri::AppHandle) -> Result<LocalApiInfo, String> {
+    let settings = load_settings(&app_handle)?;
+    let token = load_or_create_token(&app_handle)?;
+    let running = running_port().is_some();
+    Ok(LocalApiInfo {
+        url: format!("http://127.0.0.1:{}", settings.port),
+        settings,
+        running,
+        token,
+    })
+}
+
+// 保存本地 API 设置，并按设置启动、重启或停止服务
+#[tauri::command]
+pub async fn save_local_api_settings(
+    app_handle: tauri::AppHandle,
+    settings: LocalApiSettings,
+) -> Result<LocalApiInfo, String> {
+    if settings.port == 0 {
+        return Err("Invalid port".to_string());
+    }
+    let path = get_app_data_file(&app_handle, SETTINGS_FILE)?;
+    let json = serde_json::to_string_pretty(&settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    fs::write(path, json).map_err(|e| format!("Failed to write settings: {}", e))?;
+
+    stop_server();
+    if settings.enabled {
+        start_server(&app_handle, settings.port).await?;
+    }
+    get_local_api_info(app_handle).await
+}
+
+// 重新生成访问令牌（旧令牌立即失效）
+#[tauri::command]
+pub async fn regenerate_local_api_token(app_handle: tauri::AppHandle) -> Result<LocalApiInfo, String> {
+    write_new_token(&app_handle)?;
+    if let Some(port) = running_port() {
+        stop_server();
+        start_server(&app_handle, port).await?;
+    }
+    get_local_api_info(app_handle).await
+}
+
+// 应用启动时按设置启动本地 API
+pub fn start_local_api(app_handle: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let settings = match load_settings(&app_handle) {
+            Ok(settings) => settings,
+            Err(e) => {
+                eprintln!("[LocalApi] {}", e);
+                return;
+            }
+        };
+        if !settings.enabled {
+            return;
+        }
+        if let Err(e) = start_server(&app_handle, settings.port).await {
+            eprintln!("[LocalApi] Failed to start: {}", e);
+        }
+    });
+}
+
+fn running_port() -> Option<u16> {
+    SERVER.lock().ok().and_then(|s| s.as_ref().map(|s| s.port))
+}
+
+fn stop_server() {
+    if let Some(server) = SERVER.lock().ok().and_then(|mut s| s.take()) {
+        let _ = server.shutdown.send(());
+        eprintln!("[LocalApi] Stopped");
+    }
+}
+
+async fn start_server(app_handle: &tauri::AppHandle, port: u16) -> Result<(), String> {
+    let state = ApiState {
+        app_handle: app_handle.clone(),
+        token: load_or_create_token(app_handle)?,
+    };
+
+    // 只监听回环地址，不对局域网开放
+    let listener = tokio::net::TcpListener::bind(("127.0.0.1", port))
+        .await
+        .map_err(|e| format!("Failed to bind 127.0.0.1:{}: {}", port, e))?;
+    let (shutdown, rx) = tokio::sync::oneshot::channel::<()>();
+
+    tauri::async_runtime::spawn(async move {
+        let result = axum::serve(listener, router(state))
+            .with_graceful_shutdown(async {
+                let _ = rx.await;
+            })
+            .await;
+        if let Err(e) = result {
+            eprintln!("[LocalApi] Server error: {}", e);
+        }
+    });
+
+    if let Ok(mut server) = SERVER.lock() {
+        *server = Some(RunningServer { port, shutdown });
+    }
+    eprintln!("[LocalApi] Listening on http://127.0.0.1:{}", port);
+    Ok(())
+}
+
+// ========== 路由 ==========
+
+fn router(state: ApiState) -> Router {
+    let api = Router::new()
+        .route("/api/tasks", get(list_tasks).post(create_task))
+        .route("/api/tasks/search", get(search_tasks))
+        .route("/api/tasks/batch", post(batch_tasks))
+        .route("/api/tasks/{id}", get(show_task).put(update_task).delete(remove_task))
+        .route("/api/tasks/{id}/complete", post(complete_task))
+        .route("/api/lists", get(list_lists).post(create_list))
+        .route("/api/lists/{id}", get(show_list).put(update_list).delete(remove_list))
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_token));
+
+    Router::new()
+        .route("/api/health", get(health))
+        .merge(api)
+        .with_state(state)
+}
+
+// 校验 `Authorization: Bearer <token>`
+async fn require_token(State(state): State<ApiState>, request: Request, next: Next) -> Response {
+    let authorized = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .is_some_and(|token| constant_time_eq(token.as_bytes(), state.token.as_bytes()));
+    if !authorized {
+        return ApiError(StatusCode::UNAUTHORIZED, "Invalid or missing API token".to_string()).into_response();
+    }
+    next.run(request).await
+}
+
+async fn health() -> Json<serde_json::Value> {
+    Json(serde_json::json!({ "connected": true }))
+}
+
+// ========== 任务接口 ==========
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TaskFilter {
+    list_id: Option<String>,
+    completed: Option<bool>,
+    priority: Option<String>,
+    status: Option<String>,
+    q: Option<String>,
+}
+
+async fn list_tasks(State(state): State<ApiState>, Query(filter): Query<TaskFilter>) -> ApiResult<Vec<Task>> {
+    let tasks = load_tasks(&state.app_handle)?;
+    let query = filter.q.as_deref().map(str::to_lowercase);
+    let tasks = tasks
+        .into_iter()
+        .filter(|t| filter.list_id.as_ref().is_none_or(|v| &t.list_id == v))
+        .filter(|t| filter.completed.is_none_or(|v| t.completed == v))
+        .filter(|t| filter.priority.as_ref().is_none_or(|v| &t.priority == v))
+        .filter(|t| filter.status.as_ref().is_none_or(|v| &t.status == v))
+        .filter(|t| query.as_deref().is_none_or(|q| matches_query(t, q)))
+        .collect();
+    Ok(Json(tasks))
+}
+
+#[derive(Deserialize)]
+struct SearchQuery {
+    q: String,
+    limit: Option<usize>,
+}
+
+// 全文搜索（与桌面端搜索一致：标题、描述与子任务标题，按相关度排序并返回高亮摘要）
+async fn search_tasks(State(state): State<ApiState>, Query(search): Query<SearchQuery>) -> ApiResult<Vec<SearchResult>> {
+    if search.q.trim().is_empty() {
+        return Err(ApiError::bad_request("Search query is empty".to_string()));
+    }
+    let results = database::store(&state.app_handle)?.search_tasks(&search.q, search.limit.unwrap_or(50))?;
+    Ok(Json(results))
+}
+
+async fn show_task(State(state): State<ApiState>, Path(id): Path<String>) -> ApiResult<Task> {
+    find_task(&state.app_handle, &id).map(Json)
+}
+
+async fn create_task(
+    State(state): State<ApiState>,
+    Json(body): Json<serde_json::Value>,
+) -> Result<(StatusCode, Json<Task>), ApiError> {
+    let task = database::task_from_partial(body).map_err(ApiError::bad_request)?;
+    store_task(&state.app_handle, &task)?;
+    Ok((StatusCode::CREATED, Json(task)))
+}
+
+async fn update_task(
+    State(state): State<ApiState>,
+    Path(id): Path<String>,
+    Json(changes): Json<serde_json::Value>,
+) -> ApiResult<Task> {
+    let existing = find_task(&state.app_handle, &id)?;
+    let task = database::update_task_from_partial(&existing, changes).map_err(ApiError::bad_request)?;
+    store_task(&state.app_handle, &task)?;
+    Ok(Json(task))
+}
+
+// 批量修改任务（请求体同 webapi.ts 的 batchUpdateTasks），写入撤销日志
+async fn batch_tasks(State(state): State<ApiState>, Json(batch): Json<TaskBatch>) -> ApiResult<Vec<Task>> {
+    let store = database::store(&state.app_handle)?;
+    let ids = batch_ids(&**store, &batch)?;
+    let tasks = undoable(&**store, "batch", &ids, |repo| repo.batch_update_tasks(&batch))
+        .map_err(ApiError::bad_request)?;
+    let _ = state.app_handle.emit("tasks-changed", ());
+    Ok(Json(tasks))
+}
+
+// 标记任务完成
+async fn complete_task(State(state): State<ApiState>, Path(id): Path<String>) -> ApiResult<Task> {
+    find_task(&state.app_handle, &id)?;
+    // 与界面一致：连同未完成的后代一起完成，可撤销
+    let store = database::store(&state.app_handle)?;
+    let ids = affected_ids(&**store, &id, false)?;
+    let tasks = undoable(&**store, "complete", &ids, |repo| repo.set_task_completed(&id, true))?;
+    let _ = state.app_handle.emit("tasks-changed", ());
+    tasks.into_iter().next().map(Json).ok_or_else(|| ApiError::not_found("Task", &id))
+}
+
+async fn remove_task(State(state): State<ApiState>, Path(id): Path<String>) -> ApiResult<serde_json::Value> {
+    find_task(&state.app_handle, &id)?;
+    let store = database::store(&state.app_handle)?;
+    let ids = affected_ids(&**store, &id, false)?;
+    undoable(&**store, "delete", &ids, |repo| repo.delete_task_tree(&id))?;
+    let _ = state.app_handle.emit("tasks-changed", ());
+    Ok(Json(serde_json::json!({ "deleted": id })))
+}
+
+// ========== 清单接口 ==========
+
+async fn list_lists(State(state): State<ApiState>) -> ApiResult<Vec<TaskList>> {
+    Ok(Json(load_lists(&state.app_handle)?))
+}
+
+async fn show_list(State(state): State<ApiState>, Path(id): Path<String>) -> ApiResult<TaskList> {
+    find_list(&state.app_handle, &id).map(Json)
+}
+
+async fn create_list(
+    State(state): State<ApiState>,
+    Json(body): Json<serde_json::Value>,
+) -> Result<(StatusCode, Json<TaskList>), ApiError> {
+    let list = database::list_from_partial(body).map_err(ApiError::bad_request)?;
+    store_list(&state.app_handle, &list)?;
+    Ok((StatusCode::CREATED, Json(list)))
+}
+
+async fn update_list(
+    State(state): State<ApiState>,
+    Path(id): Path<String>,
+    Json(changes): Json<serde_json::Value>,
+) -> ApiResult<TaskList> {
+    let existing = find_list(&state.app_handle, &id)?;
+    let list = database::update_list_from_partial(&existing, changes).map_err(ApiError::bad_request)?;
+    store_list(&state.app_handle, &list)?;
+    Ok(Json(list))
+}
+
+async fn remove_list(State(state): State<ApiState>, Path(id): Path<String>) -> ApiResult<serde_json::Value> {
+    if !database::store(&state.app_handle)?.delete_list(&id)? {
+        return Err(ApiError::not_found("List", &id));
+    }
+    let _ = state.app_handle.emit("tasks-changed", ());
+    Ok(Json(serde_json::json!({ "deleted": id })))
+}
+
+// ========== 数据访问 ==========
+
+fn load_tasks(app_handle: &tauri::AppHandle) -> Result<Vec<Task>, ApiError> {
+    Ok(database::store(app_handle)?.tasks()?)
+}
+
+fn find_task(app_handle: &tauri::AppHandle, id: &str) -> Result<Task, ApiError> {
+    database::store(app_handle)?.task(id)?.ok_or_else(|| ApiError::not_found("Task", id))
+}
+
+// 保存任务（可撤销）并通知界面刷新
+fn store_task(app_handle: &tauri::AppHandle, task: &Task) -> Result<(), ApiError> {
+    let store = database::store(app_handle)?;
+    undoable(&**store, "save", std::slice::from_ref(&task.id), |repo| repo.save_task(task))?;
+    let _ = app_handle.emit("tasks-changed", ());
+    Ok(())
+}
+
+fn load_lists(app_handle: &tauri::AppHandle) -> Result<Vec<TaskList>, ApiError> {
+    Ok(database::store(app_handle)?.lists()?)
+}
+
+fn find_list(app_handle: &tauri::AppHandle, id: &str) -> Result<TaskList, ApiError> {
+    load_lists(app_handle)?
+        .into_iter()
+        .find(|l| l.id == id)
+        .ok_or_else(|| ApiError::not_found("List", id))
+}
+
+fn store_list(app_handle: &tauri::AppHandle, list: &TaskList) -> Result<(), ApiError> {
+    database::store(app_handle)?.save_list(list)?;
+    let _ = app_handle.emit("tasks-changed", ());
+    Ok(())
+}
+
+// query 需为小写
+fn matches_query(task: &Task, query: &str) -> bool {
+    task.title.to_lowercase().contains(query)
+        || task
+            .description
+            .as_deref()
+            .is_some_and(|d| d.to_lowercase().contains(query))
+        || task.tags.to_lowercase().contains(query)
+}
+
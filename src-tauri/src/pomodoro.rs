@@ -0,0 +1,205 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+use crate::database::{self, PomodoroRecord};
+use my_todo_core::pomodoro::{PomodoroPhase, PomodoroSettings, PomodoroState, PomodoroStatus, PomodoroTransition};
+
+const SETTINGS_FILE: &str = "pomodoro_settings.json";
+
+// 当前番茄钟（由后台任务每秒推进，窗口隐藏到托盘时同样运行）
+static POMODORO: Mutex<Option<PomodoroState>> = Mutex::new(None);
+
+fn now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+fn current() -> Result<std::sync::MutexGuard<'static, Option<PomodoroState>>, String> {
+    POMODORO.lock().map_err(|e| format!("Failed to lock pomodoro state: {}", e))
+}
+
+// 获取设置文件路径
+fn get_settings_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create app data dir: {}", e))?;
+    Ok(dir.join(SETTINGS_FILE))
+}
+
+// 保存番茄钟设置（下一次开始时生效）
+#[tauri::command]
+pub async fn save_pomodoro_settings(
+    app_handle: tauri::AppHandle,
+    settings: PomodoroSettings,
+) -> Result<(), String> {
+    let path = get_settings_path(&app_handle)?;
+    let json = serde_json::to_string_pretty(&settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    fs::write(path, json).map_err(|e| format!("Failed to write settings: {}", e))?;
+    Ok(())
+}
+
+// 加载番茄钟设置（未保存过时返回默认设置）
+#[tauri::command]
+pub async fn load_pomodoro_settings(app_handle: tauri::AppHandle) -> Result<PomodoroSettings, String> {
+    let path = get_settings_path(&app_handle)?;
+    if !path.exists() {
+        return Ok(PomodoroSettings::default());
+    }
+    let data = fs::read_to_string(path).map_err(|e| format!("Failed to read settings: {}", e))?;
+    serde_json::from_str(&data).map_err(|e| format!("Failed to parse settings: {}", e))
+}
+
+// 为任务开始番茄钟（替换正在进行的番茄钟）
+#[tauri::command]
+pub async fn start_pomodoro(app_handle: tauri::AppHandle, task_id: String) -> Result<PomodoroStatus, String> {
+    if database::store(&app_handle)?.task(&task_id)?.is_none() {
+        return Err(format!("Task not found: {}", task_id));
+    }
+    let settings = load_pomodoro_settings(app_handle.clone()).await?;
+
+    let now = now();
+    let state = PomodoroState::new(&task_id, settings, now);
+    let status = state.status(now);
+    *current()? = Some(state);
+
+    eprintln!("[Pomodoro] Started for task {}", task_id);
+    let _ = app_handle.emit("pomodoro-tick", &status);
+    Ok(status)
+}
+
+// 暂停番茄钟
+#[tauri::command]
+pub async fn pause_pomodoro(app_handle: tauri::AppHandle) -> Result<Option<PomodoroStatus>, String> {
+    update(&app_handle, |state, now| state.pause(now))
+}
+
+// 继续番茄钟
+#[tauri::command]
+pub async fn resume_pomodoro(app_handle: tauri::AppHandle) -> Result<Option<PomodoroStatus>, String> {
+    update(&app_handle, |state, now| state.resume(now))
+}
+
+// 跳过当前阶段（跳过的专注不记录番茄）
+#[tauri::command]
+pub async fn skip_pomodoro_phase(app_handle: tauri::AppHandle) -> Result<Option<PomodoroStatus>, String> {
+    let (status, transition) = {
+        let mut guard = current()?;
+        let Some(state) = guard.as_mut() else {
+            return Ok(None);
+        };
+        let now = now();
+        let transition = state.skip(now);
+        (state.status(now), transition)
+    };
+
+    let _ = app_handle.emit("pomodoro-phase-changed", &transition);
+    let _ = app_handle.emit("pomodoro-tick", &status);
+    Ok(Some(status))
+}
+
+// 停止番茄钟
+#[tauri::command]
+pub async fn stop_pomodoro(app_handle: tauri::AppHandle) -> Result<(), String> {
+    if current()?.take().is_some() {
+        eprintln!("[Pomodoro] Stopped");
+        let _ = app_handle.emit("pomodoro-stopped", ());
+    }
+    Ok(())
+}
+
+// 获取番茄钟状态（未开始时返回 None）
+#[tauri::command]
+pub async fn get_pomodoro_status() -> Result<Option<PomodoroStatus>, String> {
+    Ok(current()?.as_ref().map(|state| state.status(now())))
+}
+
+// 获取完成的番茄记录（task_id 为空时返回全部）
+#[tauri::command]
+pub async fn get_pomodoros(app_handle: tauri::AppHandle, task_id: Option<String>) -> Result<Vec<PomodoroRecord>, String> {
+    database::store(&app_handle)?.pomodoros(task_id.as_deref())
+}
+
+// 启动番茄钟后台任务：每秒推送 pomodoro-tick，阶段结束时推送 pomodoro-phase-changed 并发送系统通知
+pub fn start_pomodoro_ticker(app_handle: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(1));
+
+        loop {
+            interval.tick().await;
+
+            let (status, transition) = {
+                let mut guard = match current() {
+                    Ok(guard) => guard,
+                    Err(e) => {
+                        eprintln!("[Pomodoro] {}", e);
+                        continue;
+                    }
+                };
+                let Some(state) = guard.as_mut().filter(|s| !s.is_paused()) else {
+                    continue;
+                };
+                let now = now();
+                let transition = state.tick(now);
+                (state.status(now), transition)
+            };
+
+            if let Some(transition) = transition {
+                finish_phase(&app_handle, &transition).await;
+            }
+            let _ = app_handle.emit("pomodoro-tick", &status);
+        }
+    });
+}
+
+// 修改番茄钟并推送最新状态
+fn update(
+    app_handle: &tauri::AppHandle,
+    apply: impl FnOnce(&mut PomodoroState, i64),
+) -> Result<Option<PomodoroStatus>, String> {
+    let status = {
+        let mut guard = current()?;
+        let Some(state) = guard.as_mut() else {
+            return Ok(None);
+        };
+        let now = now();
+        apply(state, now);
+        state.status(now)
+    };
+    let _ = app_handle.emit("pomodoro-tick", &status);
+    Ok(Some(status))
+}
+
+// 阶段结束：记录完成的番茄、通知前端并发送系统通知
+async fn finish_phase(app_handle: &tauri::AppHandle, transition: &PomodoroTransition) {
+    let title = match database::store(app_handle).and_then(|store| store.task(&transition.task_id)) {
+        Ok(task) => task.map(|t| t.title).unwrap_or_default(),
+        Err(e) => {
+            eprintln!("[Pomodoro] Failed to load task: {}", e);
+            String::new()
+        }
+    };
+
+    if let Some(record) = transition.record() {
+        match database::store(app_handle).and_then(|store| store.log_pomodoro(&record)) {
+            Ok(()) => eprintln!("[Pomodoro] Logged pomodoro for task {}", transition.task_id),
+            Err(e) => eprintln!("[Pomodoro] Failed to log pomodoro: {}", e),
+        }
+    }
+
+    eprintln!("[Pomodoro] {:?} -> {:?}", transition.finished, transition.next);
+    let _ = app_handle.emit("pomodoro-phase-changed", transition);
+
+    let body = match transition.next {
+        PomodoroPhase::Work => format!("休息结束，开始专注：{}", title),
+        PomodoroPhase::ShortBreak => format!("完成一个番茄（{}），短暂休息一下", title),
+        PomodoroPhase::LongBreak => format!("已完成 {} 个番茄（{}），好好休息一下", transition.completed, title),
+    };
+    if let Err(e) = crate::send_notification("番茄钟".to_string(), body).await {
+        eprintln!("[Pomodoro] Failed to send notification: {}", e);
+    }
+}
@@ -0,0 +1,436 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+use crate::database::{self, Task, TaskRepository};
+use crate::conflicts;
+use my_todo_core::sync::{DownloadResponse, SyncChange, UploadRequest, UploadResponse};
+
+// 同步状态键
+const STATE_DEVICE_ID: &str = "device_id";
+const STATE_PUSHED_SEQ: &str = "rest_pushed_seq";
+const STATE_PULL_CURSOR: &str = "rest_pull_cursor";
+
+// REST 同步设置（对应前端 webapi.ts 的 ApiConfig）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiSyncSettings {
+    // 是否启用后台自动同步
+    pub enabled: bool,
+    // 服务地址（例如：http://localhost:8787）
+    pub base_url: String,
+    // 访问令牌（以 Bearer 方式发送）
+    #[serde(default)]
+    pub api_key: Option<String>,
+    // 自动同步间隔（分钟，默认 5）
+    #[serde(default)]
+    pub interval_minutes: Option<u32>,
+}
+
+// 同步结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestSyncSummary {
+    pub pushed: usize,
+    pub pulled: usize,
+    // 以服务端较新版本覆盖本地的冲突数
+    pub conflicts: usize,
+}
+
+// 获取设置文件路径
+fn get_settings_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create app data dir: {}", e))?;
+    Ok(dir.join("api_sync_settings.json"))
+}
+
+// 保存 REST 同步设置
+#[tauri::command]
+pub async fn save_api_sync_settings(
+    app_handle: tauri::AppHandle,
+    settings: ApiSyncSettings,
+) -> Result<(), String> {
+    let path = get_settings_path(&app_handle)?;
+    let json = serde_json::to_string_pretty(&settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    fs::write(path, json).map_err(|e| format!("Failed to write settings: {}", e))?;
+    Ok(())
+}
+
+// 加载 REST 同步设置
+#[tauri::command]
+pub async fn load_api_sync_settings(
+    app_handle: tauri::AppHandle,
+) -> Result<Option<ApiSyncSettings>, String> {
+    let path = get_settings_path(&app_handle)?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let data = fs::read_to_string(path).map_err(|e| format!("Failed to read settings: {}", e))?;
+    let settings: ApiSyncSettings =
+        serde_json::from_str(&data).map_err(|e| format!("Failed to parse settings: {}", e))?;
+    Ok(Some(settings))
+}
+
+// 推送本地变更到 REST 服务
+#[tauri::command]
+pub async fn rest_sync_push(app_handle: tauri::AppHandle) -> Result<RestSyncSummary, String> {
+    let client = ApiClient::from_settings(&app_handle).await?;
+    let summary = push_changes(&app_handle, &client).await?;
+    notify_if_changed(&app_handle, &summary);
+    Ok(summary)
+}
+
+// 从 REST 服务拉取远端变更
+#[tauri::command]
+pub async fn rest_sync_pull(app_handle: tauri::AppHandle) -> Result<RestSyncSummary, String> {
+    let client = ApiClient::from_settings(&app_handle).await?;
+    let summary = pull_changes(&app_handle, &client).await?;
+    notify_if_changed(&app_handle, &summary);
+    Ok(summary)
+}
+
+// 完整同步：先推送再拉取
+#[tauri::command]
+pub async fn rest_sync_now(app_handle: tauri::AppHandle) -> Result<RestSyncSummary, String> {
+    let client = ApiClient::from_settings(&app_handle).await?;
+    let pushed = push_changes(&app_handle, &client).await?;
+    let pulled = pull_changes(&app_handle, &client).await?;
+    let summary = RestSyncSummary {
+        pushed: pushed.pushed,
+        pulled: pulled.pulled,
+        conflicts: pushed.conflicts + pulled.conflicts,
+    };
+    notify_if_changed(&app_handle, &summary);
+    Ok(summary)
+}
+
+// 启动后台同步任务（由 Rust 驱动，窗口隐藏到托盘时同样运行）
+pub fn start_rest_sync_scheduler(app_handle: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(60));
+        let mut elapsed_minutes: u32 = 0;
+
+        loop {
+            interval.tick().await;
+
+            let settings = match load_api_sync_settings(app_handle.clone()).await {
+                Ok(Some(s)) if s.enabled => s,
+                _ => {
+                    elapsed_minutes = 0;
+                    continue;
+                }
+            };
+
+            elapsed_minutes += 1;
+            if elapsed_minutes < settings.interval_minutes.unwrap_or(5).max(1) {
+                continue;
+            }
+            elapsed_minutes = 0;
+
+            match rest_sync_now(app_handle.clone()).await {
+                Ok(summary) => eprintln!("[RestSync] Done: {:?}", summary),
+                Err(e) => eprintln!("[RestSync] Auto sync failed: {}", e),
+            }
+        }
+    });
+}
+
+fn notify_if_changed(app_handle: &tauri::AppHandle, summary: &RestSyncSummary) {
+    if summary.pulled > 0 || summary.conflicts > 0 {
+        let _ = app_handle.emit("tasks-changed", ());
+    }
+}
+
+// REST 服务客户端
+struct ApiClient {
+    client: reqwest::Client,
+    base_url: String,
+    api_key: Option<String>,
+}
+
+impl ApiClient {
+    async fn from_settings(app_handle: &tauri::AppHandle) -> Result<Self, String> {
+        let settings = load_api_sync_settings(app_handle.clone())
+            .await?
+            .ok_or_else(|| "API sync settings not configured".to_string())?;
+        if settings.base_url.trim().is_empty() {
+            return Err("API base URL not configured".to_string());
+        }
+
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+        Ok(Self {
+            client,
+            base_url: settings.base_url.trim().trim_end_matches('/').to_string(),
+            api_key: settings.api_key.filter(|k| !k.is_empty()),
+        })
+    }
+
+    fn request(&self, method: reqwest::Method, endpoint: &str) -> reqwest::RequestBuilder {
+        let request = self.client.request(method, format!("{}{}", self.base_url, endpoint));
+        match &self.api_key {
+            Some(key) => request.bearer_auth(key),
+            None => request,
+        }
+    }
+
+    // 发送请求并解析 JSON 响应，错误时读取服务端返回的 message
+    async fn send<T: serde::de::DeserializeOwned>(&self, request: reqwest::RequestBuilder) -> Result<T, String> {
+        let resp = request
+            .send()
+            .await
+            .map_err(|e| format!("Failed to connect sync server: {}", e))?;
+        let status = resp.status();
+        if !status.is_success() {
+            let message = resp
+                .json::<serde_json::Value>()
+                .await
+                .ok()
+                .and_then(|v| v.get("message").and_then(|m| m.as_str()).map(str::to_string))
+                .unwrap_or_else(|| "Request failed".to_string());
+            return Err(format!("Sync server error: {} - {}", status, message));
+        }
+        resp.json::<T>()
+            .await
+            .map_err(|e| format!("Failed to parse sync response: {}", e))
+    }
+}
+
+// 推送 seq 大于上次推送位置的本地变更；服务端版本更新时以服务端为准
+async fn push_changes(app_handle: &tauri::AppHandle, client: &ApiClient) -> Result<RestSyncSummary, String> {
+    let store = database::store(app_handle)?;
+    let device_id = get_device_id(&**store)?;
+    let pushed_seq = store.sync_state(STATE_PUSHED_SEQ)?.and_then(|v| v.parse::<i64>().ok());
+    let max_seq = store.max_change_seq()?;
+
+    let mut changes = Vec::new();
+    match pushed_seq {
+        Some(after) => {
+            for change in store.changes_since(after)? {
+                changes.push(match change.entity.as_str() {
+                    "task" => task_change(&**store, change.entity_id, &change.op, change.changed_at)?,
+                    "list" => list_change(&**store, change.entity_id, &change.op, change.changed_at)?,
+                    _ => continue,
+                });
+            }
+        }
+        // 首次同步：推送全部清单与任务
+        None => {
+            let now = chrono::Utc::now().to_rfc3339();
+            for list in store.lists()? {
+                changes.push(SyncChange {
+                    entity: "list".to_string(),
+                    id: list.id.clone(),
+                    op: "upsert".to_string(),
+                    updated_at: now.clone(),
+                    data: None,
+                    list: Some(list),
+                });
+            }
+            for task in store.tasks()? {
+                changes.push(SyncChange {
+                    entity: "task".to_string(),
+                    id: task.id.clone(),
+                    op: "upsert".to_string(),
+                    updated_at: task.updated_at.clone(),
+                    data: Some(task),
+                    list: None,
+                });
+            }
+        }
+    }
+
+    let mut summary = RestSyncSummary::default();
+    if !changes.is_empty() {
+        eprintln!("[RestSync] Pushing {} changes", changes.len());
+        let body = UploadRequest { device_id, changes };
+        let response: UploadResponse = client
+            .send(client.request(reqwest::Method::POST, "/api/sync/upload").json(&body))
+            .await?;
+
+        summary.pushed = response.accepted.len();
+        summary.conflicts = response.conflicts.len();
+
+        // 服务端版本胜出：记录被覆盖的本地版本（本地已删除时以墓碑记录）
+        let mut task_conflicts = Vec::new();
+        for change in response.conflicts.iter().filter(|c| c.entity == "task") {
+            let local_time = body
+                .changes
+                .iter()
+                .find(|c| c.entity == "task" && c.id == change.id)
+                .map_or("", |c| c.updated_at.as_str());
+            let local = store.task(&change.id)?;
+            if let Some((local, remote)) = conflict_versions(local, local_time, change.data.clone(), &change.updated_at) {
+                task_conflicts.push(conflicts::new_conflict("rest", &local, &remote, "remote"));
+            }
+        }
+
+        apply_remote_changes(&**store, response.conflicts)?;
+        conflicts::record_conflicts(app_handle, &task_conflicts)?;
+    }
+
+    store.set_sync_state(STATE_PUSHED_SEQ, &max_seq.to_string())?;
+    Ok(summary)
+}
+
+// 拉取游标之后的远端变更；本地有未推送且更新的修改时保留本地版本
+async fn pull_changes(app_handle: &tauri::AppHandle, client: &ApiClient) -> Result<RestSyncSummary, String> {
+    let store = database::store(app_handle)?;
+    let device_id = get_device_id(&**store)?;
+    let cursor = store.sync_state(STATE_PULL_CURSOR)?.unwrap_or_else(|| "0".to_string());
+
+    let response: DownloadResponse = client
+        .send(
+            client
+                .request(reqwest::Method::GET, "/api/sync/download")
+                .query(&[("since", cursor.as_str()), ("deviceId", device_id.as_str())]),
+        )
+        .await?;
+
+    let pushed_seq = store
+        .sync_state(STATE_PUSHED_SEQ)?
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or(0);
+    let pending = store.changes_since(pushed_seq)?;
+
+    let mut summary = RestSyncSummary::default();
+    let mut accepted = Vec::new();
+    let mut task_conflicts = Vec::new();
+    for change in response.changes {
+        if change.entity == "list" {
+            // 清单没有更新时间：本地有未推送的修改且更晚时保留本地
+            let local_newer = pending.iter().any(|c| {
+                c.entity == "list" && c.entity_id == change.id && timestamp(&c.changed_at) >= timestamp(&change.updated_at)
+            });
+            if local_newer {
+                summary.conflicts += 1;
+            } else {
+                accepted.push(change);
+            }
+            continue;
+        }
+        if change.entity != "task" {
+            continue;
+        }
+        // 冲突检测：本地未推送的修改比远端更新时保留本地
+        let Some(pending_change) = pending.iter().find(|c| c.entity == "task" && c.entity_id == change.id) else {
+            accepted.push(change);
+            continue;
+        };
+        let local_task = store.task(&change.id)?;
+        let local_time = local_task
+            .as_ref()
+            .map_or_else(|| pending_change.changed_at.clone(), |t| t.updated_at.clone());
+        if timestamp(&local_time) >= timestamp(&change.updated_at) {
+            // 本地版本胜出：记录未采用的远端版本（任一方已删除时以墓碑记录）
+            if let Some((local, remote)) = conflict_versions(local_task, &local_time, change.data, &change.updated_at) {
+                task_conflicts.push(conflicts::new_conflict("rest", &local, &remote, "local"));
+            }
+            summary.conflicts += 1;
+            continue;
+        }
+        accepted.push(change);
+    }
+
+    summary.pulled = accepted.len();
+    apply_remote_changes(&**store, accepted)?;
+    conflicts::record_conflicts(app_handle, &task_conflicts)?;
+    store.set_sync_state(STATE_PULL_CURSOR, &response.cursor)?;
+
+    Ok(summary)
+}
+
+// 获取（或生成）本设备 ID，服务端据此排除设备自身的变更
+fn get_device_id(store: &dyn TaskRepository) -> Result<String, String> {
+    if let Some(id) = store.sync_state(STATE_DEVICE_ID)? {
+        return Ok(id);
+    }
+    let id = uuid::Uuid::new_v4().to_string();
+    store.set_sync_state(STATE_DEVICE_ID, &id)?;
+    Ok(id)
+}
+
+// 构造本地任务变更（任务已不存在时作为删除推送）
+fn task_change(store: &dyn TaskRepository, id: String, op: &str, changed_at: String) -> Result<SyncChange, String> {
+    let task = match op {
+        "delete" => None,
+        _ => store.task(&id)?,
+    };
+    Ok(SyncChange {
+        entity: "task".to_string(),
+        op: if task.is_some() { "upsert" } else { "delete" }.to_string(),
+        updated_at: task.as_ref().map_or(changed_at, |t| t.updated_at.clone()),
+        id,
+        data: task,
+        list: None,
+    })
+}
+
+// 构造本地清单变更（清单已不存在时作为删除推送）
+fn list_change(store: &dyn TaskRepository, id: String, op: &str, changed_at: String) -> Result<SyncChange, String> {
+    let list = match op {
+        "delete" => None,
+        _ => store.lists()?.into_iter().find(|l| l.id == id),
+    };
+    Ok(SyncChange {
+        entity: "list".to_string(),
+        op: if list.is_some() { "upsert" } else { "delete" }.to_string(),
+        updated_at: changed_at,
+        id,
+        data: None,
+        list,
+    })
+}
+
+// 冲突双方的任务版本：一方已删除时以另一方标记为已删除的副本（墓碑）表示，双方都已删除时没有冲突
+fn conflict_versions(
+    local: Option<Task>,
+    local_time: &str,
+    remote: Option<Task>,
+    remote_time: &str,
+) -> Option<(Task, Task)> {
+    match (local, remote) {
+        (Some(local), Some(remote)) => Some((local, remote)),
+        (Some(local), None) => {
+            let remote = conflicts::tombstone(&local, remote_time);
+            Some((local, remote))
+        }
+        (None, Some(remote)) => Some((conflicts::tombstone(&remote, local_time), remote)),
+        (None, None) => None,
+    }
+}
+
+// 写入远端变更（不记录本地变更，避免回传）：清单与任务分别批量写入
+fn apply_remote_changes(store: &dyn TaskRepository, changes: Vec<SyncChange>) -> Result<(), String> {
+    let mut list_upserts = Vec::new();
+    let mut list_deletes = Vec::new();
+    let mut upserts = Vec::new();
+    let mut deletes = Vec::new();
+    for change in changes {
+        match (change.entity.as_str(), change.op.as_str()) {
+            ("list", "delete") => list_deletes.push(change.id),
+            ("list", _) => list_upserts.extend(change.list),
+            ("task", "delete") => deletes.push(change.id),
+            ("task", _) => upserts.extend(change.data),
+            _ => {}
+        }
+    }
+    store.apply_list_changes(&list_upserts, &list_deletes, false)?;
+    store.apply_synced_changes(&upserts, &deletes, false)
+}
+
+// 解析 RFC 3339 时间为毫秒时间戳（无法解析时视为最早）
+fn timestamp(value: &str) -> i64 {
+    chrono::DateTime::parse_from_rfc3339(value)
+        .map(|d| d.timestamp_millis())
+        .unwrap_or(0)
+}
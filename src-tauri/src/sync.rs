@@ -0,0 +1,173 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::{Emitter, Manager};
+
+use my_todo_core::crypto;
+use my_todo_core::merge::{merge, SyncBase, SyncDocument};
+use crate::backup::{self, BackupTargetKind, WebDavSettings, WebDavTarget};
+use crate::conflicts;
+use crate::database::{self, Task};
+
+// WebDAV 上的同步文件名（位于备份目录下）
+const SYNC_FILE: &str = "my-todo-sync.json";
+// 本地保存的上次同步快照（三方合并的基准）
+const SYNC_BASE_FILE: &str = "sync_base.json";
+// 远端被其他设备同时修改时的重试次数
+const MAX_ATTEMPTS: usize = 3;
+
+// 同步结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncSummary {
+    // 从远端写入本地的任务数
+    pub pulled: usize,
+    // 本地删除的任务数
+    pub deleted_local: usize,
+    // 推送到远端的任务变更数（含删除）
+    pub pushed: usize,
+}
+
+// 与 WebDAV 双向同步任务（按记录、按字段合并）
+#[tauri::command]
+pub async fn sync_webdav(app_handle: tauri::AppHandle) -> Result<SyncSummary, String> {
+    let settings = backup::load_webdav_settings(app_handle.clone())
+        .await?
+        .ok_or_else(|| "WebDAV settings not configured".to_string())?;
+    if !settings.enabled {
+        return Err("Backup is disabled".to_string());
+    }
+    if settings.target != BackupTargetKind::Webdav {
+        return Err("Sync requires a WebDAV target".to_string());
+    }
+
+    let target = WebDavTarget::new(&settings)?;
+    backup::BackupTarget::prepare(&target).await?;
+
+    for attempt in 1..=MAX_ATTEMPTS {
+        let remote = target.download_versioned(SYNC_FILE).await?;
+        let remote_exists = remote.is_some();
+        let (remote_doc, etag) = match remote {
+            Some((bytes, etag)) => (decode_document(&bytes, &settings)?, etag),
+            None => (SyncDocument::default(), None),
+        };
+
+        // 远端同步文件不存在（首次同步或被清空）时不以旧快照推断删除，避免误删本地任务
+        let base = if remote_exists {
+            load_sync_base(&app_handle)?
+        } else {
+            SyncBase::default()
+        };
+        let local = load_local_tasks(&app_handle)?;
+        let outcome = merge(&base, &local, remote_doc, chrono::Utc::now());
+
+        // 先上传合并结果，成功后再写入本地，避免与其他设备并发时丢失变更
+        let bytes = encode_document(&outcome.document, &settings)?;
+        if !target.upload_if_unchanged(SYNC_FILE, bytes, etag.as_deref()).await? {
+            eprintln!("[Sync] Remote changed during sync, retrying ({}/{})", attempt, MAX_ATTEMPTS);
+            continue;
+        }
+
+        apply_local_changes(&app_handle, &outcome.local_upserts, &outcome.local_deletes)?;
+        conflicts::record_conflicts(&app_handle, &outcome.conflicts)?;
+        save_sync_base(
+            &app_handle,
+            &SyncBase {
+                tasks: outcome.document.tasks.clone(),
+            },
+        )?;
+
+        let summary = SyncSummary {
+            pulled: outcome.local_upserts.len(),
+            deleted_local: outcome.local_deletes.len(),
+            pushed: outcome.pushed,
+        };
+        if summary.pulled > 0 || summary.deleted_local > 0 {
+            let _ = app_handle.emit("tasks-changed", ());
+        }
+        eprintln!("[Sync] Done: {:?}", summary);
+        return Ok(summary);
+    }
+
+    Err("Sync failed: remote data kept changing, please try again later".to_string())
+}
+
+// 启动定期同步任务（每 5 分钟，窗口隐藏到托盘时同样运行）
+pub fn start_sync_scheduler(app_handle: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(5 * 60));
+        loop {
+            interval.tick().await;
+
+            let enabled = matches!(
+                backup::load_webdav_settings(app_handle.clone()).await,
+                Ok(Some(WebDavSettings { enabled: true, sync_enabled: true, .. }))
+            );
+            if !enabled {
+                continue;
+            }
+            if let Err(e) = sync_webdav(app_handle.clone()).await {
+                eprintln!("[Sync] Auto sync failed: {}", e);
+            }
+        }
+    });
+}
+
+fn decode_document(bytes: &[u8], settings: &WebDavSettings) -> Result<SyncDocument, String> {
+    let data = if crypto::is_encrypted(bytes) {
+        let passphrase = settings
+            .passphrase()
+            .ok_or_else(|| "Sync data is encrypted, please set the encryption passphrase".to_string())?;
+        crypto::decrypt_backup(bytes, passphrase)?
+    } else {
+        bytes.to_vec()
+    };
+    serde_json::from_slice(&data).map_err(|e| format!("Failed to parse sync data: {}", e))
+}
+
+fn encode_document(document: &SyncDocument, settings: &WebDavSettings) -> Result<Vec<u8>, String> {
+    let json = serde_json::to_vec(document).map_err(|e| format!("Failed to serialize sync data: {}", e))?;
+    match settings.passphrase() {
+        Some(passphrase) => crypto::encrypt_backup(&json, passphrase),
+        None => Ok(json),
+    }
+}
+
+fn get_sync_base_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create app data dir: {}", e))?;
+    Ok(dir.join(SYNC_BASE_FILE))
+}
+
+fn load_sync_base(app_handle: &tauri::AppHandle) -> Result<SyncBase, String> {
+    let path = get_sync_base_path(app_handle)?;
+    if !path.exists() {
+        return Ok(SyncBase::default());
+    }
+    let data = fs::read(&path).map_err(|e| format!("Failed to read sync base: {}", e))?;
+    serde_json::from_slice(&data).map_err(|e| format!("Failed to parse sync base: {}", e))
+}
+
+fn save_sync_base(app_handle: &tauri::AppHandle, base: &SyncBase) -> Result<(), String> {
+    let path = get_sync_base_path(app_handle)?;
+    let json = serde_json::to_vec(base).map_err(|e| format!("Failed to serialize sync base: {}", e))?;
+    fs::write(path, json).map_err(|e| format!("Failed to write sync base: {}", e))
+}
+
+fn load_local_tasks(app_handle: &tauri::AppHandle) -> Result<BTreeMap<String, Task>, String> {
+    let tasks = database::store(app_handle)?.tasks()?;
+    Ok(tasks.into_iter().map(|t| (t.id.clone(), t)).collect())
+}
+
+fn apply_local_changes(app_handle: &tauri::AppHandle, upserts: &[Task], deletes: &[String]) -> Result<(), String> {
+    if upserts.is_empty() && deletes.is_empty() {
+        return Ok(());
+    }
+
+    database::store(app_handle)?.apply_synced_changes(upserts, deletes, true)
+}
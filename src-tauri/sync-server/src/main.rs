@@ -0,0 +1,787 @@
+// 自托管同步服务：实现 webapi.ts 使用的 REST 接口（任务、清单、增量同步、批量更新、用户配置）
+//
+// 运行：cargo run -p my-todo-sync-server -- --db ./todo-server.db --token <令牌>
+// 也可通过环境变量 MY_TODO_SERVER_DB / MY_TODO_SERVER_BIND / MY_TODO_SERVER_TOKEN 配置；
+// 未指定令牌时启动时随机生成并打印。
+
+use axum::extract::{Path, Query, Request, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use clap::Parser;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Deserialize;
+use std::sync::{Arc, Mutex};
+
+use my_todo_core::crypto::constant_time_eq;
+use my_todo_core::sync::{DownloadResponse, SyncChange, UploadRequest, UploadResponse};
+use my_todo_core::{Task, TaskBatch, TaskList};
+
+const DEFAULT_BIND: &str = "127.0.0.1:8787";
+const DEFAULT_DB: &str = "todo-server.db";
+// 通过 REST 接口直接修改数据时使用的设备 ID（未携带 X-Device-Id 时）
+const API_DEVICE_ID: &str = "api";
+
+#[derive(Clone)]
+struct AppState {
+    store: Arc<SyncStore>,
+    token: Arc<String>,
+}
+
+// 接口错误（响应体为 `{ "message": ... }`，与 webapi.ts 的错误读取方式一致）
+#[derive(Debug)]
+struct ApiError(StatusCode, String);
+
+impl ApiError {
+    fn not_found(what: &str, id: &str) -> Self {
+        Self(StatusCode::NOT_FOUND, format!("{} not found: {}", what, id))
+    }
+
+    fn bad_request(message: String) -> Self {
+        Self(StatusCode::BAD_REQUEST, message)
+    }
+
+    fn internal(message: String) -> Self {
+        Self(StatusCode::INTERNAL_SERVER_ERROR, message)
+    }
+}
+
+impl From<rusqlite::Error> for ApiError {
+    fn from(e: rusqlite::Error) -> Self {
+        Self::internal(format!("Database error: {}", e))
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(serde_json::json!({ "message": self.1 }))).into_response()
+    }
+}
+
+type ApiResult<T> = Result<Json<T>, ApiError>;
+
+// ========== 存储 ==========
+
+// 服务端存储：任务与清单以 JSON 保存，changes 表为每个实体保留最后一次变更、序号与来源设备（按设备过滤下载）
+struct SyncStore {
+    conn: Mutex<Connection>,
+}
+
+impl SyncStore {
+    fn open(path: &str) -> Result<Self, String> {
+        let conn = Connection::open(path).map_err(|e| format!("Failed to open database: {}", e))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS tasks (
+                id TEXT PRIMARY KEY,
+                data TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS lists (
+                id TEXT PRIMARY KEY,
+                data TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS changes (
+                seq INTEGER PRIMARY KEY AUTOINCREMENT,
+                entity TEXT NOT NULL,
+                entity_id TEXT NOT NULL,
+                op TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                device_id TEXT NOT NULL
+            );
+            CREATE UNIQUE INDEX IF NOT EXISTS idx_changes_entity ON changes(entity, entity_id);
+            CREATE TABLE IF NOT EXISTS user_config (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                data TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );",
+        )
+        .map_err(|e| format!("Failed to initialize database: {}", e))?;
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, Connection>, ApiError> {
+        self.conn
+            .lock()
+            .map_err(|_| ApiError::internal("Database lock poisoned".to_string()))
+    }
+}
+
+fn get_tasks(conn: &Connection) -> Result<Vec<Task>, ApiError> {
+    let mut stmt = conn.prepare("SELECT data FROM tasks")?;
+    let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+    let mut tasks = Vec::new();
+    for data in rows {
+        tasks.push(parse_json(&data?)?);
+    }
+    // 与桌面端一致：按 order 升序、创建时间倒序
+    tasks.sort_by(|a: &Task, b: &Task| a.order.cmp(&b.order).then_with(|| b.created_at.cmp(&a.created_at)));
+    Ok(tasks)
+}
+
+fn get_task(conn: &Connection, id: &str) -> Result<Option<Task>, ApiError> {
+    let data: Option<String> = conn
+        .query_row("SELECT data FROM tasks WHERE id = ?1", params![id], |row| row.get(0))
+        .optional()?;
+    data.map(|d| parse_json(&d)).transpose()
+}
+
+fn save_task(conn: &Connection, task: &Task, device_id: &str) -> Result<(), ApiError> {
+    conn.execute(
+        "INSERT OR REPLACE INTO tasks (id, data, updated_at) VALUES (?1, ?2, ?3)",
+        params![task.id, to_json(task)?, task.updated_at],
+    )?;
+    record_change(conn, "task", &task.id, "upsert", &task.updated_at, device_id)
+}
+
+fn delete_task(conn: &Connection, id: &str, deleted_at: &str, device_id: &str) -> Result<(), ApiError> {
+    conn.execute("DELETE FROM tasks WHERE id = ?1", params![id])?;
+    record_change(conn, "task", id, "delete", deleted_at, device_id)
+}
+
+fn get_lists(conn: &Connection) -> Result<Vec<TaskList>, ApiError> {
+    let mut stmt = conn.prepare("SELECT data FROM lists")?;
+    let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+    let mut lists = Vec::new();
+    for data in rows {
+        lists.push(parse_json(&data?)?);
+    }
+    lists.sort_by_key(|l: &TaskList| l.order);
+    Ok(lists)
+}
+
+fn get_list(conn: &Connection, id: &str) -> Result<Option<TaskList>, ApiError> {
+    let data: Option<String> = conn
+        .query_row("SELECT data FROM lists WHERE id = ?1", params![id], |row| row.get(0))
+        .optional()?;
+    data.map(|d| parse_json(&d)).transpose()
+}
+
+fn save_list(conn: &Connection, list: &TaskList, changed_at: &str, device_id: &str) -> Result<(), ApiError> {
+    conn.execute(
+        "INSERT OR REPLACE INTO lists (id, data) VALUES (?1, ?2)",
+        params![list.id, to_json(list)?],
+    )?;
+    record_change(conn, "list", &list.id, "upsert", changed_at, device_id)
+}
+
+fn delete_list(conn: &Connection, id: &str, deleted_at: &str, device_id: &str) -> Result<bool, ApiError> {
+    let deleted = conn.execute("DELETE FROM lists WHERE id = ?1", params![id])?;
+    if deleted > 0 {
+        record_change(conn, "list", id, "delete", deleted_at, device_id)?;
+    }
+    Ok(deleted > 0)
+}
+
+// 记录变更：每个实体只保留一行，重新插入以获得新的序号
+fn record_change(
+    conn: &Connection,
+    entity: &str,
+    entity_id: &str,
+    op: &str,
+    updated_at: &str,
+    device_id: &str,
+) -> Result<(), ApiError> {
+    conn.execute(
+        "DELETE FROM changes WHERE entity = ?1 AND entity_id = ?2",
+        params![entity, entity_id],
+    )?;
+    conn.execute(
+        "INSERT INTO changes (entity, entity_id, op, updated_at, device_id) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![entity, entity_id, op, updated_at, device_id],
+    )?;
+    Ok(())
+}
+
+// 实体当前在服务端的状态（用于下载与冲突响应）
+fn current_change(conn: &Connection, entity: &str, id: &str, op: &str, updated_at: String) -> Result<SyncChange, ApiError> {
+    let (data, list) = match (entity, op) {
+        ("task", "upsert") => (get_task(conn, id)?, None),
+        ("list", "upsert") => (None, get_list(conn, id)?),
+        _ => (None, None),
+    };
+    Ok(SyncChange {
+        entity: entity.to_string(),
+        id: id.to_string(),
+        op: if data.is_some() || list.is_some() { "upsert" } else { "delete" }.to_string(),
+        data,
+        list,
+        updated_at,
+    })
+}
+
+// ========== 任务接口 ==========
+
+#[derive(Deserialize)]
+struct Pagination {
+    page: Option<usize>,
+    limit: Option<usize>,
+}
+
+async fn list_tasks(State(state): State<AppState>, Query(page): Query<Pagination>) -> ApiResult<Vec<Task>> {
+    let conn = state.store.lock()?;
+    let tasks = get_tasks(&conn)?;
+    let tasks = match (page.page, page.limit) {
+        (Some(page), Some(limit)) if page > 0 && limit > 0 => {
+            tasks.into_iter().skip((page - 1) * limit).take(limit).collect()
+        }
+        _ => tasks,
+    };
+    Ok(Json(tasks))
+}
+
+async fn show_task(State(state): State<AppState>, Path(id): Path<String>) -> ApiResult<Task> {
+    let conn = state.store.lock()?;
+    get_task(&conn, &id)?
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found("Task", &id))
+}
+
+async fn create_task(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(body): Json<serde_json::Value>,
+) -> Result<(StatusCode, Json<Task>), ApiError> {
+    let task = my_todo_core::task_from_partial(body).map_err(ApiError::bad_request)?;
+
+    let conn = state.store.lock()?;
+    save_task(&conn, &task, device_id(&headers))?;
+    Ok((StatusCode::CREATED, Json(task)))
+}
+
+async fn update_task(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(changes): Json<serde_json::Value>,
+) -> ApiResult<Task> {
+    let conn = state.store.lock()?;
+    let task = apply_task_changes(&conn, &id, changes, device_id(&headers))?;
+    Ok(Json(task))
+}
+
+async fn remove_task(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> ApiResult<serde_json::Value> {
+    let conn = state.store.lock()?;
+    if get_task(&conn, &id)?.is_none() {
+        return Err(ApiError::not_found("Task", &id));
+    }
+    delete_task(&conn, &id, &chrono::Utc::now().to_rfc3339(), device_id(&headers))?;
+    Ok(Json(serde_json::json!({ "deleted": id })))
+}
+
+// 批量修改（请求体同本地 API），在一个事务中执行，任一项失败则全部回滚
+async fn batch_update_tasks(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(batch): Json<TaskBatch>,
+) -> ApiResult<Vec<Task>> {
+    let mut conn = state.store.lock()?;
+    let tx = conn.transaction()?;
+    let (upserts, deletes) = my_todo_core::batch_changes(&get_tasks(&tx)?, &batch).map_err(ApiError::bad_request)?;
+    let now = chrono::Utc::now().to_rfc3339();
+    for task in &upserts {
+        save_task(&tx, task, device_id(&headers))?;
+    }
+    for id in &deletes {
+        delete_task(&tx, id, &now, device_id(&headers))?;
+    }
+    tx.commit()?;
+    Ok(Json(upserts))
+}
+
+fn apply_task_changes(
+    conn: &Connection,
+    id: &str,
+    changes: serde_json::Value,
+    device_id: &str,
+) -> Result<Task, ApiError> {
+    let existing = get_task(conn, id)?.ok_or_else(|| ApiError::not_found("Task", id))?;
+    let task = my_todo_core::update_task_from_partial(&existing, changes).map_err(ApiError::bad_request)?;
+    save_task(conn, &task, device_id)?;
+    Ok(task)
+}
+
+// ========== 清单接口 ==========
+
+async fn list_lists(State(state): State<AppState>) -> ApiResult<Vec<TaskList>> {
+    let conn = state.store.lock()?;
+    Ok(Json(get_lists(&conn)?))
+}
+
+async fn show_list(State(state): State<AppState>, Path(id): Path<String>) -> ApiResult<TaskList> {
+    let conn = state.store.lock()?;
+    get_list(&conn, &id)?
+        .map(Json)
+        .ok_or_else(|| ApiError::not_found("List", &id))
+}
+
+async fn create_list(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(body): Json<serde_json::Value>,
+) -> Result<(StatusCode, Json<TaskList>), ApiError> {
+    let list = my_todo_core::list_from_partial(body).map_err(ApiError::bad_request)?;
+
+    let conn = state.store.lock()?;
+    save_list(&conn, &list, &chrono::Utc::now().to_rfc3339(), device_id(&headers))?;
+    Ok((StatusCode::CREATED, Json(list)))
+}
+
+async fn update_list(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(changes): Json<serde_json::Value>,
+) -> ApiResult<TaskList> {
+    let conn = state.store.lock()?;
+    let existing = get_list(&conn, &id)?.ok_or_else(|| ApiError::not_found("List", &id))?;
+    let list = my_todo_core::update_list_from_partial(&existing, changes).map_err(ApiError::bad_request)?;
+    save_list(&conn, &list, &chrono::Utc::now().to_rfc3339(), device_id(&headers))?;
+    Ok(Json(list))
+}
+
+async fn remove_list(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> ApiResult<serde_json::Value> {
+    let conn = state.store.lock()?;
+    if !delete_list(&conn, &id, &chrono::Utc::now().to_rfc3339(), device_id(&headers))? {
+        return Err(ApiError::not_found("List", &id));
+    }
+    Ok(Json(serde_json::json!({ "deleted": id })))
+}
+
+// ========== 同步接口 ==========
+
+// 接收客户端变更；服务端版本比客户端更新（且来自其他设备）时拒绝并返回服务端版本
+async fn sync_upload(State(state): State<AppState>, Json(body): Json<UploadRequest>) -> ApiResult<UploadResponse> {
+    let mut conn = state.store.lock()?;
+    let tx = conn.transaction()?;
+    let mut response = UploadResponse::default();
+
+    for change in body.changes {
+        if change.entity != "task" && change.entity != "list" {
+            continue;
+        }
+
+        let existing: Option<(String, String, String)> = tx
+            .query_row(
+                "SELECT op, updated_at, device_id FROM changes WHERE entity = ?1 AND entity_id = ?2",
+                params![change.entity, change.id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .optional()?;
+        if let Some((op, updated_at, device)) = existing {
+            if device != body.device_id && timestamp(&updated_at) > timestamp(&change.updated_at) {
+                response
+                    .conflicts
+                    .push(current_change(&tx, &change.entity, &change.id, &op, updated_at)?);
+                continue;
+            }
+        }
+
+        if change.entity == "list" {
+            apply_list_change(&tx, &change, &body.device_id)?;
+            response.accepted.push(change.id);
+            continue;
+        }
+
+        match (change.op.as_str(), change.data) {
+            ("delete", _) => delete_task(&tx, &change.id, &change.updated_at, &body.device_id)?,
+            (_, Some(task)) => {
+                if task.id != change.id {
+                    return Err(ApiError::bad_request(format!("Task id mismatch: {}", change.id)));
+                }
+                save_task(&tx, &task, &body.device_id)?
+            }
+            (_, None) => return Err(ApiError::bad_request(format!("Missing data for task: {}", change.id))),
+        }
+        response.accepted.push(change.id);
+    }
+
+    tx.commit()?;
+    Ok(Json(response))
+}
+
+// 写入上传的清单变更；内容与服务端一致时不记录新变更（客户端写入拉取的清单后会再次推送）
+fn apply_list_change(conn: &Connection, change: &SyncChange, device_id: &str) -> Result<(), ApiError> {
+    if change.op == "delete" {
+        delete_list(conn, &change.id, &change.updated_at, device_id)?;
+        return Ok(());
+    }
+    let list = change
+        .list
+        .as_ref()
+        .ok_or_else(|| ApiError::bad_request(format!("Missing data for list: {}", change.id)))?;
+    if list.id != change.id {
+        return Err(ApiError::bad_request(format!("List id mismatch: {}", change.id)));
+    }
+    let unchanged = get_list(conn, &list.id)?.is_some_and(|stored| to_json(&stored).ok() == to_json(list).ok());
+    if !unchanged {
+        save_list(conn, list, &change.updated_at, device_id)?;
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DownloadQuery {
+    since: Option<String>,
+    device_id: Option<String>,
+}
+
+// 返回游标之后其他设备产生的变更（游标为变更序号，无法解析时从头开始）
+async fn sync_download(State(state): State<AppState>, Query(query): Query<DownloadQuery>) -> ApiResult<DownloadResponse> {
+    let since = query
+        .since
+        .as_deref()
+        .and_then(|s| s.parse::<i64>().ok())
+        .unwrap_or(0);
+    let device_id = query.device_id.unwrap_or_default();
+
+    let conn = state.store.lock()?;
+    let rows: Vec<(String, String, String, String)> = {
+        let mut stmt = conn.prepare(
+            "SELECT entity, entity_id, op, updated_at FROM changes WHERE seq > ?1 AND device_id != ?2 ORDER BY seq ASC",
+        )?;
+        let rows = stmt.query_map(params![since, device_id], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+        })?;
+        rows.collect::<Result<_, _>>()?
+    };
+
+    let mut changes = Vec::with_capacity(rows.len());
+    for (entity, id, op, updated_at) in rows {
+        changes.push(current_change(&conn, &entity, &id, &op, updated_at)?);
+    }
+
+    let cursor: i64 = conn.query_row("SELECT COALESCE(MAX(seq), 0) FROM changes", [], |row| row.get(0))?;
+    Ok(Json(DownloadResponse {
+        changes,
+        cursor: cursor.max(since).to_string(),
+    }))
+}
+
+// ========== 用户配置 ==========
+
+// 读取用户配置（未保存过时为空对象）
+async fn get_user_config(State(state): State<AppState>) -> ApiResult<serde_json::Value> {
+    let conn = state.store.lock()?;
+    let data: Option<String> = conn
+        .query_row("SELECT data FROM user_config WHERE id = 1", [], |row| row.get(0))
+        .optional()?;
+    match data {
+        Some(data) => parse_json(&data).map(Json),
+        None => Ok(Json(serde_json::json!({}))),
+    }
+}
+
+// 保存用户配置（整体替换，须为 JSON 对象）
+async fn update_user_config(
+    State(state): State<AppState>,
+    Json(config): Json<serde_json::Value>,
+) -> ApiResult<serde_json::Value> {
+    if !config.is_object() {
+        return Err(ApiError::bad_request("User config must be a JSON object".to_string()));
+    }
+    let conn = state.store.lock()?;
+    conn.execute(
+        "INSERT OR REPLACE INTO user_config (id, data, updated_at) VALUES (1, ?1, ?2)",
+        params![to_json(&config)?, chrono::Utc::now().to_rfc3339()],
+    )?;
+    Ok(Json(config))
+}
+
+async fn health() -> Json<serde_json::Value> {
+    Json(serde_json::json!({ "connected": true }))
+}
+
+// ========== 鉴权与启动 ==========
+
+// 校验 `Authorization: Bearer <token>`
+async fn require_token(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let authorized = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .is_some_and(|token| constant_time_eq(token.as_bytes(), state.token.as_bytes()));
+    if !authorized {
+        return ApiError(StatusCode::UNAUTHORIZED, "Invalid or missing API token".to_string()).into_response();
+    }
+    next.run(request).await
+}
+
+fn router(state: AppState) -> Router {
+    let api = Router::new()
+        .route("/api/tasks", get(list_tasks).post(create_task))
+        .route("/api/tasks/batch", axum::routing::post(batch_update_tasks))
+        .route("/api/tasks/{id}", get(show_task).put(update_task).delete(remove_task))
+        .route("/api/lists", get(list_lists).post(create_list))
+        .route("/api/lists/{id}", get(show_list).put(update_list).delete(remove_list))
+        .route("/api/sync/upload", axum::routing::post(sync_upload))
+        .route("/api/sync/download", get(sync_download))
+        .route("/api/user/config", get(get_user_config).put(update_user_config))
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_token));
+
+    Router::new()
+        .route("/api/health", get(health))
+        .merge(api)
+        .with_state(state)
+}
+
+// 命令行参数（未指定时读取环境变量）
+#[derive(Parser)]
+#[command(name = "sync-server", version, about = "Self-hostable REST sync server for My Todo")]
+struct Config {
+    /// 数据库文件
+    #[arg(long, env = "MY_TODO_SERVER_DB", default_value = DEFAULT_DB)]
+    db: String,
+    /// 监听地址
+    #[arg(long, env = "MY_TODO_SERVER_BIND", default_value = DEFAULT_BIND)]
+    bind: String,
+    /// API 令牌（未指定时随机生成并打印）
+    #[arg(long, env = "MY_TODO_SERVER_TOKEN")]
+    token: Option<String>,
+}
+
+#[tokio::main]
+async fn main() {
+    if let Err(e) = run().await {
+        eprintln!("[SyncServer] {}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn run() -> Result<(), String> {
+    let config = Config::parse();
+    let token = match config.token.filter(|t| !t.is_empty()) {
+        Some(token) => token,
+        None => {
+            let token = uuid::Uuid::new_v4().simple().to_string();
+            println!("[SyncServer] Generated API token: {}", token);
+            token
+        }
+    };
+
+    let state = AppState {
+        store: Arc::new(SyncStore::open(&config.db)?),
+        token: Arc::new(token),
+    };
+
+    let listener = tokio::net::TcpListener::bind(&config.bind)
+        .await
+        .map_err(|e| format!("Failed to bind {}: {}", config.bind, e))?;
+    println!("[SyncServer] Listening on http://{} (db: {})", config.bind, config.db);
+
+    axum::serve(listener, router(state))
+        .await
+        .map_err(|e| format!("Server error: {}", e))
+}
+
+// ========== 工具函数 ==========
+
+fn device_id(headers: &HeaderMap) -> &str {
+    headers
+        .get("x-device-id")
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.is_empty())
+        .unwrap_or(API_DEVICE_ID)
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> Result<String, ApiError> {
+    serde_json::to_string(value).map_err(|e| ApiError::internal(format!("Failed to serialize: {}", e)))
+}
+
+fn parse_json<T: serde::de::DeserializeOwned>(data: &str) -> Result<T, ApiError> {
+    serde_json::from_str(data).map_err(|e| ApiError::internal(format!("Corrupted record: {}", e)))
+}
+
+// 解析 RFC 3339 时间为毫秒时间戳（无法解析时视为最早）
+fn timestamp(value: &str) -> i64 {
+    chrono::DateTime::parse_from_rfc3339(value)
+        .map(|d| d.timestamp_millis())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state() -> AppState {
+        AppState {
+            store: Arc::new(SyncStore::open(":memory:").unwrap()),
+            token: Arc::new("token".to_string()),
+        }
+    }
+
+    fn list(id: &str, name: &str) -> TaskList {
+        TaskList {
+            id: id.to_string(),
+            name: name.to_string(),
+            icon: None,
+            color: None,
+            order: 0,
+            created_at: "2026-01-01T00:00:00+00:00".to_string(),
+        }
+    }
+
+    fn list_change(list: TaskList, updated_at: &str) -> SyncChange {
+        SyncChange {
+            entity: "list".to_string(),
+            id: list.id.clone(),
+            op: "upsert".to_string(),
+            data: None,
+            list: Some(list),
+            updated_at: updated_at.to_string(),
+        }
+    }
+
+    async fn upload(state: &AppState, device_id: &str, changes: Vec<SyncChange>) -> UploadResponse {
+        let body = UploadRequest { device_id: device_id.to_string(), changes };
+        sync_upload(State(state.clone()), Json(body)).await.unwrap().0
+    }
+
+    async fn download(state: &AppState, device_id: &str, since: &str) -> DownloadResponse {
+        let query = DownloadQuery { since: Some(since.to_string()), device_id: Some(device_id.to_string()) };
+        sync_download(State(state.clone()), Query(query)).await.unwrap().0
+    }
+
+    #[tokio::test]
+    async fn syncs_lists_between_devices() {
+        let state = state();
+        let response = upload(&state, "a", vec![list_change(list("work", "工作"), "2026-01-02T00:00:00+00:00")]).await;
+        assert_eq!(response.accepted, vec!["work".to_string()]);
+
+        let pulled = download(&state, "b", "0").await;
+        assert_eq!(pulled.changes.len(), 1);
+        assert_eq!(pulled.changes[0].entity, "list");
+        assert_eq!(pulled.changes[0].list.as_ref().unwrap().name, "工作");
+        // 上传设备自身的变更不会被下载
+        assert!(download(&state, "a", "0").await.changes.is_empty());
+
+        let mut delete = list_change(list("work", "工作"), "2026-01-03T00:00:00+00:00");
+        delete.op = "delete".to_string();
+        delete.list = None;
+        upload(&state, "b", vec![delete]).await;
+
+        let pulled = download(&state, "a", &pulled.cursor).await;
+        assert_eq!(pulled.changes.len(), 1);
+        assert_eq!(pulled.changes[0].op, "delete");
+        assert!(pulled.changes[0].list.is_none());
+        assert!(get_list(&state.store.lock().unwrap(), "work").unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn unchanged_list_echo_creates_no_change() {
+        let state = state();
+        upload(&state, "a", vec![list_change(list("work", "工作"), "2026-01-02T00:00:00+00:00")]).await;
+        let cursor = download(&state, "b", "0").await.cursor;
+
+        // 设备 b 写入拉取的清单后再次推送相同内容
+        let response = upload(&state, "b", vec![list_change(list("work", "工作"), "2026-01-02T00:01:00+00:00")]).await;
+        assert_eq!(response.accepted, vec!["work".to_string()]);
+        assert!(download(&state, "a", "0").await.changes.is_empty());
+        assert_eq!(download(&state, "b", "0").await.cursor, cursor);
+    }
+
+    #[tokio::test]
+    async fn rejects_older_list_change_from_other_device() {
+        let state = state();
+        upload(&state, "a", vec![list_change(list("work", "新名称"), "2026-01-03T00:00:00+00:00")]).await;
+
+        let response = upload(&state, "b", vec![list_change(list("work", "旧名称"), "2026-01-02T00:00:00+00:00")]).await;
+        assert!(response.accepted.is_empty());
+        assert_eq!(response.conflicts.len(), 1);
+        assert_eq!(response.conflicts[0].list.as_ref().unwrap().name, "新名称");
+    }
+
+    fn task(id: &str, extra: serde_json::Value) -> Task {
+        let mut partial = serde_json::json!({ "id": id, "title": id });
+        partial.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+        my_todo_core::task_from_partial(partial).unwrap()
+    }
+
+    async fn batch(state: &AppState, body: serde_json::Value) -> Result<Vec<Task>, ApiError> {
+        let batch: TaskBatch = serde_json::from_value(body).unwrap();
+        batch_update_tasks(State(state.clone()), HeaderMap::new(), Json(batch)).await.map(|r| r.0)
+    }
+
+    #[tokio::test]
+    async fn batch_creates_orders_and_deletes_like_the_local_api() {
+        let state = state();
+        {
+            let conn = state.store.lock().unwrap();
+            for task in [task("a", serde_json::json!({})), task("b", serde_json::json!({})), task("c", serde_json::json!({ "parentId": "b" }))] {
+                save_task(&conn, &task, "device").unwrap();
+            }
+        }
+
+        let tasks = batch(&state, serde_json::json!({
+            "updates": [{ "id": "a", "changes": { "title": "renamed" } }, { "id": "new", "changes": { "title": "created" } }],
+            "order": ["new", "a"],
+            "deletes": ["b"],
+        }))
+        .await
+        .unwrap();
+        assert_eq!(tasks.len(), 2);
+
+        let conn = state.store.lock().unwrap();
+        let a = get_task(&conn, "a").unwrap().unwrap();
+        assert_eq!((a.title.as_str(), a.order), ("renamed", 1));
+        assert_eq!(get_task(&conn, "new").unwrap().unwrap().order, 0);
+        // 删除连同后代，并为每个任务记录删除变更
+        assert!(get_task(&conn, "b").unwrap().is_none());
+        assert!(get_task(&conn, "c").unwrap().is_none());
+        let ops: Vec<String> = conn
+            .prepare("SELECT op FROM changes WHERE entity_id IN ('b', 'c')")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(ops, ["delete", "delete"]);
+    }
+
+    #[tokio::test]
+    async fn batch_rolls_back_when_an_item_fails() {
+        let state = state();
+        let err = batch(&state, serde_json::json!({
+            "updates": [{ "id": "a", "changes": { "title": "ok" } }, { "id": "b", "changes": { "title": "x", "parentId": "missing" } }],
+        }))
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::BAD_REQUEST);
+        assert!(get_task(&state.store.lock().unwrap(), "a").unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn stores_user_config() {
+        let state = state();
+        assert_eq!(get_user_config(State(state.clone())).await.unwrap().0, serde_json::json!({}));
+
+        let config = serde_json::json!({ "theme": "dark", "language": "zh" });
+        let saved = update_user_config(State(state.clone()), Json(config.clone())).await.unwrap();
+        assert_eq!(saved.0, config);
+        assert_eq!(get_user_config(State(state.clone())).await.unwrap().0, config);
+
+        let err = update_user_config(State(state.clone()), Json(serde_json::json!([1]))).await.unwrap_err();
+        assert_eq!(err.0, StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn parses_arguments() {
+        let config = Config::try_parse_from(["sync-server", "--db", "data.db", "--bind", "0.0.0.0:9000", "--token", "secret"]).unwrap();
+        assert_eq!((config.db.as_str(), config.bind.as_str(), config.token.as_deref()), ("data.db", "0.0.0.0:9000", Some("secret")));
+        assert!(Config::try_parse_from(["sync-server", "--unknown"]).is_err());
+    }
+}
@@ -1,4 +1,5 @@
-import { useState } from "react";
+import { useEffect, useState } from "react";
+import { listen } from "@tauri-apps/api/event";
 import { Plus, Search, Moon, Sun, Menu, X } from "lucide-react";
 import { useTodoStore } from "./store/todoStore";
 import { Task, MainView } from "./types";
@@ -14,6 +15,7 @@ import { TopTabBar } from "./components/TopTabBar";
 import { SortableTaskItem } from "./components/SortableTaskItem";
 import { AddTaskDialog } from "./components/AddTaskDialog";
 import { cn } from "./lib/utils";
+import { isTauri } from "./lib/api";
 import "./index.css";
 import {
   DndContext,
@@ -53,9 +55,20 @@ function App() {
   const [activeId, setActiveId] = useState<string | null>(null);
   const [isSidebarOpen, setIsSidebarOpen] = useState(false);
 
-  const { getFilteredTasks, searchQuery, setSearchQuery, mainView, reorderTasks, isTrashView, setMainView } = useTodoStore();
+  const { getFilteredTasks, searchQuery, setSearchQuery, mainView, applyTaskBatch, isTrashView, setMainView, loadTasks } = useTodoStore();
   const filteredTasks = getFilteredTasks();
 
+  // 后端修改任务后（撤销、重做、同步、本地 API）重新加载
+  useEffect(() => {
+    if (!isTauri()) return;
+    const unlisten = listen("tasks-changed", () => {
+      loadTasks();
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [loadTasks]);
+
   const greeting = getGreeting();
 
   const handleTaskClick = (task: Task) => {
@@ -91,11 +104,8 @@ function App() {
       const [removed] = newTasks.splice(oldIndex, 1);
       newTasks.splice(newIndex, 0, removed);
 
-      newTasks.forEach((task, index) => {
-        task.order = index;
-      });
-
-      reorderTasks(newTasks);
+      // 新顺序通过一次批量修改保存（单条撤销记录）
+      applyTaskBatch({ order: newTasks.map((task) => task.id) });
     }
 
     setActiveId(null);
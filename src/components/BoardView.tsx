@@ -36,7 +36,7 @@ const COLUMNS = [
 ] as const;
 
 export function BoardView({ onTaskClick }: BoardViewProps) {
-  const { getFilteredTasks, updateTask } = useTodoStore();
+  const { getFilteredTasks, applyTaskBatch } = useTodoStore();
   const tasks = getFilteredTasks();
   const [activeId, setActiveId] = useState<string | null>(null);
 
@@ -84,11 +84,22 @@ export function BoardView({ onTaskClick }: BoardViewProps) {
       }
     }
 
-    // 如果找到了目标状态，并且与当前状态不同，则更新
-    if (targetStatus) {
-      const currentTask = tasks.find(t => t.id === taskId);
-      if (currentTask && currentTask.status !== targetStatus) {
-        updateTask(taskId, { status: targetStatus });
+    // 状态变更与目标列的新顺序通过一次批量修改保存（单个事务、单条撤销记录）
+    const currentTask = tasks.find(t => t.id === taskId);
+    if (targetStatus && currentTask) {
+      // 拖到任务上时插入到该任务的位置，拖到列的空白处时放到末尾
+      const columnTasks = getTasksByStatus(targetStatus).filter(t => t.id !== taskId);
+      const overIndex = columnTasks.findIndex(t => t.id === over.id);
+      columnTasks.splice(overIndex >= 0 ? overIndex : columnTasks.length, 0, currentTask);
+
+      const order = columnTasks.map(t => t.id);
+      const statusChanged = currentTask.status !== targetStatus;
+      const orderChanged = order.join() !== getTasksByStatus(targetStatus).map(t => t.id).join();
+      if (statusChanged || orderChanged) {
+        applyTaskBatch({
+          updates: statusChanged ? [{ id: taskId, changes: { status: targetStatus } }] : [],
+          order,
+        });
       }
     }
 
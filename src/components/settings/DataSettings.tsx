@@ -18,7 +18,7 @@ import { Button } from "../ui/button";
 import { Input } from "../ui/input";
 import { Badge } from "../ui/badge";
 import { cn } from "../../lib/utils";
-import { webdavApi, WebDavSettings, isTauri } from "../../lib/api";
+import { webdavApi, WebDavSettings, BackupTargetKind, S3Settings, isTauri } from "../../lib/api";
 import { webApiService, syncManager } from "../../lib/webapi";
 
 export function DataSettings() {
@@ -221,6 +221,9 @@ function BackupPanel() {
   const [isLoading, setIsLoading] = useState(false);
   const [message, setMessage] = useState<{ type: "success" | "error"; text: string } | null>(null);
 
+  const s3: S3Settings = settings.s3 || { region: "us-east-1", bucket: "", accessKey: "", secretKey: "", pathStyle: true };
+  const setS3 = (changes: Partial<S3Settings>) => setSettings({ ...settings, s3: { ...s3, ...changes } });
+
   useEffect(() => {
     setIsDesktop(isTauri());
     webdavApi.loadSettings().then(s => s && setSettings(s));
@@ -255,8 +258,25 @@ function BackupPanel() {
     }
   };
 
+  const handleSync = async () => {
+    setIsLoading(true);
+    try {
+      const result = await webdavApi.sync();
+      const text = result ? `同步完成：拉取 ${result.pulled} 条，删除 ${result.deletedLocal} 条，推送 ${result.pushed} 条` : "同步完成";
+      setMessage({ type: "success", text });
+    } catch (e: any) {
+      console.error(e);
+      const errMsg = typeof e === 'string' ? e : (e.message || JSON.stringify(e));
+      setMessage({ type: "error", text: `同步失败: ${errMsg}` });
+    } finally {
+      setIsLoading(false);
+    }
+  };
+
   const handleRestore = async () => {
-    const filename = prompt("输入要恢复的备份文件名：");
+    const backups = await webdavApi.listBackups().catch(() => []);
+    const hint = backups.length > 0 ? `\n\n可用备份：\n${backups.slice(0, 10).map(b => b.name).join("\n")}` : "";
+    const filename = prompt(`输入要恢复的备份文件名：${hint}`, backups[0]?.name || "");
     if (!filename) return;
     setIsLoading(true);
     try {
@@ -281,8 +301,8 @@ function BackupPanel() {
 
       <div className="flex items-center justify-between p-4 bg-muted/30 rounded-lg border">
         <div>
-          <h3 className="font-medium text-sm sm:text-base">启用 WebDAV 服务</h3>
-          <p className="text-xs sm:text-sm text-muted-foreground mt-0.5">坚果云 / Nextcloud / NAS</p>
+          <h3 className="font-medium text-sm sm:text-base">启用备份</h3>
+          <p className="text-xs sm:text-sm text-muted-foreground mt-0.5">坚果云 / Nextcloud / 本地目录 / NAS</p>
         </div>
         <input
           type="checkbox"
@@ -298,6 +318,56 @@ function BackupPanel() {
       {settings.enabled && (
         <div className="space-y-4 animate-in fade-in slide-in-from-top-2 duration-200">
           <div className="grid gap-4">
+             <div>
+               <label className="text-sm font-medium mb-1 block">备份位置</label>
+               <select
+                 value={settings.target || "webdav"}
+                 onChange={e => setSettings({...settings, target: e.target.value as BackupTargetKind})}
+                 className="w-full h-9 rounded-md border bg-background px-3 text-sm"
+               >
+                 <option value="webdav">WebDAV</option>
+                 <option value="local">本地目录 / NAS</option>
+                 <option value="s3">S3 兼容存储 (AWS / MinIO)</option>
+               </select>
+             </div>
+             {settings.target === "local" ? (
+               <div>
+                 <label className="text-sm font-medium mb-1 block">备份目录</label>
+                 <Input value={settings.localPath || ""} onChange={e => setSettings({...settings, localPath: e.target.value})} placeholder="D:\Backups\my-todo 或 /mnt/nas/my-todo" />
+               </div>
+             ) : settings.target === "s3" ? (
+               <div className="grid grid-cols-1 sm:grid-cols-2 gap-4">
+                 <div>
+                   <label className="text-sm font-medium mb-1 block">服务地址</label>
+                   <Input value={s3.endpoint || ""} onChange={e => setS3({ endpoint: e.target.value })} placeholder="http://localhost:9000（留空使用 AWS）" />
+                 </div>
+                 <div>
+                   <label className="text-sm font-medium mb-1 block">区域</label>
+                   <Input value={s3.region} onChange={e => setS3({ region: e.target.value })} placeholder="us-east-1" />
+                 </div>
+                 <div>
+                   <label className="text-sm font-medium mb-1 block">存储桶</label>
+                   <Input value={s3.bucket} onChange={e => setS3({ bucket: e.target.value })} placeholder="my-todo-backups" />
+                 </div>
+                 <div>
+                   <label className="text-sm font-medium mb-1 block">对象前缀</label>
+                   <Input value={settings.basePath} onChange={e => setSettings({...settings, basePath: e.target.value})} placeholder="/my-backups" />
+                 </div>
+                 <div>
+                   <label className="text-sm font-medium mb-1 block">Access Key</label>
+                   <Input value={s3.accessKey} onChange={e => setS3({ accessKey: e.target.value })} />
+                 </div>
+                 <div>
+                   <label className="text-sm font-medium mb-1 block">Secret Key</label>
+                   <Input type="password" value={s3.secretKey} onChange={e => setS3({ secretKey: e.target.value })} />
+                 </div>
+                 <label className="flex items-center gap-2 text-sm">
+                   <input type="checkbox" checked={s3.pathStyle} onChange={e => setS3({ pathStyle: e.target.checked })} />
+                   <span>路径风格访问 (MinIO 通常需要开启)</span>
+                 </label>
+               </div>
+             ) : (
+             <>
              <div className="grid grid-cols-1 sm:grid-cols-2 gap-4">
                <div>
                  <label className="text-sm font-medium mb-1 block">服务地址</label>
@@ -330,6 +400,20 @@ function BackupPanel() {
                  </p>
                </div>
              </div>
+             </>
+             )}
+             <div>
+               <label className="text-sm font-medium mb-1 block">加密口令（可选）</label>
+               <Input
+                 type="password"
+                 value={settings.encryptionPassphrase || ""}
+                 onChange={e => setSettings({...settings, encryptionPassphrase: e.target.value})}
+                 placeholder="留空则不加密"
+               />
+               <p className="text-xs text-muted-foreground mt-1">
+                 设置后备份会在上传前加密，恢复时需使用相同口令。请妥善保管，遗失后无法恢复备份。
+               </p>
+             </div>
              
              <div className="flex flex-col sm:flex-row gap-4 pt-2">
                 <label className="flex items-center gap-2 text-sm p-2 border rounded hover:bg-muted/50 cursor-pointer">
@@ -340,6 +424,12 @@ function BackupPanel() {
                   <input type="checkbox" checked={settings.simpleMode} onChange={e => setSettings({...settings, simpleMode: e.target.checked})} />
                   <span>精简模式 (仅数据库文件)</span>
                 </label>
+                {(settings.target || "webdav") === "webdav" && (
+                  <label className="flex items-center gap-2 text-sm p-2 border rounded hover:bg-muted/50 cursor-pointer">
+                    <input type="checkbox" checked={!!settings.syncEnabled} onChange={e => setSettings({...settings, syncEnabled: e.target.checked})} />
+                    <span>多设备同步 (每5分钟按任务合并)</span>
+                  </label>
+                )}
              </div>
           </div>
 
@@ -352,6 +442,11 @@ function BackupPanel() {
                 <Button variant="outline" onClick={handleRestore} disabled={isLoading || !isDesktop} size="sm" className="flex-1 sm:flex-none">
                 <FolderDown className="w-4 h-4 mr-2" /> 恢复备份
                 </Button>
+                {settings.syncEnabled && (settings.target || "webdav") === "webdav" && (
+                  <Button variant="outline" onClick={handleSync} disabled={isLoading || !isDesktop} size="sm" className="flex-1 sm:flex-none">
+                  立即同步
+                  </Button>
+                )}
             </div>
           </div>
 
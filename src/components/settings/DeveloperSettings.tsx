@@ -1,9 +1,11 @@
-import { useState } from "react";
-import { Book, Copy, CheckCircle2 } from "lucide-react";
+import { useState, useEffect } from "react";
+import { Book, Copy, CheckCircle2, Terminal, RefreshCw, Loader2 } from "lucide-react";
 import { Button } from "../ui/button";
 import { Badge } from "../ui/badge";
+import { Input } from "../ui/input";
+import { localApi, LocalApiInfo, isTauri } from "../../lib/api";
 
-type ApiTab = "docs" | "database";
+type ApiTab = "docs" | "local" | "database";
 
 export function DeveloperSettings() {
   const [activeTab, setActiveTab] = useState<ApiTab>("docs");
@@ -32,6 +34,13 @@ export function DeveloperSettings() {
         >
           API 文档
         </Button>
+        <Button 
+          variant={activeTab === "local" ? "default" : "outline"} 
+          size="sm" 
+          onClick={() => setActiveTab("local")}
+        >
+          本地 API
+        </Button>
         <Button 
           variant={activeTab === "database" ? "default" : "outline"} 
           size="sm" 
@@ -64,6 +73,8 @@ export function DeveloperSettings() {
             </div>
         )}
 
+        {activeTab === "local" && <LocalApiPanel />}
+
         {activeTab === "database" && (
           <div className="space-y-4">
             <div className="p-4 rounded-lg bg-muted/30 border">
@@ -118,6 +129,137 @@ export function DeveloperSettings() {
   );
 }
 
+function LocalApiPanel() {
+  const [info, setInfo] = useState<LocalApiInfo | null>(null);
+  const [port, setPort] = useState("27123");
+  const [isSaving, setIsSaving] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+  const [copied, setCopied] = useState(false);
+
+  useEffect(() => {
+    localApi.getInfo().then((result) => {
+      if (result) {
+        setInfo(result);
+        setPort(String(result.settings.port));
+      }
+    }).catch((e) => setError(String(e)));
+  }, []);
+
+  const apply = async (enabled: boolean) => {
+    setIsSaving(true);
+    setError(null);
+    try {
+      const result = await localApi.saveSettings({ enabled, port: Number(port) || 27123 });
+      if (result) setInfo(result);
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setIsSaving(false);
+    }
+  };
+
+  const regenerate = async () => {
+    if (!confirm("重新生成后旧令牌立即失效，确定继续？")) return;
+    try {
+      const result = await localApi.regenerateToken();
+      if (result) setInfo(result);
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+
+  if (!isTauri()) {
+    return <p className="text-sm text-muted-foreground">本地 API 仅在桌面端可用。</p>;
+  }
+
+  return (
+    <div className="space-y-4">
+      <div className="p-4 rounded-lg bg-muted/30 border space-y-4">
+        <div className="flex items-center justify-between">
+          <div>
+            <h3 className="font-medium flex items-center gap-2">
+              <Terminal className="w-4 h-4" />
+              本地 HTTP API
+            </h3>
+            <p className="text-sm text-muted-foreground mt-1">
+              仅监听 127.0.0.1，供脚本、编辑器插件与自动化工具调用。
+            </p>
+          </div>
+          <input
+            type="checkbox"
+            className="toggle"
+            checked={info?.settings.enabled ?? false}
+            disabled={isSaving}
+            onChange={(e) => apply(e.target.checked)}
+            style={{ width: '2.5rem', height: '1.5rem' }}
+          />
+        </div>
+
+        <div className="grid gap-3">
+          <div>
+            <label className="text-sm font-medium mb-1 block">端口</label>
+            <div className="flex gap-2">
+              <Input value={port} onChange={(e) => setPort(e.target.value)} className="font-mono text-sm w-32" />
+              <Button variant="outline" size="sm" disabled={isSaving} onClick={() => apply(info?.settings.enabled ?? false)}>
+                {isSaving ? <Loader2 className="w-4 h-4 animate-spin" /> : "应用"}
+              </Button>
+            </div>
+          </div>
+          <div>
+            <label className="text-sm font-medium mb-1 block">访问令牌</label>
+            <div className="flex gap-2">
+              <Input readOnly value={info?.token ?? ""} className="font-mono text-xs" />
+              <Button
+                variant="outline"
+                size="sm"
+                onClick={() => {
+                  if (!info) return;
+                  navigator.clipboard.writeText(info.token);
+                  setCopied(true);
+                  setTimeout(() => setCopied(false), 2000);
+                }}
+              >
+                {copied ? <CheckCircle2 className="w-4 h-4 text-green-600" /> : <Copy className="w-4 h-4" />}
+              </Button>
+              <Button variant="outline" size="sm" onClick={regenerate} title="重新生成">
+                <RefreshCw className="w-4 h-4" />
+              </Button>
+            </div>
+          </div>
+        </div>
+
+        <div className="text-sm">
+          {info?.running ? (
+            <Badge variant="success">运行中：{info.url}</Badge>
+          ) : (
+            <Badge variant="outline">未运行</Badge>
+          )}
+          {error && <p className="text-red-600 mt-2">{error}</p>}
+        </div>
+      </div>
+
+      <div className="p-4 rounded-lg bg-muted/30 border">
+        <p className="text-sm text-muted-foreground mb-4">
+          请求需携带 <code>Authorization: Bearer &lt;令牌&gt;</code>，请求与响应均为 JSON。
+        </p>
+        <div className="space-y-3">
+          <ApiEndpointDoc method="GET" endpoint="/api/tasks?listId=&completed=&priority=&status=&q=" description="查询任务" />
+          <ApiEndpointDoc method="GET" endpoint="/api/tasks/search?q=" description="搜索任务" />
+          <ApiEndpointDoc method="POST" endpoint="/api/tasks" description="创建任务" />
+          <ApiEndpointDoc method="GET" endpoint="/api/tasks/:id" description="获取任务" />
+          <ApiEndpointDoc method="PUT" endpoint="/api/tasks/:id" description="更新任务（部分字段）" />
+          <ApiEndpointDoc method="POST" endpoint="/api/tasks/:id/complete" description="完成任务" />
+          <ApiEndpointDoc method="DELETE" endpoint="/api/tasks/:id" description="删除任务" />
+          <ApiEndpointDoc method="GET" endpoint="/api/lists" description="获取清单" />
+          <ApiEndpointDoc method="POST" endpoint="/api/lists" description="创建清单" />
+          <ApiEndpointDoc method="PUT" endpoint="/api/lists/:id" description="更新清单" />
+          <ApiEndpointDoc method="DELETE" endpoint="/api/lists/:id" description="删除清单" />
+        </div>
+      </div>
+    </div>
+  );
+}
+
 function ApiEndpointDoc({ method, endpoint, description }: { method: string, endpoint: string, description: string }) {
   const methodColors: Record<string, string> = {
     GET: "bg-blue-100 text-blue-700 border-blue-200",
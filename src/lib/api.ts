@@ -1,5 +1,198 @@
 import { invoke } from "@tauri-apps/api/core";
-import { Task, TaskList } from "../types";
+import { Priority, SubTask, Task, TaskList, TaskStatus } from "../types";
+
+// 任务查询条件（对应后端 query_tasks，未设置的条件不参与筛选）
+export type TaskSortField = "order" | "dueDate" | "priority" | "createdAt" | "updatedAt" | "title";
+
+export interface TaskQuery {
+  listId?: string; // "all" 表示不限清单
+  categoryId?: string;
+  tags?: string[]; // 标签 ID，包含任意一个即匹配
+  priorities?: Priority[];
+  statuses?: TaskStatus[];
+  dueFrom?: string; // ISO 时间，包含
+  dueTo?: string; // ISO 时间，不包含
+  hasDueDate?: boolean;
+  completed?: boolean;
+  blocked?: boolean; // 仅返回被阻塞（true）或未被阻塞（false）的任务
+  deleted?: boolean; // true 时只返回回收站中的任务
+  text?: string;
+  sort?: TaskSortField;
+  descending?: boolean;
+  limit?: number;
+  offset?: number;
+}
+
+export interface TaskPage {
+  tasks: Task[];
+  total: number;
+}
+
+// 全文搜索结果（title / snippet 为已转义的 HTML，命中部分以 <mark> 包裹）
+export interface SearchResult {
+  task: Task;
+  score: number;
+  title: string;
+  snippet?: string;
+}
+
+// 任务历史（field 为 created 或字段名：title、status、priority、dueDate、listId、completed、deleted）
+export interface TaskEvent {
+  id: number;
+  taskId: string;
+  field: string;
+  oldValue?: string;
+  newValue?: string;
+  createdAt: string;
+}
+
+// 批量修改（updates 按部分字段更新任务，ID 不存在时创建；order 依次设置排序；deletes 连同子任务永久删除）
+export interface TaskBatch {
+  updates?: Array<{ id: string; changes: Partial<Task> }>;
+  order?: string[];
+  deletes?: string[];
+}
+
+// 可撤销的操作（label 为 save、delete、reorder、batch、move、complete、trash、restore）
+export interface UndoEntry {
+  id: number;
+  label: string;
+  taskIds: string[];
+  createdAt: string;
+}
+
+// 撤销 / 重做栈状态
+export interface UndoState {
+  undoCount: number;
+  redoCount: number;
+  nextUndo?: UndoEntry;
+  nextRedo?: UndoEntry;
+}
+
+// 任务树节点
+export interface TaskNode {
+  task: Task;
+  children: TaskNode[];
+}
+
+// 任务依赖：taskId 被 blockedBy 阻塞
+export interface TaskDependency {
+  taskId: string;
+  blockedBy: string;
+  createdAt: string;
+}
+
+// 计时记录（时间为 Unix 时间戳，单位秒；endedAt 为空表示正在计时）
+export interface TimeEntry {
+  id: string;
+  taskId: string;
+  startedAt: number;
+  endedAt?: number;
+  note?: string;
+  manual: boolean;
+  createdAt: string;
+}
+
+// 计时汇总（id 为任务 ID 或清单 ID，seconds 包含正在计时的记录）
+export type TimeGroup = "task" | "list";
+
+export interface TimeTotal {
+  id: string;
+  seconds: number;
+  entries: number;
+}
+
+// 番茄钟（时长设置单位为分钟，remaining / duration 单位为秒）
+export type PomodoroPhase = "work" | "shortBreak" | "longBreak";
+
+export interface PomodoroSettings {
+  workMinutes: number;
+  shortBreakMinutes: number;
+  longBreakMinutes: number;
+  longBreakInterval: number; // 每完成几个番茄进入一次长休息
+}
+
+export interface PomodoroStatus {
+  taskId: string;
+  phase: PomodoroPhase;
+  remaining: number;
+  duration: number;
+  paused: boolean;
+  completed: number;
+}
+
+export interface PomodoroRecord {
+  id: string;
+  taskId: string;
+  startedAt: number;
+  endedAt: number;
+}
+
+// 预估对比（时长单位为分钟；ratio = 实际 / 预估，大于 1 表示低估）
+export interface EstimateRow {
+  key: string; // 清单 ID、标签 ID 或 ISO 周（例如 2026-W42）
+  name?: string; // 标签名称
+  tasks: number;
+  estimatedMinutes: number;
+  actualMinutes: number;
+  storyPoints: number;
+  underestimated: number; // 实际用时超过预估的任务数
+  ratio?: number;
+}
+
+// 只统计已完成且填写了预估时长的任务
+export interface EstimateReport {
+  total: EstimateRow;
+  byList: EstimateRow[];
+  byTag: EstimateRow[];
+  byWeek: EstimateRow[];
+}
+
+// 效率统计条件（时间为 ISO 字符串，包含 from、不包含 to，默认最近 30 天）
+export interface StatisticsQuery {
+  from?: string;
+  to?: string;
+  utcOffsetMinutes?: number; // 按天、周统计使用的时区，默认取本地时区
+  listId?: string;
+}
+
+export interface PeriodCount {
+  period: string; // 日期（2026-10-19）或 ISO 周（2026-W42）
+  completed: number;
+}
+
+export interface StatisticsGroup {
+  key: string; // 优先级、清单 ID 或标签 ID
+  name?: string;
+  completed: number;
+  averageCompletionHours?: number;
+}
+
+export interface Statistics {
+  from: string;
+  to: string;
+  created: number;
+  completed: number;
+  completedPerDay: PeriodCount[];
+  completedPerWeek: PeriodCount[];
+  averageCompletionHours?: number;
+  due: number;
+  overdue: number;
+  overdueRate?: number;
+  currentStreak: number;
+  longestStreak: number;
+  byPriority: StatisticsGroup[];
+  byList: StatisticsGroup[];
+  byTag: StatisticsGroup[];
+}
+
+// 子任务完成进度
+export interface TaskProgress {
+  taskId: string;
+  total: number;
+  completed: number;
+  percent: number;
+}
 
 // 通知设置类型
 export interface NotificationSettings {
@@ -7,9 +200,25 @@ export interface NotificationSettings {
   wechat_webhook?: string;
 }
 
+// 备份目标
+export type BackupTargetKind = "webdav" | "local" | "s3";
+
+// S3 兼容对象存储设置
+export interface S3Settings {
+  endpoint?: string;
+  region: string;
+  bucket: string;
+  accessKey: string;
+  secretKey: string;
+  pathStyle: boolean;
+}
+
 // 备份设置
 export interface WebDavSettings {
   enabled: boolean;
+  target?: BackupTargetKind;
+  localPath?: string;
+  s3?: S3Settings;
   url: string;
   username: string;
   password: string;
@@ -17,6 +226,57 @@ export interface WebDavSettings {
   autoBackup: boolean;
   maxBackups?: number;
   simpleMode: boolean;
+  encryptionPassphrase?: string;
+  syncEnabled?: boolean;
+}
+
+// 同步结果
+export interface SyncSummary {
+  pulled: number;
+  deletedLocal: number;
+  pushed: number;
+}
+
+// 备份文件
+export interface BackupEntry {
+  name: string;
+  size?: number;
+  modified?: string;
+}
+
+// 同步/恢复冲突：同时保留本地与远端版本
+export interface TaskConflict {
+  id: string;
+  taskId: string;
+  source: "webdav" | "rest" | "restore";
+  local: Task;
+  remote: Task;
+  winner: "local" | "remote";
+  detectedAt: string;
+  resolvedAt?: string;
+  resolution?: string;
+}
+
+// 冲突字段差异
+export interface FieldDiff {
+  field: string;
+  local: unknown;
+  remote: unknown;
+}
+
+export type ConflictResolution = "keepLocal" | "keepRemote" | "merge";
+
+// 本地 HTTP API 设置（仅监听 127.0.0.1）
+export interface LocalApiSettings {
+  enabled: boolean;
+  port: number;
+}
+
+export interface LocalApiInfo {
+  settings: LocalApiSettings;
+  running: boolean;
+  url: string;
+  token: string;
 }
 
 // 检查是否在 Tauri 环境中（更可靠的检测方法）
@@ -29,6 +289,42 @@ export const isTauri = () => {
   }
 };
 
+// 将任务的部分字段转换为后端结构（数组序列化为 JSON 字符串，日期转换为 ISO 字符串）
+const changesForBackend = (changes: Partial<Task>): Record<string, unknown> => {
+  const result: Record<string, unknown> = { ...changes };
+  for (const key of ["tags", "subTasks", "reminders"] as const) {
+    if (changes[key] !== undefined) {
+      result[key] = JSON.stringify(changes[key]);
+    }
+  }
+  for (const key of ["createdAt", "updatedAt", "dueDate", "deletedAt", "completedAt"] as const) {
+    const value = changes[key];
+    if (value instanceof Date) {
+      result[key] = value.toISOString();
+    }
+  }
+  return result;
+};
+
+// 将后端返回的任务转换为前端结构
+const fromBackendTask = (task: any): Task => ({
+  ...task,
+  // 将后端返回的 JSON 字符串解析回数组
+  tags: JSON.parse(task.tags || "[]"),
+  subTasks: JSON.parse(task.subTasks || "[]"),
+  reminders: JSON.parse(task.reminders || "[]"),
+  categoryId: task.categoryId ?? undefined,
+  parentId: task.parentId ?? undefined,
+  estimateMinutes: task.estimateMinutes ?? undefined,
+  storyPoints: task.storyPoints ?? undefined,
+  // 将 ISO 字符串转换回 Date 对象
+  createdAt: new Date(task.createdAt),
+  updatedAt: new Date(task.updatedAt),
+  dueDate: task.dueDate ? new Date(task.dueDate) : undefined,
+  deletedAt: task.deletedAt ? new Date(task.deletedAt) : undefined,
+  completedAt: task.completedAt ? new Date(task.completedAt) : undefined,
+});
+
 // 数据库 API
 export const databaseApi = {
   // 初始化数据库
@@ -50,16 +346,32 @@ export const databaseApi = {
     }
 
     const tasks = await invoke<any[]>("get_tasks");
-    // 将后端返回的 JSON 字符串解析回数组
-    return tasks.map((task: any) => ({
-      ...task,
-      tags: JSON.parse(task.tags || "[]"),
-      subTasks: JSON.parse(task.subTasks || "[]"),
-      reminders: JSON.parse(task.reminders || "[]"),
-      // 将 ISO 字符串转换回 Date 对象
-      createdAt: new Date(task.createdAt),
-      updatedAt: new Date(task.updatedAt),
-      dueDate: task.dueDate ? new Date(task.dueDate) : undefined,
+    return tasks.map(fromBackendTask);
+  },
+
+  // 按条件查询任务（筛选、排序与分页由后端完成）
+  async queryTasks(query: TaskQuery): Promise<TaskPage> {
+    if (!isTauri()) {
+      // 非 Tauri 环境不支持查询，返回全部任务
+      const tasks = await this.getTasks();
+      return { tasks, total: tasks.length };
+    }
+
+    const page = await invoke<{ tasks: any[]; total: number }>("query_tasks", { query });
+    return { tasks: page.tasks.map(fromBackendTask), total: page.total };
+  },
+
+  // 全文搜索任务（按相关度排序）
+  async searchTasks(query: string, limit?: number): Promise<SearchResult[]> {
+    if (!isTauri()) {
+      return [];
+    }
+
+    const results = await invoke<any[]>("search_tasks", { query, limit });
+    return results.map((result) => ({
+      ...result,
+      task: fromBackendTask(result.task),
+      snippet: result.snippet ?? undefined,
     }));
   },
 
@@ -88,6 +400,13 @@ export const databaseApi = {
       createdAt: task.createdAt instanceof Date ? task.createdAt.toISOString() : task.createdAt,
       updatedAt: task.updatedAt instanceof Date ? task.updatedAt.toISOString() : task.updatedAt,
       dueDate: task.dueDate instanceof Date ? task.dueDate.toISOString() : task.dueDate,
+      deletedAt: task.deletedAt instanceof Date ? task.deletedAt.toISOString() : task.deletedAt,
+      // 重新打开的任务不带完成时间，完成时由后端记录
+      completedAt: !task.completed
+        ? undefined
+        : task.completedAt instanceof Date
+          ? task.completedAt.toISOString()
+          : task.completedAt,
     };
 
     console.log("[API] Saving task:", taskForBackend);
@@ -114,6 +433,47 @@ export const databaseApi = {
     return invoke("delete_task", { id });
   },
 
+  // 批量修改任务（单个事务内完成，完成后统一检查一次提醒）
+  async batchUpdateTasks(batch: TaskBatch): Promise<Task[]> {
+    if (!isTauri()) {
+      // 在非 Tauri 环境中，直接修改 localStorage
+      const deletes = new Set(batch.deletes ?? []);
+      let tasks = (await this.getTasks()).filter((t) => !deletes.has(t.id));
+      for (const { id, changes } of batch.updates ?? []) {
+        const index = tasks.findIndex((t) => t.id === id);
+        if (index >= 0) {
+          tasks[index] = { ...tasks[index], ...changes, id, updatedAt: new Date() };
+        } else {
+          tasks.push({ ...(changes as Task), id });
+        }
+      }
+      (batch.order ?? []).forEach((id, order) => {
+        tasks = tasks.map((t) => (t.id === id ? { ...t, order } : t));
+      });
+      localStorage.setItem("tasks", JSON.stringify(tasks));
+      const changed = new Set([...(batch.updates ?? []).map((u) => u.id), ...(batch.order ?? [])]);
+      return tasks.filter((t) => changed.has(t.id));
+    }
+
+    const tasks = await invoke<any[]>("batch_update_tasks", {
+      batch: {
+        updates: (batch.updates ?? []).map(({ id, changes }) => ({ id, changes: changesForBackend(changes) })),
+        order: batch.order ?? [],
+        deletes: batch.deletes ?? [],
+      },
+    });
+    await this.checkDueReminders();
+    return tasks.map(fromBackendTask);
+  },
+
+  // 按给定顺序重排任务（拖拽排序）
+  async reorderTasks(ids: string[]): Promise<void> {
+    if (!isTauri()) {
+      return;
+    }
+    await invoke("reorder_tasks", { ids });
+  },
+
   // 获取所有清单
   async getLists(): Promise<TaskList[]> {
     if (!isTauri()) {
@@ -277,10 +637,330 @@ export const webdavApi = {
       return "web-backup-placeholder";
     }
   },
-  async restore(filename: string): Promise<void> {
+  async listBackups(): Promise<BackupEntry[]> {
+    if (isTauri()) {
+      return invoke("list_backups");
+    }
+    return [];
+  },
+  async sync(): Promise<SyncSummary | null> {
+    if (isTauri()) {
+      return invoke("sync_webdav");
+    }
+    return null;
+  },
+  // components 可选："database" | "notificationSettings" | "webdavSettings"，为空时全部恢复
+  async restore(filename: string, components?: string[]): Promise<void> {
+    if (isTauri()) {
+      return invoke("restore_from_webdav", { filename, components });
+    }
+  },
+};
+
+// 本地 HTTP API（供脚本与编辑器插件调用）
+export const localApi = {
+  async getInfo(): Promise<LocalApiInfo | null> {
+    if (isTauri()) {
+      return invoke("get_local_api_info");
+    }
+    return null;
+  },
+  async saveSettings(settings: LocalApiSettings): Promise<LocalApiInfo | null> {
+    if (isTauri()) {
+      return invoke("save_local_api_settings", { settings });
+    }
+    return null;
+  },
+  async regenerateToken(): Promise<LocalApiInfo | null> {
+    if (isTauri()) {
+      return invoke("regenerate_local_api_token");
+    }
+    return null;
+  },
+};
+
+// 任务层级 API（移动、完成、回收站操作会作用于整棵子树，返回被修改的任务）
+const fromBackendNode = (node: any): TaskNode => ({
+  task: fromBackendTask(node.task),
+  children: node.children.map(fromBackendNode),
+});
+
+export const hierarchyApi = {
+  async tree(rootId?: string): Promise<TaskNode[]> {
+    if (isTauri()) {
+      const nodes = await invoke<any[]>("get_task_tree", { rootId });
+      return nodes.map(fromBackendNode);
+    }
+    return [];
+  },
+  async move(id: string, parentId?: string, listId?: string): Promise<Task[]> {
+    if (isTauri()) {
+      const tasks = await invoke<any[]>("move_task", { id, parentId, listId });
+      return tasks.map(fromBackendTask);
+    }
+    return [];
+  },
+  async setCompleted(id: string, completed: boolean): Promise<Task[]> {
+    if (isTauri()) {
+      const tasks = await invoke<any[]>("set_task_completed", { id, completed });
+      return tasks.map(fromBackendTask);
+    }
+    return [];
+  },
+  async trash(id: string): Promise<Task[]> {
+    if (isTauri()) {
+      const tasks = await invoke<any[]>("trash_task", { id });
+      return tasks.map(fromBackendTask);
+    }
+    return [];
+  },
+  async restore(id: string): Promise<Task[]> {
+    if (isTauri()) {
+      const tasks = await invoke<any[]>("restore_task", { id });
+      return tasks.map(fromBackendTask);
+    }
+    return [];
+  },
+};
+
+// 撤销 / 重做 API（后端完成后推送 tasks-changed，前端重新加载任务）
+export const undoApi = {
+  async undo(steps = 1): Promise<UndoEntry[]> {
+    if (isTauri()) {
+      return invoke("undo", { steps });
+    }
+    return [];
+  },
+  async redo(steps = 1): Promise<UndoEntry[]> {
+    if (isTauri()) {
+      return invoke("redo", { steps });
+    }
+    return [];
+  },
+  async state(): Promise<UndoState | null> {
+    if (isTauri()) {
+      return invoke("get_undo_state");
+    }
+    return null;
+  },
+};
+
+// 任务历史 API
+export const historyApi = {
+  async timeline(taskId: string): Promise<TaskEvent[]> {
+    if (isTauri()) {
+      return invoke("get_task_events", { taskId });
+    }
+    return [];
+  },
+};
+
+// 任务依赖 API（添加会形成环的依赖时后端返回错误）
+export const dependencyApi = {
+  async list(taskId?: string): Promise<TaskDependency[]> {
+    if (isTauri()) {
+      return invoke("get_task_dependencies", { taskId });
+    }
+    return [];
+  },
+  async add(taskId: string, blockedBy: string): Promise<TaskDependency | null> {
+    if (isTauri()) {
+      return invoke("add_task_dependency", { taskId, blockedBy });
+    }
+    return null;
+  },
+  async remove(taskId: string, blockedBy: string): Promise<boolean> {
+    if (isTauri()) {
+      return invoke("remove_task_dependency", { taskId, blockedBy });
+    }
+    return false;
+  },
+};
+
+// 统计 API
+export const statisticsApi = {
+  async get(query: StatisticsQuery = {}): Promise<Statistics | null> {
+    if (isTauri()) {
+      return invoke("get_statistics", {
+        query: { utcOffsetMinutes: -new Date().getTimezoneOffset(), ...query },
+      });
+    }
+    return null;
+  },
+};
+
+// 计时 API（同一时间只有一个计时，开始新计时会停止正在进行的计时；托盘停止计时后会触发 timer-changed 事件）
+export const timeApi = {
+  async entries(taskId?: string): Promise<TimeEntry[]> {
+    if (isTauri()) {
+      return invoke("get_time_entries", { taskId });
+    }
+    return [];
+  },
+  async running(): Promise<TimeEntry | null> {
+    if (isTauri()) {
+      return invoke("get_running_timer");
+    }
+    return null;
+  },
+  async start(taskId: string): Promise<TimeEntry | null> {
+    if (isTauri()) {
+      return invoke("start_timer", { taskId });
+    }
+    return null;
+  },
+  async stop(): Promise<TimeEntry | null> {
+    if (isTauri()) {
+      return invoke("stop_timer");
+    }
+    return null;
+  },
+  async addManual(taskId: string, startedAt: Date, endedAt: Date, note?: string): Promise<TimeEntry | null> {
+    if (isTauri()) {
+      return invoke("add_manual_entry", {
+        taskId,
+        startedAt: Math.floor(startedAt.getTime() / 1000),
+        endedAt: Math.floor(endedAt.getTime() / 1000),
+        note,
+      });
+    }
+    return null;
+  },
+  async totals(group: TimeGroup = "task"): Promise<TimeTotal[]> {
+    if (isTauri()) {
+      return invoke("get_time_totals", { group });
+    }
+    return [];
+  },
+  async estimateReport(): Promise<EstimateReport | null> {
+    if (isTauri()) {
+      return invoke("get_estimate_report");
+    }
+    return null;
+  },
+};
+
+// 番茄钟 API（后端每秒推送 pomodoro-tick，阶段切换时推送 pomodoro-phase-changed，停止时推送 pomodoro-stopped）
+export const pomodoroApi = {
+  async loadSettings(): Promise<PomodoroSettings | null> {
+    if (isTauri()) {
+      return invoke("load_pomodoro_settings");
+    }
+    return null;
+  },
+  async saveSettings(settings: PomodoroSettings): Promise<void> {
+    if (isTauri()) {
+      await invoke("save_pomodoro_settings", { settings });
+    }
+  },
+  async start(taskId: string): Promise<PomodoroStatus | null> {
+    if (isTauri()) {
+      return invoke("start_pomodoro", { taskId });
+    }
+    return null;
+  },
+  async pause(): Promise<PomodoroStatus | null> {
+    if (isTauri()) {
+      return invoke("pause_pomodoro");
+    }
+    return null;
+  },
+  async resume(): Promise<PomodoroStatus | null> {
+    if (isTauri()) {
+      return invoke("resume_pomodoro");
+    }
+    return null;
+  },
+  async skip(): Promise<PomodoroStatus | null> {
+    if (isTauri()) {
+      return invoke("skip_pomodoro_phase");
+    }
+    return null;
+  },
+  async stop(): Promise<void> {
+    if (isTauri()) {
+      await invoke("stop_pomodoro");
+    }
+  },
+  async status(): Promise<PomodoroStatus | null> {
+    if (isTauri()) {
+      return invoke("get_pomodoro_status");
+    }
+    return null;
+  },
+  async records(taskId?: string): Promise<PomodoroRecord[]> {
+    if (isTauri()) {
+      return invoke("get_pomodoros", { taskId });
+    }
+    return [];
+  },
+};
+
+// 子任务 API
+export const subtaskApi = {
+  async list(taskId: string): Promise<SubTask[]> {
+    if (isTauri()) {
+      return invoke("get_subtasks", { taskId });
+    }
+    return [];
+  },
+  async add(taskId: string, title: string, dueDate?: string): Promise<SubTask | null> {
+    if (isTauri()) {
+      return invoke("add_subtask", { taskId, title, dueDate });
+    }
+    return null;
+  },
+  async toggle(id: string): Promise<SubTask | null> {
+    if (isTauri()) {
+      return invoke("toggle_subtask", { id });
+    }
+    return null;
+  },
+  async reorder(taskId: string, ids: string[]): Promise<SubTask[]> {
+    if (isTauri()) {
+      return invoke("reorder_subtasks", { taskId, ids });
+    }
+    return [];
+  },
+  // 提升为独立任务，返回新任务
+  async promote(id: string): Promise<Task | null> {
+    if (isTauri()) {
+      return fromBackendTask(await invoke("promote_subtask", { id }));
+    }
+    return null;
+  },
+  async progress(): Promise<TaskProgress[]> {
+    if (isTauri()) {
+      return invoke("get_task_progress");
+    }
+    return [];
+  },
+};
+
+// 同步冲突 API
+export const conflictApi = {
+  async list(includeResolved = false): Promise<TaskConflict[]> {
+    if (isTauri()) {
+      return invoke("list_task_conflicts", { includeResolved });
+    }
+    return [];
+  },
+  async diff(id: string): Promise<FieldDiff[]> {
+    if (isTauri()) {
+      return invoke("get_task_conflict_diff", { id });
+    }
+    return [];
+  },
+  // fields 仅在 merge 时使用：字段名 -> "local" | "remote"，未指定的字段保留本地
+  async resolve(
+    id: string,
+    resolution: ConflictResolution,
+    fields?: Record<string, "local" | "remote">
+  ): Promise<Task | null> {
     if (isTauri()) {
-      return invoke("restore_from_webdav", { filename });
+      return invoke("resolve_task_conflict", { id, resolution, fields });
     }
+    return null;
   },
 };
 
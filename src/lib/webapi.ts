@@ -1,4 +1,6 @@
+import { invoke } from "@tauri-apps/api/core";
 import { Task, TaskList } from "../types";
+import { isTauri, type TaskBatch } from "./api";
 
 // API 配置
 export interface ApiConfig {
@@ -130,13 +132,11 @@ class WebApiService {
     });
   }
 
-  // 批量操作
-  async batchUpdateTasks(
-    updates: Array<{ id: string; changes: Partial<Task> }>
-  ): Promise<ApiResponse<Task[]>> {
+  // 批量操作（与本地 API 一致：更新或创建、重排与删除）
+  async batchUpdateTasks(batch: TaskBatch): Promise<ApiResponse<Task[]>> {
     return this.request<Task[]>("/api/tasks/batch", {
       method: "POST",
-      body: JSON.stringify({ updates }),
+      body: JSON.stringify(batch),
     });
   }
 
@@ -177,11 +177,6 @@ class WebApiService {
 
   // ========== 同步 API ==========
 
-  // 获取本地更改
-  async getLocalChanges(): Promise<ApiResponse<any>> {
-    return this.request<any>("/api/sync/changes");
-  }
-
   // 上传本地更改
   async uploadChanges(changes: any): Promise<ApiResponse<any>> {
     return this.request<any>("/api/sync/upload", {
@@ -259,6 +254,25 @@ export class SyncManager {
 
   // 手动同步
   async sync() {
+    // 桌面端由 Rust 后端负责增量同步（窗口隐藏时也会定期运行）
+    if (isTauri()) {
+      try {
+        const config = webApiService.getConfig();
+        if (config) {
+          await invoke("save_api_sync_settings", {
+            settings: { enabled: true, baseUrl: config.baseUrl, apiKey: config.apiKey },
+          });
+        }
+        const summary = await invoke("rest_sync_now");
+        console.log("Sync finished:", summary);
+        this.lastSync = new Date().toISOString();
+        localStorage.setItem("lastSync", this.lastSync);
+      } catch (error) {
+        console.error("Sync failed:", error);
+      }
+      return;
+    }
+
     if (!webApiService.getConfig()) {
       console.log("API not configured, skipping sync");
       return;
@@ -1,7 +1,7 @@
 import { create } from "zustand";
 import { persist } from "zustand/middleware";
 import { Task, TaskList, Category, Priority, TaskStatus, ViewMode, Tag, MainView } from "../types";
-import { databaseApi } from "../lib/api";
+import { databaseApi, type TaskBatch } from "../lib/api";
 
 interface TodoStore {
   // 状态
@@ -29,6 +29,8 @@ interface TodoStore {
   permanentlyDeleteTask: (id: string) => void; // 永久删除
   toggleTaskComplete: (id: string) => void;
   reorderTasks: (tasks: Task[]) => void;
+  applyTaskBatch: (batch: TaskBatch) => void; // 批量修改（先更新本地状态，再一次性保存到后端，可撤销）
+  loadTasks: () => Promise<void>; // 从后端重新加载（撤销、重做、同步等后端修改之后）
 
   // 操作 - 清单
   addList: (list: Omit<TaskList, "id" | "createdAt">) => void;
@@ -188,6 +190,15 @@ export const useTodoStore = create<TodoStore>()(
         }));
       },
 
+      loadTasks: async () => {
+        try {
+          const tasks = await databaseApi.getTasks();
+          set({ tasks });
+        } catch (error) {
+          console.error("[Store] Failed to load tasks from backend:", error);
+        }
+      },
+
       permanentlyDeleteTask: (id) => {
         set((state) => ({
           tasks: state.tasks.filter((task) => task.id !== id),
@@ -209,7 +220,28 @@ export const useTodoStore = create<TodoStore>()(
       },
 
       reorderTasks: (tasks) => {
-        set({ tasks });
+        get().applyTaskBatch({ order: tasks.map((t) => t.id) });
+      },
+
+      applyTaskBatch: (batch) => {
+        const order = new Map((batch.order ?? []).map((id, index) => [id, index]));
+        const updates = new Map((batch.updates ?? []).map(({ id, changes }) => [id, changes]));
+        const deletes = new Set(batch.deletes ?? []);
+        set((state) => ({
+          tasks: state.tasks
+            .filter((task) => !deletes.has(task.id))
+            .map((task) => {
+              const changes = updates.get(task.id);
+              const index = order.get(task.id);
+              if (!changes && index === undefined) return task;
+              return { ...task, ...changes, order: index ?? task.order, updatedAt: new Date() };
+            }),
+        }));
+        // 单次后端调用（单个事务、一条撤销记录）；失败时从后端重新加载
+        databaseApi.batchUpdateTasks(batch).catch((error) => {
+          console.error("[Store] Failed to save task batch to backend:", error);
+          get().loadTasks();
+        });
       },
 
       // 清单操作
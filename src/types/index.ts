@@ -25,6 +25,9 @@ export interface SubTask {
   id: string;
   title: string;
   completed: boolean;
+  completedAt?: string; // 完成时间（由后端记录）
+  dueDate?: string;
+  order?: number;
 }
 
 // 提醒设置
@@ -54,6 +57,11 @@ export interface Task {
   order: number;
   deleted?: boolean; // 是否已删除（软删除）
   deletedAt?: Date; // 删除时间
+  parentId?: string; // 父任务（为空时是顶层任务）
+  estimateMinutes?: number; // 预估时长（分钟）
+  storyPoints?: number; // 故事点
+  completedAt?: Date; // 完成时间（由后端在完成时记录）
+  blocked?: boolean; // 是否被未完成的任务阻塞（由后端计算，只读）
 }
 
 // 清单